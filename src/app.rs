@@ -1,24 +1,69 @@
-use core::f32;
-
 use egui::{
-    CentralPanel, Color32, Id, Key, Response, RichText, ScrollArea, SidePanel, TextEdit,
+    Button, CentralPanel, Color32, Key, LayerId, Modifiers, Rect, RichText, ScrollArea, SidePanel,
     TopBottomPanel, Ui,
 };
-use egui_extras::syntax_highlighting::{highlight, CodeTheme};
-//use egui_extras::syntax_highlighting::{highlight, CodeTheme};
 
-use crate::Runtime;
+use crate::{
+    completion::{self, Topic},
+    editor::{self, code_editor_with_autoindent},
+    fonts::MAX_PROJECT_FONT_SIZE,
+    history::{self, History},
+    hud::PerfHud,
+    playback::{self, InputRecording, Playback},
+    simulation::Simulation,
+    ProjectFont, Runtime, RuntimePolicy,
+};
 
 #[derive(serde::Deserialize, serde::Serialize, Default)]
 struct Project {
     code: String,
     run_mode: RunMode,
+    #[serde(default)]
+    show_perf_hud: bool,
+    #[serde(default)]
+    policy: RuntimePolicy,
+    #[serde(default)]
+    simulation: Simulation,
+    #[serde(default)]
+    fonts: Vec<ProjectFont>,
+    #[serde(default)]
+    show_docs: bool,
+    #[serde(default)]
+    history: History,
 }
 
+const CODE_EDITOR_ID: &str = "CodeEditor";
+
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 pub struct TemplateApp {
     project: Project,
     runtime: crate::Runtime,
+    perf_hud: PerfHud,
+    /// Policy being edited in the sandbox window, if it is open
+    policy_draft: Option<RuntimePolicy>,
+    playback: Playback,
+    /// Where the script's GUI was shown last frame, for telling its input from the host's
+    script_area: Rect,
+    /// Whether keyboard input went to the script last frame, rather than to the host's UI
+    keyboard_to_script: bool,
+    /// Whether the pointer was over the script last frame, so leaving it is input for it
+    pointer_over_script: bool,
+    /// The last finished or loaded input recording
+    recording: Option<InputRecording>,
+    recording_path: String,
+    /// Result of the last recording save or load
+    recording_status: Option<String>,
+    show_fonts: bool,
+    /// Name and file for the next font to add in the fonts window
+    font_name: String,
+    font_path: String,
+    /// Result of the last font change
+    font_status: Option<String>,
+    /// Where the editor's cursor was when the docs panel last looked
+    docs_cursor: Option<usize>,
+    /// What the docs panel shows, and the help for it if there is any
+    docs: Option<(Topic, Option<String>)>,
+    history_browser: history::Browser,
 }
 
 impl TemplateApp {
@@ -29,26 +74,348 @@ impl TemplateApp {
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        let project: Project = cc.storage.and_then(|storage| eframe::get_value(storage, eframe::APP_KEY)).unwrap_or_default();
+        let mut project: Project = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+            .unwrap_or_default();
+
+        project.history.sync(&project.code, unix_time());
 
-        let mut runtime = Runtime::new();
+        let mut runtime = Runtime::new(project.policy.clone());
         runtime.load(project.code.clone());
+        let font_status = runtime
+            .load_fonts(&project.fonts, &cc.egui_ctx)
+            .err()
+            .map(|e| format!("Some project fonts could not be loaded:\n{e}"));
 
         Self {
             project,
             runtime,
+            perf_hud: PerfHud::default(),
+            policy_draft: None,
+            playback: Playback::default(),
+            script_area: Rect::NOTHING,
+            keyboard_to_script: true,
+            pointer_over_script: false,
+            recording: None,
+            recording_path: "recording.json".to_owned(),
+            recording_status: None,
+            show_fonts: font_status.is_some(),
+            font_name: String::new(),
+            font_path: String::new(),
+            font_status,
+            docs_cursor: None,
+            docs: None,
+            history_browser: history::Browser::default(),
         }
     }
 }
 
+impl TemplateApp {
+    fn show_policy_window(&mut self, ctx: &egui::Context) {
+        let Some(draft) = &mut self.policy_draft else {
+            return;
+        };
+
+        let mut open = true;
+        let mut apply = false;
+        egui::Window::new("Sandbox policy")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                draft.show(ui);
+                ui.separator();
+                ui.add_enabled_ui(*draft != self.project.policy, |ui| {
+                    apply = ui.button("Apply (resets state)").clicked();
+                });
+            });
+
+        if apply {
+            self.project.policy = draft.clone();
+            self.runtime.set_policy(draft.clone());
+        }
+
+        if !open {
+            self.policy_draft = None;
+        }
+    }
+
+    fn show_fonts_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_fonts;
+        let mut changed = false;
+        egui::Window::new("Fonts").open(&mut open).show(ctx, |ui| {
+            ui.label("Fonts saved with the project. Use them by family name in scripts.");
+            let mut remove = None;
+            for (i, font) in self.project.fonts.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(&font.name);
+                    ui.weak(format!("{} KiB", font.data.len() / 1024));
+                    if ui.small_button("Remove").clicked() {
+                        remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = remove {
+                self.project.fonts.remove(i);
+                changed = true;
+            }
+
+            ui.separator();
+            egui::Grid::new("add_font").num_columns(2).show(ui, |ui| {
+                ui.label("Family name");
+                ui.text_edit_singleline(&mut self.font_name);
+                ui.end_row();
+                ui.label("TTF/OTF file");
+                ui.text_edit_singleline(&mut self.font_path);
+                ui.end_row();
+            });
+            let can_add = !self.font_name.is_empty() && !self.font_path.is_empty();
+            if ui.add_enabled(can_add, Button::new("Add")).clicked() {
+                match std::fs::read(&self.font_path) {
+                    Ok(data) if data.len() > MAX_PROJECT_FONT_SIZE => {
+                        self.font_status = Some(format!(
+                            "{} is {} MiB, fonts saved with the project can be at most {} MiB",
+                            self.font_path,
+                            data.len() / (1024 * 1024),
+                            MAX_PROJECT_FONT_SIZE / (1024 * 1024)
+                        ));
+                    }
+                    Ok(data) => {
+                        self.project
+                            .fonts
+                            .retain(|font| font.name != self.font_name);
+                        self.project.fonts.push(ProjectFont {
+                            name: std::mem::take(&mut self.font_name),
+                            data,
+                        });
+                        changed = true;
+                    }
+                    Err(e) => self.font_status = Some(format!("{}: {e}", self.font_path)),
+                }
+            }
+
+            if let Some(status) = &self.font_status {
+                ui.label(RichText::new(status).color(Color32::LIGHT_RED));
+            }
+        });
+        self.show_fonts = open;
+
+        if changed {
+            self.font_status = match self.runtime.load_fonts(&self.project.fonts, ctx) {
+                Ok(()) => None,
+                Err(e) => {
+                    // Keep only what loaded, so the project doesn't carry broken fonts
+                    let mut check = crate::fonts::FontRegistry::default();
+                    self.project
+                        .fonts
+                        .retain(|font| check.insert(&font.name, font.data.clone()).is_ok());
+                    Some(e)
+                }
+            };
+        }
+    }
+
+    /// Help for the name under the editor's cursor, kept when the cursor leaves it
+    fn show_docs_panel(&mut self, ctx: &egui::Context, code_changed: bool) {
+        let cursor = editor::cursor(ctx, CODE_EDITOR_ID.into(), &self.project.code);
+        if cursor.is_some() && (cursor != self.docs_cursor || code_changed) {
+            self.docs_cursor = cursor;
+            let globals = self.runtime.globals();
+            let topic = cursor.and_then(|c| completion::topic_at(&self.project.code, c, &globals));
+            if let Some(topic) = topic {
+                let help = self.runtime.help(&topic);
+                self.docs = Some((topic, help));
+            }
+        }
+
+        SidePanel::right("docs").show(ctx, |ui| {
+            ui.strong("Docs");
+            ScrollArea::vertical().show(ui, |ui| match &self.docs {
+                Some((_, Some(help))) => {
+                    ui.label(RichText::new(help).monospace());
+                }
+                Some((_, None)) => {
+                    ui.weak(
+                        "Nothing known about this name, it may only exist once the script runs",
+                    );
+                }
+                None => {
+                    ui.weak("Put the cursor on a name in the editor to see its documentation");
+                }
+            });
+        });
+    }
+
+    /// Undo and redo keys for the editor, handled before its text edit sees them. Returns
+    /// the code to switch to.
+    fn undo_redo(&mut self, ctx: &egui::Context) -> Option<String> {
+        let editor_id = egui::Id::from(CODE_EDITOR_ID);
+        let focused = ctx.memory(|memory| memory.focused());
+        // Other text fields keep their own undo
+        if focused.is_some_and(|id| id != editor_id) {
+            return None;
+        }
+        let (undo, redo) = ctx.input_mut(|i| {
+            // Without shift also matches shift, so check for it first
+            let redo = i.consume_key(Modifiers::COMMAND | Modifiers::SHIFT, Key::Z)
+                || i.consume_key(Modifiers::COMMAND, Key::Y);
+            (i.consume_key(Modifiers::COMMAND, Key::Z), redo)
+        });
+        let history = &mut self.project.history;
+        match (undo, redo) {
+            (true, _) => history.undo().map(str::to_owned),
+            (_, true) => history.redo().map(str::to_owned),
+            _ => None,
+        }
+    }
+
+    fn show_history_window(&mut self, ctx: &egui::Context, now: f64) -> Option<String> {
+        let mut open = self.history_browser.open;
+        let mut switch_to = None;
+        egui::Window::new("History")
+            .open(&mut open)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                switch_to = self.project.history.show(
+                    ui,
+                    &mut self.history_browser,
+                    &self.project.code,
+                    now,
+                );
+            });
+        self.history_browser.open = open;
+        switch_to
+    }
+
+    /// Whether this frame has input the script could react to: pointer input over its
+    /// area, leaving it or in a drag that started there, or keyboard input while one of
+    /// its widgets has focus
+    fn has_script_input(&mut self, ctx: &egui::Context) -> bool {
+        let (pointer, press_origin, keyboard, other) = ctx.input(|i| {
+            let is_keyboard = playback::is_keyboard_input;
+            let keyboard = i.events.iter().any(is_keyboard);
+            let other = i.events.iter().any(|event| !is_keyboard(event));
+            let pointer = &i.pointer;
+            (
+                pointer.latest_pos(),
+                pointer.press_origin(),
+                keyboard,
+                other,
+            )
+        });
+        let was_over_script = self.pointer_over_script;
+        let script_area = self.script_area;
+        self.pointer_over_script = pointer.is_some_and(|pos| over_script(script_area, ctx, pos));
+        let pointer_input = self.pointer_over_script
+            || was_over_script
+            || press_origin.is_some_and(|pos| over_script(script_area, ctx, pos));
+        (keyboard && self.keyboard_to_script) || (other && pointer_input)
+    }
+
+    fn show_input_menu(&mut self, ui: &mut Ui) {
+        match self.playback {
+            Playback::Idle => (),
+            Playback::Recording(_) => {
+                if ui.button("Stop recording").clicked() {
+                    self.recording = self.playback.stop();
+                    ui.close_menu();
+                }
+                return;
+            }
+            Playback::Replaying { .. } => {
+                ui.label("Replaying, press Esc to stop");
+                return;
+            }
+        }
+
+        if ui.button("Record").clicked() {
+            // Start from a clean slate so the replay can reproduce it
+            self.runtime.reset_state();
+            let script_size = egui::vec2(
+                self.script_area.width(),
+                ui.ctx().screen_rect().bottom() - self.script_area.top(),
+            );
+            self.playback.start_recording(script_size);
+            ui.close_menu();
+        }
+
+        let replay = ui.add_enabled(self.recording.is_some(), Button::new("Replay"));
+        if let (true, Some(recording)) = (replay.clicked(), &self.recording) {
+            self.runtime.reset_state();
+            self.playback.start_replay(recording.clone());
+            ui.close_menu();
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("File");
+            ui.text_edit_singleline(&mut self.recording_path);
+        });
+        ui.horizontal(|ui| {
+            let save = ui.add_enabled(self.recording.is_some(), Button::new("Save"));
+            if let (true, Some(recording)) = (save.clicked(), &self.recording) {
+                self.recording_status = Some(
+                    match std::fs::write(&self.recording_path, recording.to_json()) {
+                        Ok(()) => format!("Saved {}", self.recording_path),
+                        Err(e) => format!("Failed to save: {e}"),
+                    },
+                );
+            }
+
+            if ui.button("Load").clicked() {
+                let loaded = std::fs::read_to_string(&self.recording_path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|json| InputRecording::from_json(&json).map_err(|e| e.to_string()));
+                self.recording_status = Some(match loaded {
+                    Ok(recording) => {
+                        let status = format!(
+                            "Loaded {} frames from {}",
+                            recording.frames, self.recording_path
+                        );
+                        self.recording = Some(recording);
+                        status
+                    }
+                    Err(e) => format!("Failed to load: {e}"),
+                });
+            }
+        });
+
+        if let Some(status) = &self.recording_status {
+            ui.label(status);
+        }
+    }
+}
+
+/// Whether the script's area was at `pos` last frame, and not covered by a menu or window,
+/// which are on layers above the panels
+fn over_script(script_area: Rect, ctx: &egui::Context, pos: egui::Pos2) -> bool {
+    script_area.contains(pos) && ctx.layer_id_at(pos) == Some(LayerId::background())
+}
+
 impl eframe::App for TemplateApp {
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
         eframe::set_value(storage, eframe::APP_KEY, &self.project);
     }
 
+    fn raw_input_hook(&mut self, ctx: &egui::Context, raw_input: &mut egui::RawInput) {
+        let script_area = self.script_area;
+        self.playback.process_input(
+            raw_input,
+            script_area.min,
+            |pos| over_script(script_area, ctx, pos),
+            self.keyboard_to_script,
+        );
+    }
+
     /// Called each time the UI needs repainting, which may be many times per second.
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        self.perf_hud.begin_frame();
+
+        // Recordings advance one frame at a time, so keep the frames coming
+        if !self.playback.is_idle() {
+            ctx.request_repaint();
+        }
+
         // TOOD: Wait this is stupid on mobile
         let mut force_step = ctx.input(|r| r.key_pressed(Key::E) && r.modifiers.ctrl);
         let mut reset_state = ctx.input(|r| r.key_pressed(Key::R) && r.modifiers.ctrl);
@@ -58,17 +425,39 @@ impl eframe::App for TemplateApp {
             egui::menu::bar(ui, |ui| {
                 ui.menu_button("Project", |ui| {
                     force_save |= ui.button("Save (CTRL + S)").clicked();
+                    if ui.button("Sandbox policy...").clicked() {
+                        self.policy_draft = Some(self.project.policy.clone());
+                        ui.close_menu();
+                    }
+                    if ui.button("Fonts...").clicked() {
+                        self.show_fonts = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("History...").clicked() {
+                        self.history_browser.open = true;
+                        ui.close_menu();
+                    }
                 });
 
                 ui.menu_button("Run", |ui| {
                     ui.menu_button("Mode", |ui| self.project.run_mode.show(ui));
                     force_step |= ui.button("Step (CTRL + E)").clicked();
+                    if self.project.run_mode == RunMode::FixedTimestep {
+                        ui.separator();
+                        self.project.simulation.show_controls(ui);
+                    }
                 });
 
                 ui.menu_button("State", |ui| {
                     reset_state |= ui.button("Reset (CTRL + R)").clicked();
                 });
 
+                ui.menu_button("Input", |ui| self.show_input_menu(ui));
+
+                ui.menu_button("View", |ui| {
+                    ui.checkbox(&mut self.project.show_perf_hud, "Performance HUD");
+                    ui.checkbox(&mut self.project.show_docs, "Docs panel");
+                });
             });
         });
 
@@ -80,23 +469,53 @@ impl eframe::App for TemplateApp {
 
         if reset_state {
             self.runtime.reset_state();
+            self.project.simulation.reset();
         }
 
+        self.show_policy_window(ctx);
+        self.show_fonts_window(ctx);
+
+        let now = unix_time();
         let mut changed = false;
+        let undone = self.undo_redo(ctx);
+        let restored = self.show_history_window(ctx, now);
+        if let Some(code) = restored.or(undone) {
+            let cursor = history::edit_end(&self.project.code, &code);
+            editor::set_cursor(ctx, CODE_EDITOR_ID.into(), &code, cursor);
+            self.project.code = code;
+            changed = true;
+        }
+
+        let mut edited = false;
         SidePanel::left("leeft").show(ctx, |ui| {
+            edited |= editor::find_bar(ui, CODE_EDITOR_ID.into(), &mut self.project.code);
             ScrollArea::vertical().show(ui, |ui| {
-                changed |=
-                    code_editor_with_autoindent(ui, "CodeEditor".into(), &mut self.project.code, "py")
-                        .changed();
+                edited |= code_editor_with_autoindent(
+                    ui,
+                    CODE_EDITOR_ID.into(),
+                    &mut self.project.code,
+                    "py",
+                    || self.runtime.globals(),
+                )
+                .changed();
             });
         });
+        if edited {
+            self.project.history.edit(&self.project.code, now);
+            changed = true;
+        }
 
         if changed {
-            //let start = Instant::now();
             self.runtime.load(self.project.code.clone());
-            //println!("Load took {}s", start.elapsed().as_secs_f32());
         };
 
+        if self.project.show_docs {
+            self.show_docs_panel(ctx, changed);
+        }
+
+        let mut steps = 0;
+        self.runtime
+            .set_keep_output(self.project.run_mode == RunMode::ScriptDecides);
         let run_requested = match self.project.run_mode {
             RunMode::Continuous => {
                 ctx.request_repaint();
@@ -105,6 +524,31 @@ impl eframe::App for TemplateApp {
             RunMode::Manual => false,
             RunMode::OnScreenUpdate => true,
             RunMode::OnCodeChange => changed,
+            RunMode::ScriptDecides => {
+                let now = ctx.input(|i| i.time);
+                let has_input = self.has_script_input(ctx);
+                let changed = changed || !self.runtime.has_run();
+                match self.runtime.scheduled_run() {
+                    Some(at) if at <= now => true,
+                    Some(at) => {
+                        // Host repaints in between would otherwise drop the script's request
+                        ctx.request_repaint_after(std::time::Duration::from_secs_f64(at - now));
+                        changed || has_input
+                    }
+                    None => changed || has_input,
+                }
+            }
+            RunMode::FixedTimestep => {
+                let simulation = &mut self.project.simulation;
+                if force_step {
+                    simulation.queue(1);
+                }
+                steps = simulation.advance(ctx.input(|i| i.unstable_dt).into());
+                if simulation.is_running() {
+                    ctx.request_repaint();
+                }
+                true
+            }
         };
 
         CentralPanel::default().show(ctx, |ui| {
@@ -116,20 +560,34 @@ impl eframe::App for TemplateApp {
                 .max_width(f32::INFINITY)
                 .auto_shrink([false, false])
                 .show(ui, |ui| {
-                    if self.project.run_mode.show_run_button() {
-                        if ui.button("Run").clicked() {
-                            force_step = true;
-                        }
+                    if self.project.run_mode.show_run_button() && ui.button("Run").clicked() {
+                        force_step = true;
                     }
 
                     ui.strong("GUI");
-                    self.runtime.set_egui(ui);
-                    if run_requested || force_step {
-                        self.runtime.run_loaded_code();
+                    let top = ui.cursor().top();
+                    let run = run_requested || force_step;
+                    if !run && self.project.run_mode == RunMode::ScriptDecides {
+                        self.runtime.show_last_output(ui);
+                    } else {
+                        self.runtime.set_egui(ui);
+                        if run {
+                            self.runtime.run_loaded_code();
+                            self.runtime.run_steps(self.project.simulation.dt(), steps);
+                            self.perf_hud.record_run(self.runtime.timings());
+                        }
+                        self.runtime.take_up_egui_space(ui);
                     }
-                    self.runtime.take_up_egui_space(ui);
+                    self.script_area = Rect::from_x_y_ranges(
+                        ui.max_rect().x_range(),
+                        top..=ui.min_rect().bottom(),
+                    )
+                    .intersect(ui.clip_rect());
 
                     ui.strong("Console out:");
+                    if let Some(notice) = self.runtime.notice() {
+                        ui.label(RichText::new(notice).color(Color32::YELLOW));
+                    }
                     if let Some(error) = self.runtime.error() {
                         ui.label(RichText::new(error).color(Color32::LIGHT_RED));
                     } else {
@@ -139,71 +597,15 @@ impl eframe::App for TemplateApp {
                 });
         });
 
-        //let start = Instant::now();
-        //println!("Run took {}ms", (start.elapsed().as_secs_f32() * 1000.0).floor());
-    }
-}
-
-fn code_editor_with_autoindent(
-    ui: &mut Ui,
-    id: Id,
-    code: &mut String,
-    lang: &'static str,
-) -> Response {
-    let mut layouter = move |ui: &Ui, string: &str, wrap_width: f32| {
-        let mut layout_job = highlight(
-            ui.ctx(),
-            ui.style(),
-            &CodeTheme::from_style(ui.style()),
-            string,
-            lang,
-        );
+        self.perf_hud
+            .show(ctx, &mut self.project.show_perf_hud, self.runtime.timings());
 
-        layout_job.wrap.max_width = wrap_width;
-        ui.fonts(|f| f.layout_job(layout_job.clone()))
-    };
-
-    let ret = TextEdit::multiline(code)
-        .id(id)
-        .desired_width(f32::INFINITY)
-        .desired_rows(50)
-        .code_editor()
-        .layouter(&mut layouter)
-        .show(ui);
-
-    // Did we make a new line?
-    if ret.response.changed() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
-        if let Some(cursor) = ret.cursor_range {
-            let cursor = cursor.primary.ccursor;
-
-            let prev_newline_idx = code[..cursor.index - 1].rfind('\n');
-
-            if cursor.prefer_next_row {
-                if let Some(prev) = prev_newline_idx {
-                    // Find the indent
-                    let indent_chars: String = code[prev..cursor.index]
-                        .chars()
-                        .take_while(|c| c.is_whitespace())
-                        .filter(|c| *c == ' ' || *c == '\t')
-                        .collect();
-
-                    // Insert indent
-                    code.insert_str(cursor.index, &indent_chars);
-
-                    // Set the new cursor pos
-                    let mut new_cursor_range = cursor;
-                    new_cursor_range.index += indent_chars.len();
-                    let mut new_state = ret.state;
-                    new_state
-                        .cursor
-                        .set_char_range(Some(egui::text::CCursorRange::one(new_cursor_range)));
-                    TextEdit::store_state(ui.ctx(), id, new_state);
-                }
-            }
-        }
+        // The script's own text fields are the only ones in its area
+        self.keyboard_to_script = ctx.memory(|mem| mem.focused()).is_none_or(|id| {
+            ctx.read_response(id)
+                .is_some_and(|response| self.script_area.contains_rect(response.rect))
+        });
     }
-
-    ret.response
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
@@ -213,6 +615,10 @@ enum RunMode {
     OnScreenUpdate,
     OnCodeChange,
     Manual,
+    /// Runs on input meant for the script, and when it asks with `egui.request_repaint()`
+    ScriptDecides,
+    /// Runs every frame, and calls the script's `step(dt)` at a fixed rate in between
+    FixedTimestep,
 }
 
 impl RunMode {
@@ -221,9 +627,18 @@ impl RunMode {
         ui.selectable_value(self, Self::OnScreenUpdate, "On Screen Update");
         ui.selectable_value(self, Self::OnCodeChange, "On Code Change");
         ui.selectable_value(self, Self::Manual, "Manual");
+        ui.selectable_value(self, Self::ScriptDecides, "Script decides");
+        ui.selectable_value(self, Self::FixedTimestep, "Fixed timestep");
     }
 
     fn show_run_button(&self) -> bool {
         matches!(self, Self::OnCodeChange | Self::Manual)
     }
 }
+
+/// Wall clock seconds, for the edit history saved with the project
+fn unix_time() -> f64 {
+    web_time::SystemTime::now()
+        .duration_since(web_time::UNIX_EPOCH)
+        .map_or(0.0, |since| since.as_secs_f64())
+}
@@ -0,0 +1,117 @@
+//! Run a script without a window, for validating scripts in CI.
+//!
+//! ```text
+//! headless <script.py> [--frames N] [--size WIDTHxHEIGHT] [--events events.json] [--policy policy.json] [--png out.png]
+//! headless <script.py> --recording recording.json
+//! ```
+//!
+//! `--recording` replays input recorded in the app, with the script's area at its recorded
+//! size, for the recorded number of frames.
+//!
+//! Exits with a non-zero status if the script failed on any frame.
+
+/// Counts script allocations, so the policy's memory budget applies
+#[global_allocator]
+static ALLOCATOR: eframe_template::CountingAllocator = eframe_template::CountingAllocator;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> std::process::ExitCode {
+    use std::process::ExitCode;
+
+    match run(std::env::args().skip(1)) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("error: {e}");
+            eprintln!(
+                "usage: headless <script.py> [--frames N] [--size WIDTHxHEIGHT] [--events FILE] [--policy FILE] [--png FILE] [--recording FILE]"
+            );
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Returns whether every frame ran without errors
+#[cfg(not(target_arch = "wasm32"))]
+fn run(mut args: impl Iterator<Item = String>) -> Result<bool, String> {
+    use eframe_template::{
+        headless::{Headless, ScriptedEvent},
+        playback::InputRecording,
+        RuntimePolicy,
+    };
+
+    let mut script = None;
+    let mut frames = 1;
+    let mut size = egui::vec2(800.0, 600.0);
+    let mut events: Vec<ScriptedEvent> = vec![];
+    let mut policy = RuntimePolicy::default();
+    let mut png = None;
+    let mut recording = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{arg} needs a value"));
+        match arg.as_str() {
+            "--frames" => {
+                frames = value()?
+                    .parse()
+                    .map_err(|e| format!("bad frame count: {e}"))?;
+            }
+            "--size" => {
+                let value = value()?;
+                let (w, h) = value
+                    .split_once('x')
+                    .ok_or(format!("size must look like 800x600, not {value}"))?;
+                let parse = |s: &str| s.parse::<f32>().map_err(|e| format!("bad size: {e}"));
+                size = egui::vec2(parse(w)?, parse(h)?);
+            }
+            "--events" => events = read_json(&value()?)?,
+            "--policy" => policy = read_json(&value()?)?,
+            "--png" => png = Some(value()?),
+            "--recording" => recording = Some(read_json::<InputRecording>(&value()?)?),
+            _ if script.is_none() && !arg.starts_with("--") => script = Some(arg),
+            _ => return Err(format!("unexpected argument {arg}")),
+        }
+    }
+
+    let script = script.ok_or("no script given")?;
+    let code = std::fs::read_to_string(&script).map_err(|e| format!("{script}: {e}"))?;
+
+    let mut headless = Headless::new(code, policy, size);
+    if let Some(recording) = recording {
+        // Recorded positions are relative to the script's area, wherever it is here
+        headless.fit_script_area(recording.script_size);
+        frames = recording.frames;
+        events = recording.events_at(headless.script_area().min);
+    }
+    let mut success = true;
+    let reports = headless.run(frames, &events);
+    for report in &reports {
+        println!("--- frame {} ---", report.frame);
+        print!("{}", report.stdout);
+        if let Some(notice) = &report.notice {
+            eprintln!("{notice}");
+        }
+        if let Some(error) = &report.error {
+            eprintln!("{error}");
+            success = false;
+        }
+    }
+
+    if let (Some(path), Some(last)) = (png, reports.last()) {
+        let image = headless.render(&last.output);
+        let bytes = eframe_template::snapshot::encode_png(&image).map_err(|e| e.to_string())?;
+        std::fs::write(&path, bytes).map_err(|e| format!("{path}: {e}"))?;
+    }
+
+    Ok(success)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_json<T: serde::de::DeserializeOwned>(path: &str) -> Result<T, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    serde_json::from_str(&text).map_err(|e| format!("{path}: {e}"))
+}
+
+// There is nothing to run headless on the web
+#[cfg(target_arch = "wasm32")]
+fn main() {}
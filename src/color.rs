@@ -0,0 +1,91 @@
+//! The color formats scripts may use, apart from the Python types themselves
+
+use egui::Color32;
+
+/// Names accepted in place of a color, matching egui's constants
+const NAMED_COLORS: &[(&str, Color32)] = &[
+    ("transparent", Color32::TRANSPARENT),
+    ("black", Color32::BLACK),
+    ("dark_gray", Color32::DARK_GRAY),
+    ("gray", Color32::GRAY),
+    ("light_gray", Color32::LIGHT_GRAY),
+    ("white", Color32::WHITE),
+    ("brown", Color32::BROWN),
+    ("dark_red", Color32::DARK_RED),
+    ("red", Color32::RED),
+    ("light_red", Color32::LIGHT_RED),
+    ("yellow", Color32::YELLOW),
+    ("orange", Color32::ORANGE),
+    ("light_yellow", Color32::LIGHT_YELLOW),
+    ("khaki", Color32::KHAKI),
+    ("dark_green", Color32::DARK_GREEN),
+    ("green", Color32::GREEN),
+    ("light_green", Color32::LIGHT_GREEN),
+    ("dark_blue", Color32::DARK_BLUE),
+    ("blue", Color32::BLUE),
+    ("light_blue", Color32::LIGHT_BLUE),
+    ("gold", Color32::GOLD),
+];
+
+/// A hex string like "#ff8800" or "#ff880080" with unmultiplied alpha, or a color name
+pub fn parse_str(s: &str) -> Result<Color32, String> {
+    if s.starts_with('#') {
+        return Color32::from_hex(s).map_err(|_| {
+            format!("{s:?} is not a hex color, expected \"#rgb\", \"#rgba\", \"#rrggbb\" or \"#rrggbbaa\"")
+        });
+    }
+
+    let name = s.trim().to_lowercase().replace([' ', '-'], "_");
+    NAMED_COLORS
+        .iter()
+        .find(|(known, _)| *known == name)
+        .map(|(_, color)| *color)
+        .ok_or_else(|| {
+            let names: Vec<&str> = NAMED_COLORS.iter().map(|(name, _)| *name).collect();
+            format!("Unknown color {s:?}, expected one of {}", names.join(", "))
+        })
+}
+
+/// RGB, or unmultiplied RGBA, in 0..=255
+pub fn from_ints(values: &[i64]) -> Result<Color32, String> {
+    let channels = values
+        .iter()
+        .map(|&v| {
+            u8::try_from(v).map_err(|_| format!("Color components must be 0 to 255, not {v}"))
+        })
+        .collect::<Result<Vec<u8>, String>>()?;
+    match channels[..] {
+        [r, g, b] => Ok(Color32::from_rgb(r, g, b)),
+        [r, g, b, a] => Ok(Color32::from_rgba_unmultiplied(r, g, b, a)),
+        _ => Err(component_count_error(values.len())),
+    }
+}
+
+/// RGB, or unmultiplied RGBA, in 0..=1
+pub fn from_floats(values: &[f64]) -> Result<Color32, String> {
+    let channels = values
+        .iter()
+        .map(|&v| match (0.0..=1.0).contains(&v) {
+            true => Ok((v * 255.0).round() as u8),
+            false => Err(format!("Float color components must be 0 to 1, not {v}")),
+        })
+        .collect::<Result<Vec<u8>, String>>()?;
+    match channels[..] {
+        [r, g, b] => Ok(Color32::from_rgb(r, g, b)),
+        [r, g, b, a] => Ok(Color32::from_rgba_unmultiplied(r, g, b, a)),
+        _ => Err(component_count_error(values.len())),
+    }
+}
+
+pub fn component_count_error(count: usize) -> String {
+    format!("Colors have 3 or 4 components, not {count}")
+}
+
+/// Hex string with unmultiplied alpha, leaving alpha out when opaque
+pub fn to_hex(color: Color32) -> String {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    match a {
+        255 => format!("#{r:02x}{g:02x}{b:02x}"),
+        _ => format!("#{r:02x}{g:02x}{b:02x}{a:02x}"),
+    }
+}
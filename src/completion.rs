@@ -0,0 +1,612 @@
+//! Completion, signature help and docs topics for the code editor, driven by the `egui.pyi`
+//! stubs, the script's own assignments and the globals of its last run
+
+use std::{collections::BTreeMap, sync::OnceLock};
+
+/// Stubs for the `egui` global, also useful to external editors
+pub const STUBS: &str = include_str!("egui.pyi");
+
+/// How far back to look for the call the cursor is in
+const MAX_CALL_LOOKBACK: usize = 2000;
+
+/// How deep to follow `a = b`, `b = c.d()`... when working out types
+const MAX_RESOLVE_DEPTH: usize = 8;
+
+const KEYWORDS: &[&str] = &[
+    "and", "as", "assert", "break", "class", "continue", "def", "del", "elif", "else", "except",
+    "False", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "None",
+    "nonlocal", "not", "or", "pass", "raise", "return", "True", "try", "while", "with", "yield",
+];
+
+/// A method or attribute from the stubs
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Member {
+    pub name: String,
+    /// Parameters without `self`, or `None` for attributes
+    pub params: Option<String>,
+    /// The attribute's type, or what the method returns
+    pub ty: String,
+}
+
+impl Member {
+    pub fn is_method(&self) -> bool {
+        self.params.is_some()
+    }
+
+    pub fn signature(&self) -> String {
+        match &self.params {
+            Some(params) => format!("{}({params}) -> {}", self.name, self.ty),
+            None => format!("{}: {}", self.name, self.ty),
+        }
+    }
+}
+
+/// The classes and globals declared in a stub file
+#[derive(Default, Debug)]
+pub struct Stubs {
+    classes: BTreeMap<String, Vec<Member>>,
+    globals: Vec<Member>,
+}
+
+impl Stubs {
+    /// The stubs for the `egui` global
+    pub fn get() -> &'static Self {
+        static STUBS_CELL: OnceLock<Stubs> = OnceLock::new();
+        STUBS_CELL.get_or_init(|| Self::parse(STUBS))
+    }
+
+    /// Understands the subset of `.pyi` syntax `egui.pyi` uses: classes holding
+    /// one-line `def`s and `name: Type` attributes, and top level `name: Type` globals
+    pub fn parse(source: &str) -> Self {
+        let mut stubs = Self::default();
+        let mut class: Option<String> = None;
+
+        for line in source.lines() {
+            if line.trim().is_empty() || line.trim_start().starts_with(['#', '@']) {
+                continue;
+            }
+            let indented = line.starts_with([' ', '\t']);
+            let line = line.trim();
+
+            if !indented {
+                class = None;
+                if let Some(rest) = line.strip_prefix("class ") {
+                    let name = rest.split([':', '(']).next().unwrap_or_default().trim();
+                    stubs.classes.insert(name.to_owned(), vec![]);
+                    class = Some(name.to_owned());
+                } else if let Some(member) = parse_attribute(line) {
+                    stubs.globals.push(member);
+                }
+                continue;
+            }
+
+            let Some(class) = &class else {
+                continue;
+            };
+            let member = match line.strip_prefix("def ") {
+                Some(def) => parse_def(def),
+                None => parse_attribute(line),
+            };
+            if let (Some(member), Some(members)) = (member, stubs.classes.get_mut(class)) {
+                members.push(member);
+            }
+        }
+
+        stubs
+    }
+
+    pub fn class(&self, name: &str) -> Option<&[Member]> {
+        self.classes.get(name).map(Vec::as_slice)
+    }
+
+    pub fn classes(&self) -> impl Iterator<Item = (&str, &[Member])> {
+        self.classes
+            .iter()
+            .map(|(name, members)| (name.as_str(), members.as_slice()))
+    }
+
+    pub fn globals(&self) -> &[Member] {
+        &self.globals
+    }
+
+    fn member(&self, ty: &str, name: &str) -> Option<&Member> {
+        self.class(class_of(ty))?.iter().find(|m| m.name == name)
+    }
+}
+
+/// `name(params) -> ty: ...`
+fn parse_def(def: &str) -> Option<Member> {
+    let (name, rest) = def.split_once('(')?;
+    let (params, ty) = rest.rsplit_once(") ->")?;
+    let params = params.trim();
+    let params = params
+        .strip_prefix("self, ")
+        .or_else(|| params.strip_prefix("self"))
+        .unwrap_or(params);
+    let ty = ty
+        .trim()
+        .trim_end_matches("...")
+        .trim()
+        .trim_end_matches(':');
+    Some(Member {
+        name: name.trim().to_owned(),
+        params: Some(params.to_owned()),
+        ty: ty.trim().to_owned(),
+    })
+}
+
+/// `name: Type`
+fn parse_attribute(line: &str) -> Option<Member> {
+    let (name, ty) = line.split_once(':')?;
+    let name = name.trim();
+    if !is_identifier(name) || ty.contains('=') {
+        return None;
+    }
+    Some(Member {
+        name: name.to_owned(),
+        params: None,
+        ty: ty.trim().to_owned(),
+    })
+}
+
+/// A global of the script's last run, with the name of its class
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Global {
+    pub name: String,
+    pub class: String,
+}
+
+/// Something to insert at the cursor
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Candidate {
+    pub name: String,
+    /// What the popup shows next to the name
+    pub detail: String,
+    pub is_method: bool,
+}
+
+impl Candidate {
+    /// Methods get their opening parenthesis, so signature help shows up right away
+    pub fn insert_text(&self) -> String {
+        match self.is_method {
+            true => format!("{}(", self.name),
+            false => self.name.clone(),
+        }
+    }
+}
+
+/// Completions for the identifier being typed at byte offset `cursor`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Completions {
+    /// Byte range of the partly typed identifier, which a candidate replaces
+    pub replace: std::ops::Range<usize>,
+    pub candidates: Vec<Candidate>,
+}
+
+pub fn complete(code: &str, cursor: usize, globals: &[Global]) -> Completions {
+    let stubs = Stubs::get();
+    let before = &code[..cursor];
+    let chain = trailing_chain(before);
+    let prefix_start = before.len() - trailing_identifier(before).len();
+    let prefix = &before[prefix_start..];
+    let replace = prefix_start..cursor;
+    let resolver = Resolver {
+        stubs,
+        code: before,
+        globals,
+    };
+
+    let mut candidates = vec![];
+    match chain.rsplit_once('.') {
+        Some((_, name)) if name != prefix => {}
+        Some((receiver, _)) => {
+            let Some(members) = resolver
+                .resolve(receiver, 0)
+                .and_then(|ty| stubs.class(class_of(&ty)))
+            else {
+                return Completions::default();
+            };
+            for member in members {
+                if is_candidate(&member.name, prefix) {
+                    candidates.push(Candidate {
+                        name: member.name.clone(),
+                        detail: member.signature(),
+                        is_method: member.is_method(),
+                    });
+                }
+            }
+        }
+        None => {
+            if prefix.is_empty() || prefix.starts_with(|c: char| c.is_ascii_digit()) {
+                return Completions::default();
+            }
+            let mut names = BTreeMap::new();
+            for keyword in KEYWORDS {
+                names.insert(keyword.to_string(), "keyword".to_owned());
+            }
+            for global in globals {
+                names.insert(global.name.clone(), global.class.clone());
+            }
+            for name in assigned_names(before) {
+                names.entry(name).or_insert_with(|| "variable".to_owned());
+            }
+            for global in stubs.globals() {
+                names.insert(global.name.clone(), global.ty.clone());
+            }
+            for (name, detail) in names {
+                if is_candidate(&name, prefix) {
+                    candidates.push(Candidate {
+                        name,
+                        detail,
+                        is_method: false,
+                    });
+                }
+            }
+        }
+    }
+
+    Completions {
+        replace,
+        candidates,
+    }
+}
+
+/// Names already typed out in full are left out, so Enter still starts a new line
+fn is_candidate(name: &str, prefix: &str) -> bool {
+    name.starts_with(prefix) && name != prefix && !name.starts_with('_')
+}
+
+/// The signature of the call the cursor is in
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignatureHelp {
+    /// Like "circle"
+    pub name: String,
+    pub params: Vec<String>,
+    pub ty: String,
+    /// Index into `params` of the argument being typed
+    pub active: usize,
+}
+
+pub fn signature_help(code: &str, cursor: usize, globals: &[Global]) -> Option<SignatureHelp> {
+    let before = &code[..cursor];
+    let (open, active) = enclosing_call(before)?;
+    let callee = trailing_chain(&before[..open]);
+    if callee.is_empty() {
+        return None;
+    }
+    let resolver = Resolver {
+        stubs: Stubs::get(),
+        code: &before[..open],
+        globals,
+    };
+
+    let (name, member) = match callee.rsplit_once('.') {
+        Some((receiver, name)) => {
+            let ty = resolver.resolve(receiver, 0)?;
+            match resolver.stubs.member(&ty, name) {
+                Some(member) if member.is_method() => (name, member.clone()),
+                // Calling a class, like `egui.Vec2(`
+                Some(member) => (name, resolver.stubs.member(&member.ty, "__init__")?.clone()),
+                None => return None,
+            }
+        }
+        None => {
+            let ty = resolver.resolve(callee, 0)?;
+            (callee, resolver.stubs.member(&ty, "__init__")?.clone())
+        }
+    };
+
+    let params = split_top_level(member.params.as_deref().unwrap_or_default(), ',')
+        .into_iter()
+        .map(str::to_owned)
+        .filter(|param| !param.is_empty())
+        .collect::<Vec<_>>();
+    Some(SignatureHelp {
+        name: name.to_owned(),
+        active: active.min(params.len().saturating_sub(1)),
+        params,
+        ty: member.ty,
+    })
+}
+
+/// What the docs panel explains
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Topic {
+    /// A method or attribute of a class in the stubs
+    Member { class: String, name: String },
+    /// A class in the stubs, or a name holding one like `egui`
+    Class(String),
+    /// Any other global or builtin
+    Global(String),
+}
+
+/// The identifier around byte offset `cursor`
+pub fn topic_at(code: &str, cursor: usize, globals: &[Global]) -> Option<Topic> {
+    let end = cursor + trailing_identifier_start(&code[cursor..]).len();
+    let before = &code[..end];
+    let name = trailing_identifier(before);
+    let chain = trailing_chain(before);
+    if !is_identifier(name) || chain.is_empty() || KEYWORDS.contains(&name) {
+        return None;
+    }
+    let stubs = Stubs::get();
+    let resolver = Resolver {
+        stubs,
+        code: &before[..before.len() - chain.len()],
+        globals,
+    };
+
+    let ty = match chain.rsplit_once('.') {
+        Some((receiver, _)) => {
+            let receiver = resolver.resolve(receiver, 0)?;
+            let member = stubs.member(&receiver, name)?;
+            if !member.ty.starts_with("type[") {
+                return Some(Topic::Member {
+                    class: class_of(&receiver).to_owned(),
+                    name: name.to_owned(),
+                });
+            }
+            member.ty.clone()
+        }
+        // The name may be assigned on this very line, or further down
+        None => Resolver { code, ..resolver }
+            .resolve(name, 0)
+            .unwrap_or_default(),
+    };
+    match stubs.class(class_of(&ty)) {
+        Some(_) => Some(Topic::Class(class_of(&ty).to_owned())),
+        None => Some(Topic::Global(name.to_owned())),
+    }
+}
+
+/// Works out the types of expressions from the stubs
+struct Resolver<'a> {
+    stubs: &'a Stubs,
+    /// The code before the expression, for finding assignments
+    code: &'a str,
+    globals: &'a [Global],
+}
+
+impl Resolver<'_> {
+    /// The type of a chain like `egui.painter().text(...)`, as written in the stubs
+    fn resolve(&self, chain: &str, depth: usize) -> Option<String> {
+        if depth > MAX_RESOLVE_DEPTH {
+            return None;
+        }
+        let mut segments = split_top_level(chain, '.').into_iter();
+        let first = segments.next()?;
+        let (name, calls) = split_calls(first);
+        let mut ty = self.resolve_name(name, depth)?;
+        ty = self.apply_calls(ty, &calls)?;
+
+        for segment in segments {
+            let (name, calls) = split_calls(segment);
+            let member = self.stubs.member(&ty, name)?;
+            ty = match (member.is_method(), calls.is_empty()) {
+                (true, false) => self.apply_calls(member.ty.clone(), &calls[1..])?,
+                (true, true) => return None,
+                (false, _) => self.apply_calls(member.ty.clone(), &calls)?,
+            };
+        }
+        Some(ty)
+    }
+
+    /// Calling a class makes an instance, indexing a tuple with a number picks an item
+    fn apply_calls(&self, mut ty: String, calls: &[&str]) -> Option<String> {
+        for call in calls {
+            ty = if call.starts_with('(') {
+                let class = ty.strip_prefix("type[")?.strip_suffix(']')?;
+                class.to_owned()
+            } else {
+                let index: usize = call[1..call.len() - 1].trim().parse().ok()?;
+                let items = ty.strip_prefix("tuple[")?.strip_suffix(']')?;
+                split_top_level(items, ',').get(index)?.to_string()
+            };
+        }
+        Some(ty)
+    }
+
+    fn resolve_name(&self, name: &str, depth: usize) -> Option<String> {
+        if let Some(value) = last_assignment(self.code, name) {
+            return match value {
+                Assigned::Whole(expr) => self.resolve(expr, depth + 1),
+                Assigned::Item(expr, index) => {
+                    let ty = self.resolve(expr, depth + 1)?;
+                    self.apply_calls(ty, &[&format!("[{index}]")])
+                }
+            };
+        }
+        if let Some(global) = self.stubs.globals().iter().find(|g| g.name == name) {
+            return Some(global.ty.clone());
+        }
+        self.globals
+            .iter()
+            .find(|g| g.name == name)
+            .map(|g| g.class.clone())
+    }
+}
+
+/// The class an attribute lives on: `Pos2 | None` looks in `Pos2`, `type[Vec2]` in `Vec2`
+fn class_of(ty: &str) -> &str {
+    let ty = ty
+        .split('|')
+        .map(str::trim)
+        .find(|part| *part != "None")
+        .unwrap_or(ty);
+    ty.strip_prefix("type[")
+        .and_then(|ty| ty.strip_suffix(']'))
+        .unwrap_or(ty)
+}
+
+enum Assigned<'a> {
+    /// `name = expr`
+    Whole(&'a str),
+    /// `a, name = expr`, with the position of `name`
+    Item(&'a str, usize),
+}
+
+/// The expression last assigned to `name` in `code`, at the start of a line
+fn last_assignment<'a>(code: &'a str, name: &str) -> Option<Assigned<'a>> {
+    code.lines().rev().find_map(|line| {
+        let (targets, value) = split_assignment(line)?;
+        let targets: Vec<&str> = targets.split(',').map(str::trim).collect();
+        let index = targets.iter().position(|target| *target == name)?;
+        Some(match targets.len() {
+            1 => Assigned::Whole(value),
+            _ => Assigned::Item(value, index),
+        })
+    })
+}
+
+/// `targets = value`, but not `==`, `+=` and friends
+fn split_assignment(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    let eq = line.find('=')?;
+    let (targets, value) = (&line[..eq], &line[eq + 1..]);
+    if value.starts_with('=') || !targets.split(',').all(|t| is_identifier(t.trim())) {
+        return None;
+    }
+    Some((targets.trim(), value.trim()))
+}
+
+/// Names the code assigns or defines, for completing bare identifiers
+fn assigned_names(code: &str) -> Vec<String> {
+    let mut names = vec![];
+    for line in code.lines() {
+        let line = line.trim();
+        if let Some(rest) = line
+            .strip_prefix("def ")
+            .or_else(|| line.strip_prefix("class "))
+        {
+            names.push(trailing_identifier_start(rest).to_owned());
+        } else if let Some(rest) = line.strip_prefix("for ") {
+            let targets = rest.split(" in ").next().unwrap_or_default();
+            names.extend(targets.split(',').map(|t| t.trim().to_owned()));
+        } else if let Some((targets, _)) = split_assignment(line) {
+            names.extend(targets.split(',').map(|t| t.trim().to_owned()));
+        }
+    }
+    names.retain(|name| is_identifier(name));
+    names
+}
+
+fn trailing_identifier_start(s: &str) -> &str {
+    let end = s.find(|c: char| !is_identifier_char(c)).unwrap_or(s.len());
+    &s[..end]
+}
+
+fn trailing_identifier(s: &str) -> &str {
+    let start = s
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_identifier_char(*c))
+        .last()
+        .map_or(s.len(), |(i, _)| i);
+    &s[start..]
+}
+
+/// The attribute chain ending at the end of `s`, like `egui.painter().ci`, with
+/// balanced calls and indexing kept whole
+fn trailing_chain(s: &str) -> &str {
+    let bytes = s.as_bytes();
+    let mut start = s.len();
+    let mut depth = 0usize;
+    while start > 0 {
+        let c = bytes[start - 1];
+        match c {
+            b')' | b']' => depth += 1,
+            b'(' | b'[' if depth > 0 => depth -= 1,
+            _ if depth > 0 => {}
+            b'.' => {}
+            c if c.is_ascii_alphanumeric() || c == b'_' || c >= 0x80 => {}
+            _ => break,
+        }
+        start -= 1;
+    }
+    if depth > 0 {
+        return "";
+    }
+    // A chain starts with a name, not `.x`, `(a, b).x` or `1.5`
+    let chain = &s[start..];
+    match chain.starts_with(|c: char| matches!(c, '.' | '(' | '[') || c.is_ascii_digit()) {
+        true => "",
+        false => chain,
+    }
+}
+
+/// Where the innermost unclosed `(` before the end of `s` is, and how many top level
+/// commas follow it
+fn enclosing_call(s: &str) -> Option<(usize, usize)> {
+    let mut depth = 0usize;
+    let mut commas = 0;
+    let lookback = s.len().saturating_sub(MAX_CALL_LOOKBACK);
+    for (i, c) in s.char_indices().rev().take_while(|(i, _)| *i >= lookback) {
+        match c {
+            ')' | ']' | '}' => depth += 1,
+            '(' if depth == 0 => return Some((i, commas)),
+            '[' | '{' if depth == 0 => return None,
+            '(' | '[' | '{' => depth -= 1,
+            ',' if depth == 0 => commas += 1,
+            ':' if depth == 0 && s[i..].starts_with(":\n") => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// A name and its calls or indexing, like `painter` and `["()"]`
+fn split_calls(segment: &str) -> (&str, Vec<&str>) {
+    let name_end = segment.find(['(', '[']).unwrap_or(segment.len());
+    let mut calls = vec![];
+    let mut depth = 0;
+    let mut start = name_end;
+    for (i, c) in segment[name_end..].char_indices() {
+        let i = i + name_end;
+        match c {
+            '(' | '[' => {
+                if depth == 0 {
+                    start = i;
+                }
+                depth += 1;
+            }
+            ')' | ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    calls.push(&segment[start..=i]);
+                }
+            }
+            _ => {}
+        }
+    }
+    (segment[..name_end].trim(), calls)
+}
+
+/// Split on `sep` outside of brackets
+fn split_top_level(s: &str, sep: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            c if c == sep && depth == 0 => {
+                parts.push(s[start..i].trim());
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(s[start..].trim());
+    parts
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty()
+        && !s.starts_with(|c: char| c.is_ascii_digit())
+        && s.chars().all(is_identifier_char)
+}
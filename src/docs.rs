@@ -0,0 +1,202 @@
+//! `help()` for scripts and the editor's docs panel. Signatures come from the stubs,
+//! descriptions from the docstrings of the bindings.
+
+use rustpython_vm::{
+    builtins::{PyStr, PyType},
+    function::OptionalArg,
+    scope::Scope,
+    AsObject, Py, PyObject, PyObjectRef, PyResult, VirtualMachine,
+};
+
+use crate::completion::{Stubs, Topic};
+
+const USAGE: &str = "help(thing) describes a function, class or object, like help(egui.button) \
+or help(egui). The docs panel in the View menu shows the same for the name under the cursor.";
+
+/// Add `help` to the builtins, printing to the script's stdout
+pub fn install(vm: &VirtualMachine) -> PyResult<()> {
+    let help = vm.new_function(
+        "help",
+        |thing: OptionalArg<PyObjectRef>, vm: &VirtualMachine| -> PyResult<()> {
+            let text = match thing {
+                OptionalArg::Present(thing) => describe(&thing, vm),
+                OptionalArg::Missing => USAGE.to_owned(),
+            };
+            let print = vm.builtins.get_attr("print", vm)?;
+            print.call((vm.ctx.new_str(text),), vm)?;
+            Ok(())
+        },
+    );
+    vm.builtins.set_attr("help", help, vm)
+}
+
+/// What `help(thing)` prints
+pub fn describe(thing: &PyObject, vm: &VirtualMachine) -> String {
+    if let Some(class) = thing.downcast_ref::<PyType>() {
+        return class_help(class, vm);
+    }
+    if let Some((class, name)) = owner(thing, vm) {
+        return member_help(&class, &name, thing, vm);
+    }
+    let class = thing.class();
+    if Stubs::get().class(&class.name()).is_some() {
+        return class_help(class, vm);
+    }
+
+    let name = attr_str(thing, "__name__", vm).unwrap_or_else(|| class.name().to_string());
+    let doc = docstring(thing, vm).unwrap_or_else(|| "No documentation".to_owned());
+    format!("{name}\n\n{doc}")
+}
+
+/// Help for what the docs panel points at, or `None` if it doesn't exist (yet)
+pub fn topic_help(topic: &Topic, scope: &Scope, vm: &VirtualMachine) -> Option<String> {
+    match topic {
+        Topic::Global(name) => {
+            let value = scope
+                .globals
+                .get_item_opt(name.as_str(), vm)
+                .ok()
+                .flatten()
+                .or_else(|| {
+                    vm.builtins
+                        .get_attr(&vm.ctx.new_str(name.as_str()), vm)
+                        .ok()
+                })?;
+            Some(describe(&value, vm))
+        }
+        Topic::Class(class) => Some(describe(&*bound_class(class, vm)?, vm)),
+        Topic::Member { class, name } => {
+            let member = bound_class(class, vm)?
+                .get_attr(&vm.ctx.new_str(name.as_str()), vm)
+                .ok()?;
+            Some(member_help(class, name, &member, vm))
+        }
+    }
+}
+
+fn bound_class(name: &str, vm: &VirtualMachine) -> Option<PyObjectRef> {
+    let module = vm.import("rust_py_module", 0).ok()?;
+    module.get_attr(&vm.ctx.new_str(name), vm).ok()
+}
+
+/// The constructor, the docstring, then every member from the stubs with the first
+/// line of its docstring
+fn class_help(class: &Py<PyType>, vm: &VirtualMachine) -> String {
+    let name = class.name().to_string();
+    let members = Stubs::get().class(&name).unwrap_or_default();
+
+    let mut text = match members.iter().find(|member| member.name == "__init__") {
+        Some(init) => format!(
+            "class {name}({})",
+            init.params.as_deref().unwrap_or_default()
+        ),
+        None => format!("class {name}"),
+    };
+    if let Some(doc) = docstring(class.as_object(), vm) {
+        text += &format!("\n\n{doc}");
+    }
+    for member in members
+        .iter()
+        .filter(|member| !member.name.starts_with('_'))
+    {
+        text += &format!("\n\n{}", member.signature());
+        let doc = class
+            .as_object()
+            .get_attr(member.name.as_str(), vm)
+            .ok()
+            .and_then(|value| docstring(&value, vm));
+        if let Some(line) = doc.as_deref().and_then(|doc| doc.lines().next()) {
+            text += &format!("\n    {line}");
+        }
+    }
+    text
+}
+
+fn member_help(class: &str, name: &str, member: &PyObject, vm: &VirtualMachine) -> String {
+    let stubbed = Stubs::get()
+        .class(class)
+        .and_then(|members| members.iter().find(|m| m.name == name));
+    let mut text = match stubbed {
+        Some(stubbed) => format!("{class}.{}", stubbed.signature()),
+        None => format!("{class}.{name}"),
+    };
+    if let Some(doc) = docstring(member, vm) {
+        text += &format!("\n\n{doc}");
+    }
+    text
+}
+
+/// The stub class and member name of a native method, bound or not
+fn owner(method: &PyObject, vm: &VirtualMachine) -> Option<(String, String)> {
+    let name = attr_str(method, "__name__", vm)?;
+    let class = match attr(method, "__objclass__", vm) {
+        Some(class) => class
+            .downcast_ref::<PyType>()
+            .map(|class| class.name().to_string()),
+        None => attr(method, "__self__", vm).map(|zelf| match zelf.downcast_ref::<PyType>() {
+            Some(class) => class.name().to_string(),
+            None => zelf.class().name().to_string(),
+        }),
+    };
+    // Static methods are bound to nothing, but their qualified name has the class
+    let class = class
+        .filter(|class| Stubs::get().class(class).is_some())
+        .or_else(|| {
+            let qualname = attr_str(method, "__qualname__", vm)?;
+            qualname.rsplit_once('.').map(|(class, _)| class.to_owned())
+        })?;
+
+    let members = Stubs::get().class(&class)?;
+    members
+        .iter()
+        .any(|member| member.name == name)
+        .then_some((class, name))
+}
+
+/// `__doc__` without the signature line RustPython puts in front, which names the
+/// Rust arguments of the binding rather than the Python ones
+fn docstring(thing: &PyObject, vm: &VirtualMachine) -> Option<String> {
+    let doc = attr_str(thing, "__doc__", vm)?;
+    let doc = match doc.split_once("\n--\n\n") {
+        Some((signature, rest)) if !signature.contains('\n') => rest,
+        _ => doc.as_str(),
+    };
+    let doc = clean_doc(doc);
+    (!doc.is_empty()).then_some(doc)
+}
+
+/// Like Python's `inspect.cleandoc`: the first line trimmed, the rest dedented
+fn clean_doc(doc: &str) -> String {
+    let mut lines = doc.trim().lines();
+    let first = lines.next().unwrap_or_default().trim();
+    let rest: Vec<&str> = lines.collect();
+    let indent = rest
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    std::iter::once(first)
+        .chain(
+            rest.iter()
+                .map(|line| line.get(indent..).unwrap_or_default()),
+        )
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_end()
+        .to_owned()
+}
+
+fn attr(thing: &PyObject, name: &str, vm: &VirtualMachine) -> Option<PyObjectRef> {
+    let value = vm
+        .get_attribute_opt(thing.to_owned(), &vm.ctx.new_str(name))
+        .ok()
+        .flatten()?;
+    (!vm.is_none(&value)).then_some(value)
+}
+
+fn attr_str(thing: &PyObject, name: &str, vm: &VirtualMachine) -> Option<String> {
+    attr(thing, name, vm)?
+        .payload::<PyStr>()
+        .map(|s| s.as_str().to_owned())
+}
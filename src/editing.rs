@@ -0,0 +1,360 @@
+//! Edits the code editor makes for keys: indentation, comments and brackets.
+//! Positions and selections are byte offsets into the code.
+
+use std::ops::Range;
+
+/// One level of indentation
+pub const INDENT: &str = "    ";
+
+/// Lines starting with these leave the block, so the next line is dedented
+const BLOCK_ENDERS: &[&str] = &["return", "pass", "break", "continue", "raise"];
+
+const PAIRS: &[(char, char)] = &[('(', ')'), ('[', ']'), ('{', '}'), ('"', '"'), ('\'', '\'')];
+
+/// Replace `range` with `text`, then select `selection`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Edit {
+    pub range: Range<usize>,
+    pub text: String,
+    /// In the code after the edit
+    pub selection: Range<usize>,
+}
+
+impl Edit {
+    pub fn apply(&self, code: &mut String) {
+        code.replace_range(self.range.clone(), &self.text);
+    }
+}
+
+/// Enter: a new line indented like the current one, one level deeper after a `:`,
+/// and one level shallower after `return`, `pass` and friends
+pub fn newline(code: &str, selection: Range<usize>) -> Edit {
+    let line = &code[line_start(code, selection.start)..selection.start];
+    let mut indent = indent_of(line).to_owned();
+
+    let statement = strip_comment(line).trim();
+    if statement.ends_with(':') {
+        indent += INDENT;
+    } else if BLOCK_ENDERS
+        .iter()
+        .any(|word| starts_with_word(statement, word))
+    {
+        indent = dedented(&indent).to_owned();
+    }
+
+    let text = format!("\n{indent}");
+    let cursor = selection.start + text.len();
+    Edit {
+        range: selection,
+        text,
+        selection: cursor..cursor,
+    }
+}
+
+/// Tab: indent every line of a selection spanning lines, otherwise insert spaces up to
+/// the next indentation level
+pub fn indent(code: &str, selection: Range<usize>) -> Edit {
+    if !code[selection.clone()].contains('\n') {
+        let column = code[line_start(code, selection.start)..selection.start]
+            .chars()
+            .count();
+        let text = " ".repeat(INDENT.len() - column % INDENT.len());
+        let cursor = selection.start + text.len();
+        return Edit {
+            range: selection,
+            text,
+            selection: cursor..cursor,
+        };
+    }
+    edit_lines(code, selection, |line| match line.trim().is_empty() {
+        true => None,
+        false => Some(Splice::insert(0, INDENT)),
+    })
+}
+
+/// Shift-Tab: remove one level of indentation from every line of the selection
+pub fn dedent(code: &str, selection: Range<usize>) -> Edit {
+    edit_lines(code, selection, |line| {
+        let indent = indent_of(line);
+        let removed = indent.len() - dedented(indent).len();
+        (removed > 0).then_some(Splice {
+            at: 0,
+            removed,
+            inserted: "",
+        })
+    })
+}
+
+/// Ctrl+/: comment out the selected lines, or uncomment them if they all are
+pub fn toggle_comment(code: &str, selection: Range<usize>) -> Edit {
+    let lines = touched_lines(code, &selection);
+    let code_lines = || {
+        code[lines.clone()]
+            .split('\n')
+            .filter(|line| !line.trim().is_empty())
+    };
+    let commented = code_lines().all(|line| line.trim_start().starts_with('#'));
+
+    if commented && code_lines().next().is_some() {
+        edit_lines(code, selection, |line| {
+            let at = indent_of(line).len();
+            let removed = match line[at..].starts_with("# ") {
+                true => 2,
+                false => line[at..].starts_with('#') as usize,
+            };
+            (removed > 0).then_some(Splice {
+                at,
+                removed,
+                inserted: "",
+            })
+        })
+    } else {
+        let at = code_lines()
+            .map(|line| indent_of(line).len())
+            .min()
+            .unwrap_or(0);
+        edit_lines(code, selection, |line| {
+            (!line.trim().is_empty()).then_some(Splice::insert(at, "# "))
+        })
+    }
+}
+
+/// Typing a bracket or quote: close it, wrap the selection in it, or step over the
+/// closing one already there. `None` leaves the character to the text edit.
+pub fn type_char(code: &str, selection: Range<usize>, c: char) -> Option<Edit> {
+    let next = code[selection.end..].chars().next();
+    let prev = code[..selection.start].chars().next_back();
+
+    let is_closer = PAIRS.iter().any(|(_, close)| *close == c);
+    if is_closer && selection.is_empty() && next == Some(c) {
+        let cursor = selection.end + c.len_utf8();
+        return Some(Edit {
+            range: selection.end..selection.end,
+            text: String::new(),
+            selection: cursor..cursor,
+        });
+    }
+
+    let (open, close) = *PAIRS.iter().find(|(open, _)| *open == c)?;
+    if !selection.is_empty() {
+        let selected = &code[selection.clone()];
+        let start = selection.start + open.len_utf8();
+        return Some(Edit {
+            text: format!("{open}{selected}{close}"),
+            selection: start..start + selected.len(),
+            range: selection,
+        });
+    }
+
+    let closes_here = match next {
+        Some(n) => n.is_whitespace() || matches!(n, ')' | ']' | '}' | ',' | ':'),
+        None => true,
+    };
+    let is_quote = open == close;
+    // Apostrophes in words, and the third quote of a docstring, stay single
+    let quote_follows_word = prev.is_some_and(|p| p.is_alphanumeric() || p == '_' || p == c);
+    if !closes_here || (is_quote && quote_follows_word) {
+        return None;
+    }
+    let cursor = selection.start + open.len_utf8();
+    Some(Edit {
+        range: selection,
+        text: format!("{open}{close}"),
+        selection: cursor..cursor,
+    })
+}
+
+/// Where the bracket matching the one at byte `at` is, ignoring brackets in strings
+/// and comments
+pub fn matching_bracket(code: &str, at: usize) -> Option<usize> {
+    let mut open: Vec<(usize, u8)> = vec![];
+    for (i, c) in brackets(code) {
+        match c {
+            b'(' | b'[' | b'{' => open.push((i, c)),
+            _ => {
+                let Some(&(start, opener)) = open.last() else {
+                    continue;
+                };
+                if closer_of(opener) != c {
+                    continue;
+                }
+                open.pop();
+                if start == at {
+                    return Some(i);
+                }
+                if i == at {
+                    return Some(start);
+                }
+            }
+        }
+    }
+    None
+}
+
+fn closer_of(open: u8) -> u8 {
+    match open {
+        b'(' => b')',
+        b'[' => b']',
+        _ => b'}',
+    }
+}
+
+/// Bracket bytes outside of strings and comments
+fn brackets(code: &str) -> Vec<(usize, u8)> {
+    let bytes = code.as_bytes();
+    let mut found = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'#' => {
+                i = code[i..].find('\n').map_or(bytes.len(), |n| i + n);
+                continue;
+            }
+            quote @ (b'"' | b'\'') => {
+                let triple = bytes[i..].starts_with(&[quote; 3]);
+                let delimiter = if triple { 3 } else { 1 };
+                i += delimiter;
+                while i < bytes.len() {
+                    if bytes[i] == b'\\' {
+                        i += 2;
+                    } else if bytes[i..].starts_with(&[quote; 3][..delimiter]) {
+                        i += delimiter;
+                        break;
+                    } else if bytes[i] == b'\n' && !triple {
+                        break;
+                    } else {
+                        i += 1;
+                    }
+                }
+                continue;
+            }
+            c @ (b'(' | b'[' | b'{' | b')' | b']' | b'}') => found.push((i, c)),
+            _ => {}
+        }
+        i += 1;
+    }
+    found
+}
+
+/// A change to one line: `removed` bytes at `at` replaced by `inserted`
+struct Splice {
+    at: usize,
+    removed: usize,
+    inserted: &'static str,
+}
+
+impl Splice {
+    fn insert(at: usize, inserted: &'static str) -> Self {
+        Self {
+            at,
+            removed: 0,
+            inserted,
+        }
+    }
+}
+
+/// Change each line the selection touches, keeping the selection on the same text
+fn edit_lines(
+    code: &str,
+    selection: Range<usize>,
+    splice: impl Fn(&str) -> Option<Splice>,
+) -> Edit {
+    let lines = touched_lines(code, &selection);
+    let mut text = String::new();
+    // Where each splice happened in the original code
+    let mut splices = vec![];
+    let mut line_start = lines.start;
+
+    for (i, line) in code[lines.clone()].split('\n').enumerate() {
+        if i > 0 {
+            text.push('\n');
+        }
+        match splice(line) {
+            Some(splice) => {
+                text += &line[..splice.at];
+                text += splice.inserted;
+                text += &line[splice.at + splice.removed..];
+                splices.push(Splice {
+                    at: line_start + splice.at,
+                    ..splice
+                });
+            }
+            None => text += line,
+        }
+        line_start += line.len() + 1;
+    }
+
+    let map = |pos: usize| {
+        let mut moved = 0isize;
+        for splice in &splices {
+            if pos >= splice.at + splice.removed {
+                moved += splice.inserted.len() as isize - splice.removed as isize;
+            } else if pos > splice.at {
+                // Inside removed text, so end up after what replaced it
+                moved += (splice.at + splice.inserted.len()) as isize - pos as isize;
+            }
+        }
+        pos.saturating_add_signed(moved)
+    };
+    Edit {
+        range: lines,
+        text,
+        selection: map(selection.start)..map(selection.end),
+    }
+}
+
+/// From the start of the first line the selection touches to the end of the last
+/// A selection ending at the very start of a line leaves that line alone.
+fn touched_lines(code: &str, selection: &Range<usize>) -> Range<usize> {
+    let last = match selection.is_empty() || !code[..selection.end].ends_with('\n') {
+        true => selection.end,
+        false => selection.end - 1,
+    };
+    let end = code[last..].find('\n').map_or(code.len(), |n| last + n);
+    line_start(code, selection.start)..end
+}
+
+fn line_start(code: &str, pos: usize) -> usize {
+    code[..pos].rfind('\n').map_or(0, |n| n + 1)
+}
+
+fn indent_of(line: &str) -> &str {
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+/// `indent` with its last level removed
+fn dedented(indent: &str) -> &str {
+    if let Some(rest) = indent.strip_suffix('\t') {
+        return rest;
+    }
+    let spaces = indent.len() - indent.trim_end_matches(' ').len();
+    let remove = match spaces % INDENT.len() {
+        0 => spaces.min(INDENT.len()),
+        partial => partial,
+    };
+    &indent[..indent.len() - remove]
+}
+
+/// The line without a trailing `# comment`, roughly: a `#` inside a string counts too
+fn strip_comment(line: &str) -> &str {
+    let end = comment_start(line).unwrap_or(line.len());
+    &line[..end]
+}
+
+fn comment_start(line: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '#') => return Some(i),
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn starts_with_word(s: &str, word: &str) -> bool {
+    s.strip_prefix(word)
+        .is_some_and(|rest| !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_'))
+}
@@ -0,0 +1,587 @@
+//! The code editor: highlighting, line numbers, indentation and bracket helpers,
+//! completion with signature help, and find and replace
+
+use std::{ops::Range, rc::Rc};
+
+use egui::{
+    pos2,
+    text::{CCursor, CCursorRange},
+    text_edit::TextEditOutput,
+    vec2, Align, Align2, Area, Event, FontId, Frame, Id, InputState, Key, Modifiers, Order, Rect,
+    Response, RichText, ScrollArea, Sense, Stroke, TextEdit, TextStyle, Ui,
+};
+use egui_extras::syntax_highlighting::{highlight, CodeTheme};
+
+use crate::{
+    completion::{self, Completions, Global, SignatureHelp},
+    editing::{self, Edit},
+    find::{self, Query},
+};
+
+/// Whether the completion popup is showing, and which candidate is picked
+#[derive(Clone, Copy, Debug, Default)]
+struct PopupState {
+    open: bool,
+    selected: usize,
+    /// Whether Enter and Tab accept the picked candidate, because the popup was asked for
+    /// with Ctrl+Space or a candidate was picked with the arrow keys. The popup opens
+    /// while typing names, and until then they are typed as usual.
+    engaged: bool,
+}
+
+/// The find bar of an editor, shared with the editor for highlighting the matches
+#[derive(Clone, Debug, Default)]
+struct FindState {
+    open: bool,
+    replacing: bool,
+    query: Query,
+    replacement: String,
+    /// The match last navigated to
+    current: Option<Range<usize>>,
+    /// Whether the editor still has to scroll to `current`
+    scroll: bool,
+}
+
+/// `globals` is only called while the editor has focus
+pub fn code_editor_with_autoindent(
+    ui: &mut Ui,
+    id: Id,
+    code: &mut String,
+    lang: &'static str,
+    globals: impl FnOnce() -> Rc<[Global]>,
+) -> Response {
+    let find_id = id.with("find");
+    let mut find: FindState = ui.data(|data| data.get_temp(find_id)).unwrap_or_default();
+    let marked = find.clone();
+    let mut layouter = move |ui: &Ui, string: &str, wrap_width: f32| {
+        let mut layout_job = highlight(
+            ui.ctx(),
+            ui.style(),
+            &CodeTheme::from_style(ui.style()),
+            string,
+            lang,
+        );
+        if let (true, Ok(matches)) = (marked.open, marked.query.find_all(string)) {
+            let color = ui.visuals().warn_fg_color;
+            find::mark_matches(
+                &mut layout_job,
+                &matches,
+                marked.current.as_ref(),
+                color.gamma_multiply(0.25),
+                color.gamma_multiply(0.6),
+            );
+        }
+
+        layout_job.wrap.max_width = wrap_width;
+        ui.fonts(|f| f.layout_job(layout_job.clone()))
+    };
+
+    let popup_id = id.with("completion");
+    let mut popup: PopupState = ui.data(|data| data.get_temp(popup_id)).unwrap_or_default();
+    let focused = ui.memory(|memory| memory.has_focus(id));
+
+    // Take the keys the popup uses before the text edit sees them
+    let mut accept = None;
+    if focused {
+        ui.input_mut(|i| {
+            if i.consume_key(Modifiers::COMMAND, Key::Space) {
+                popup = PopupState {
+                    open: true,
+                    selected: 0,
+                    engaged: true,
+                };
+            } else if popup.open {
+                if i.consume_key(Modifiers::NONE, Key::ArrowDown) {
+                    popup.selected += 1;
+                    popup.engaged = true;
+                }
+                if i.consume_key(Modifiers::NONE, Key::ArrowUp) {
+                    popup.selected = popup.selected.saturating_sub(1);
+                    popup.engaged = true;
+                }
+                if !popup.engaged {
+                    // Left for the editor, which starts a new line or indents
+                    if i.key_pressed(Key::Enter) || i.key_pressed(Key::Tab) {
+                        popup.open = false;
+                    }
+                } else if i.consume_key(Modifiers::NONE, Key::Enter)
+                    || i.consume_key(Modifiers::NONE, Key::Tab)
+                {
+                    accept = Some(popup.selected);
+                }
+                if i.consume_key(Modifiers::NONE, Key::Escape) {
+                    popup.open = false;
+                }
+            }
+        });
+    }
+
+    // Then the keys the editor handles itself
+    let mut edited = false;
+    if let (true, Some(selection)) = (focused, selection(ui.ctx(), id, code)) {
+        if let Some(edit) = ui.input_mut(|i| key_edit(i, code, selection)) {
+            edit.apply(code);
+            set_selection(ui.ctx(), id, code, edit.selection);
+            edited = true;
+        }
+    }
+
+    let font_id = TextStyle::Monospace.resolve(ui.style());
+    let char_width = ui.fonts(|fonts| fonts.glyph_width(&font_id, '0'));
+    let digits = (code.matches('\n').count() + 1).to_string().len();
+    let gutter_width = digits as f32 * char_width;
+
+    let mut ret = ui
+        .horizontal_top(|ui| {
+            let (gutter, _) = ui.allocate_exact_size(vec2(gutter_width, 0.0), Sense::hover());
+            let ret = TextEdit::multiline(code)
+                .id(id)
+                .desired_width(f32::INFINITY)
+                .desired_rows(50)
+                .code_editor()
+                .layouter(&mut layouter)
+                .show(ui);
+            paint_line_numbers(ui, gutter.right(), &ret, &font_id);
+            ret
+        })
+        .inner;
+    if edited {
+        ret.response.mark_changed();
+    }
+
+    if let (true, Some(current)) = (find.scroll, &find.current) {
+        let ccursor = CCursor::new(code[..current.start.min(code.len())].chars().count());
+        let rect = ret.galley.pos_from_ccursor(ccursor);
+        ui.scroll_to_rect(
+            rect.translate(ret.galley_pos.to_vec2()),
+            Some(Align::Center),
+        );
+        find.scroll = false;
+        ui.data_mut(|data| data.insert_temp(find_id, find));
+    }
+
+    // Typing a name or a dot opens the popup, typing anything else closes it
+    if ret.response.changed() {
+        let typed = ui.input(|i| {
+            i.events.iter().rev().find_map(|event| match event {
+                egui::Event::Text(text) => text.chars().last(),
+                _ => None,
+            })
+        });
+        match typed {
+            Some(c) if c == '.' || c == '_' || c.is_alphanumeric() => {
+                if !popup.open || c == '.' {
+                    popup.selected = 0;
+                    popup.engaged = false;
+                }
+                popup.open = true;
+            }
+            Some(_) => popup.open = false,
+            None => {}
+        }
+    }
+    if ret.response.clicked() || !ret.response.has_focus() {
+        popup.open = false;
+    }
+
+    if let (true, Some(range)) = (ret.response.has_focus(), ret.cursor_range) {
+        let cursor = byte_index(code, range.primary.ccursor.index);
+        let cursor_rect = ret
+            .galley
+            .pos_from_cursor(&range.primary)
+            .translate(ret.galley_pos.to_vec2());
+        let globals = globals();
+
+        if popup.open {
+            let completions = completion::complete(code, cursor, &globals);
+            popup.selected = popup
+                .selected
+                .min(completions.candidates.len().saturating_sub(1));
+            if completions.candidates.is_empty() {
+                popup.open = false;
+            } else if let Some(index) =
+                accept.or_else(|| show_completions(ui, popup_id, cursor_rect, &completions, popup))
+            {
+                let index = index.min(completions.candidates.len() - 1);
+                insert_completion(ui, id, code, &completions, index, &mut ret.state);
+                ret.response.mark_changed();
+                popup.open = false;
+            }
+        }
+
+        if let Some(help) = completion::signature_help(code, cursor, &globals) {
+            show_signature_help(ui, id.with("signature"), cursor_rect, &help);
+        }
+        highlight_matching_bracket(ui, &ret, code, cursor, char_width);
+    }
+
+    ui.data_mut(|data| data.insert_temp(popup_id, popup));
+    ret.response
+}
+
+/// Find and replace for the editor `id`, opened with Ctrl+F, or Ctrl+H for replacing.
+/// Show it above the editor's scroll area. Returns whether it changed the code.
+pub fn find_bar(ui: &mut Ui, id: Id, code: &mut String) -> bool {
+    let find_id = id.with("find");
+    let field_id = find_id.with("field");
+    let mut find: FindState = ui.data(|data| data.get_temp(find_id)).unwrap_or_default();
+
+    let (open_find, open_replace) = ui.input_mut(|i| {
+        (
+            i.consume_key(Modifiers::COMMAND, Key::F),
+            i.consume_key(Modifiers::COMMAND, Key::H),
+        )
+    });
+    if open_find || open_replace {
+        find.open = true;
+        find.replacing = open_replace;
+        // Start from the selection, if it's a bit of one line
+        if let Some(selection) = selection(ui.ctx(), id, code)
+            .filter(|selection| !selection.is_empty() && !code[selection.clone()].contains('\n'))
+        {
+            find.query.text = code[selection].to_owned();
+        }
+        ui.memory_mut(|memory| memory.request_focus(field_id));
+    }
+    if !find.open {
+        return false;
+    }
+
+    let before = find.query.clone();
+    let matches = find.query.find_all(code);
+    if let Ok(matches) = &matches {
+        find.current = find.current.filter(|current| matches.contains(current));
+    }
+    let cursor = selection(ui.ctx(), id, code).unwrap_or_default();
+    // Where to look for the match to go to, and whether to look backwards
+    let mut go = None;
+    let mut replace = false;
+    let mut replace_all = false;
+
+    ui.horizontal(|ui| {
+        let field = ui.add(
+            TextEdit::singleline(&mut find.query.text)
+                .id(field_id)
+                .hint_text("Find")
+                .font(TextStyle::Monospace)
+                .desired_width(160.0),
+        );
+        if field.lost_focus() {
+            if ui.input(|i| i.key_pressed(Key::Enter)) {
+                go = match ui.input(|i| i.modifiers.shift) {
+                    true => Some((cursor.start, true)),
+                    false => Some((cursor.end, false)),
+                };
+                field.request_focus();
+            } else if ui.input(|i| i.key_pressed(Key::Escape)) {
+                find.open = false;
+                ui.memory_mut(|memory| memory.request_focus(id));
+            }
+        }
+        ui.toggle_value(&mut find.query.case_sensitive, "Aa")
+            .on_hover_text("Match case");
+        ui.toggle_value(&mut find.query.whole_word, "W")
+            .on_hover_text("Whole word");
+        ui.toggle_value(&mut find.query.regex, ".*")
+            .on_hover_text("Regular expression, with $1 in replacements for groups");
+        if ui
+            .button("⏶")
+            .on_hover_text("Previous (Shift+Enter)")
+            .clicked()
+        {
+            go = Some((cursor.start, true));
+        }
+        if ui.button("⏷").on_hover_text("Next (Enter)").clicked() {
+            go = Some((cursor.end, false));
+        }
+        match &matches {
+            Err(err) => {
+                ui.colored_label(ui.visuals().error_fg_color, "Invalid regex")
+                    .on_hover_text(err);
+            }
+            Ok(_) if find.query.text.is_empty() => {}
+            Ok(matches) if matches.is_empty() => {
+                ui.weak("No results");
+            }
+            Ok(matches) => {
+                let at = find
+                    .current
+                    .as_ref()
+                    .and_then(|current| matches.iter().position(|found| found == current));
+                ui.weak(match at {
+                    Some(at) => format!("{} of {}", at + 1, matches.len()),
+                    None => format!("{} matches", matches.len()),
+                });
+            }
+        }
+        if ui
+            .small_button("✖")
+            .on_hover_text("Close (Escape)")
+            .clicked()
+        {
+            find.open = false;
+        }
+    });
+    if find.replacing {
+        ui.horizontal(|ui| {
+            ui.add(
+                TextEdit::singleline(&mut find.replacement)
+                    .hint_text("Replace")
+                    .font(TextStyle::Monospace)
+                    .desired_width(160.0),
+            );
+            replace = ui.button("Replace").clicked();
+            replace_all = ui.button("Replace all").clicked();
+        });
+    }
+
+    // Typing in the find field jumps to the first match from the cursor on
+    if find.query != before {
+        go = Some((cursor.start, false));
+        find.current = None;
+    }
+
+    let mut changed = false;
+    if replace {
+        let replaced = find.current.clone().and_then(|current| {
+            let text = find
+                .query
+                .replacement(code, current.clone(), &find.replacement)
+                .ok()??;
+            code.replace_range(current.clone(), &text);
+            Some(current.start + text.len())
+        });
+        changed = replaced.is_some();
+        // Replace the current match, or find one to replace next time
+        go = Some((replaced.unwrap_or(cursor.start), false));
+        find.current = None;
+    }
+    if replace_all {
+        if let Ok((replaced, count)) = find.query.replace_all(code, &find.replacement) {
+            if count > 0 {
+                *code = replaced;
+                changed = true;
+            }
+        }
+        go = None;
+        find.current = None;
+    }
+
+    if let Some((from, backwards)) = go {
+        let matches = find.query.find_all(code).unwrap_or_default();
+        let found = match backwards {
+            true => find::previous(&matches, from),
+            false => find::next(&matches, from),
+        };
+        if let Some(found) = found {
+            set_selection(ui.ctx(), id, code, found.clone());
+            find.current = Some(found.clone());
+            find.scroll = true;
+        }
+    }
+
+    ui.data_mut(|data| data.insert_temp(find_id, find));
+    changed
+}
+
+/// Byte offset of the cursor in the editor `id`, once it has been clicked into
+pub fn cursor(ctx: &egui::Context, id: Id, code: &str) -> Option<usize> {
+    let range = TextEdit::load_state(ctx, id)?.cursor.char_range()?;
+    Some(byte_index(code, range.primary.index))
+}
+
+/// Put the cursor of the editor `id` at byte `at`, e.g. after replacing its code
+pub fn set_cursor(ctx: &egui::Context, id: Id, code: &str, at: usize) {
+    set_selection(ctx, id, code, at..at);
+}
+
+/// Indentation, comments and brackets, done before the text edit sees the keys
+fn key_edit(i: &mut InputState, code: &str, selection: Range<usize>) -> Option<Edit> {
+    if i.consume_key(Modifiers::COMMAND, Key::Slash) {
+        return Some(editing::toggle_comment(code, selection));
+    }
+    // Without shift also matches shift, so check for it first
+    if i.consume_key(Modifiers::SHIFT, Key::Tab) {
+        return Some(editing::dedent(code, selection));
+    }
+    if i.consume_key(Modifiers::NONE, Key::Tab) {
+        return Some(editing::indent(code, selection));
+    }
+    if i.consume_key(Modifiers::NONE, Key::Enter) {
+        return Some(editing::newline(code, selection));
+    }
+
+    let typed = i
+        .events
+        .iter()
+        .position(|event| matches!(event, Event::Text(text) if text.chars().count() == 1))?;
+    let Event::Text(text) = &i.events[typed] else {
+        return None;
+    };
+    let c = text.chars().next()?;
+    let edit = editing::type_char(code, selection, c)?;
+    i.events.remove(typed);
+    Some(edit)
+}
+
+/// The selection in the editor `id` as byte offsets, once it has been clicked into
+fn selection(ctx: &egui::Context, id: Id, code: &str) -> Option<Range<usize>> {
+    let range = TextEdit::load_state(ctx, id)?.cursor.char_range()?;
+    let [min, max] = range.sorted();
+    Some(byte_index(code, min.index)..byte_index(code, max.index))
+}
+
+fn set_selection(ctx: &egui::Context, id: Id, code: &str, selection: Range<usize>) {
+    let mut state = TextEdit::load_state(ctx, id).unwrap_or_default();
+    let start = CCursor::new(code[..selection.start].chars().count());
+    let end = CCursor::new(code[..selection.end].chars().count());
+    state
+        .cursor
+        .set_char_range(Some(CCursorRange::two(start, end)));
+    TextEdit::store_state(ctx, id, state);
+}
+
+/// Numbers each line, not each row of a wrapped line, right aligned to `right`
+fn paint_line_numbers(ui: &Ui, right: f32, output: &TextEditOutput, font_id: &FontId) {
+    let color = ui.visuals().weak_text_color();
+    let visible = ui.clip_rect().y_range();
+    let mut number = 1;
+    let mut starts_line = true;
+    for row in &output.galley.rows {
+        let top = output.galley_pos.y + row.rect.top();
+        if starts_line && top <= visible.max && top + row.rect.height() >= visible.min {
+            let pos = pos2(right, top);
+            ui.painter()
+                .text(pos, Align2::RIGHT_TOP, number, font_id.clone(), color);
+        }
+        number += starts_line as usize;
+        starts_line = row.ends_with_newline;
+    }
+}
+
+/// Outline the bracket next to the cursor and its partner
+fn highlight_matching_bracket(
+    ui: &Ui,
+    output: &TextEditOutput,
+    code: &str,
+    cursor: usize,
+    char_width: f32,
+) {
+    let is_bracket = |c: &char| "()[]{}".contains(*c);
+    let before = code[..cursor]
+        .chars()
+        .next_back()
+        .filter(is_bracket)
+        .map(|c| cursor - c.len_utf8());
+    let after = code[cursor..]
+        .chars()
+        .next()
+        .filter(is_bracket)
+        .map(|_| cursor);
+    let Some((at, partner)) = before
+        .into_iter()
+        .chain(after)
+        .find_map(|at| Some((at, editing::matching_bracket(code, at)?)))
+    else {
+        return;
+    };
+
+    let stroke = Stroke::new(1.0_f32, ui.visuals().selection.stroke.color);
+    for byte in [at, partner] {
+        let ccursor = CCursor::new(code[..byte].chars().count());
+        let rect = output.galley.pos_from_ccursor(ccursor);
+        let rect = Rect::from_min_size(rect.min, vec2(char_width, rect.height()));
+        ui.painter()
+            .rect_stroke(rect.translate(output.galley_pos.to_vec2()), 2.0, stroke);
+    }
+}
+
+/// Returns the index of a clicked candidate
+fn show_completions(
+    ui: &Ui,
+    id: Id,
+    cursor_rect: Rect,
+    completions: &Completions,
+    popup: PopupState,
+) -> Option<usize> {
+    let mut clicked = None;
+    Area::new(id)
+        .order(Order::Foreground)
+        .fixed_pos(cursor_rect.left_bottom())
+        .show(ui.ctx(), |ui| {
+            Frame::popup(ui.style()).show(ui, |ui| {
+                ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                    for (i, candidate) in completions.candidates.iter().enumerate() {
+                        let selected = i == popup.selected;
+                        let label = ui
+                            .selectable_label(selected, RichText::new(&candidate.name).monospace());
+                        if selected {
+                            label.scroll_to_me(None);
+                        }
+                        if label.clicked() {
+                            clicked = Some(i);
+                        }
+                    }
+                });
+                if let Some(candidate) = completions.candidates.get(popup.selected) {
+                    ui.separator();
+                    ui.label(RichText::new(&candidate.detail).monospace().weak());
+                }
+            });
+        });
+    clicked
+}
+
+fn insert_completion(
+    ui: &Ui,
+    id: Id,
+    code: &mut String,
+    completions: &Completions,
+    index: usize,
+    state: &mut egui::text_edit::TextEditState,
+) {
+    let text = completions.candidates[index].insert_text();
+    let replace = completions.replace.clone();
+    code.replace_range(replace.clone(), &text);
+
+    let cursor = code[..replace.start].chars().count() + text.chars().count();
+    state
+        .cursor
+        .set_char_range(Some(CCursorRange::one(CCursor::new(cursor))));
+    TextEdit::store_state(ui.ctx(), id, state.clone());
+    // Clicking the popup took focus from the editor
+    ui.memory_mut(|memory| memory.request_focus(id));
+}
+
+/// The signature above the cursor, with the argument being typed in bold
+fn show_signature_help(ui: &Ui, id: Id, cursor_rect: Rect, help: &SignatureHelp) {
+    Area::new(id)
+        .order(Order::Tooltip)
+        .pivot(Align2::LEFT_BOTTOM)
+        .fixed_pos(cursor_rect.left_top())
+        .interactable(false)
+        .show(ui.ctx(), |ui| {
+            Frame::popup(ui.style()).show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.spacing_mut().item_spacing.x = 0.0;
+                    ui.label(RichText::new(format!("{}(", help.name)).monospace());
+                    for (i, param) in help.params.iter().enumerate() {
+                        if i > 0 {
+                            ui.label(RichText::new(", ").monospace());
+                        }
+                        let param = RichText::new(param).monospace();
+                        ui.label(match i == help.active {
+                            true => param.strong().underline(),
+                            false => param,
+                        });
+                    }
+                    ui.label(RichText::new(format!(") -> {}", help.ty)).monospace());
+                });
+            });
+        });
+}
+
+/// `TextEdit` cursors count chars, strings are indexed by bytes
+fn byte_index(s: &str, char_index: usize) -> usize {
+    s.char_indices().nth(char_index).map_or(s.len(), |(i, _)| i)
+}
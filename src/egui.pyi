@@ -0,0 +1,202 @@
+# Types of the `egui` global that scripts draw with.
+#
+# Kept in sync with the bindings in lib.rs by the `stubs_match_bindings` test.
+# Copy it next to your scripts for completion in other editors.
+
+from typing import Sequence
+
+Point = Vec2 | Pos2 | tuple[float, float] | Sequence[float]
+# 4 ints, 4 floats and hex strings are unmultiplied RGBA, so (255, 0, 0, 128) is the same as
+# (1.0, 0.0, 0.0, 0.5). Premultiplied and HSV colors need Color32.from_rgba_premultiplied,
+# Color32.from_hsv or Color32.from_hsva.
+Color = Color32 | str | tuple[int, int, int] | tuple[int, int, int, int] | tuple[float, float, float] | tuple[float, float, float, float]
+Text = str | RichText | LayoutJob
+
+class PyEgui:
+    Color32: type[Color32]
+    Vec2: type[Vec2]
+    Pos2: type[Pos2]
+    Rect: type[Rect]
+    def button(self, text: Text, fill: Color | None = None, min_size: Point | None = None, rounding: float | None = None) -> PyResponse: ...
+    def label(self, text: Text) -> PyResponse: ...
+    def rich_text(self, text: str) -> RichText: ...
+    def add_font(self, name: str, data: bytes) -> None: ...
+    def fonts(self) -> list[str]: ...
+    def layout_job(self) -> LayoutJob: ...
+    def style(self) -> PyStyle: ...
+    def text_edit_singleline(self, text: str) -> tuple[str, PyResponse]: ...
+    def painter(self) -> PyPainter: ...
+    def input(self) -> PyInput: ...
+    def time(self) -> float: ...
+    def dt(self) -> float: ...
+    def frame_number(self) -> int: ...
+    def animate_bool(self, id: str, value: bool) -> float: ...
+    def animate_value(self, id: str, target: float, duration: float | None = None) -> float: ...
+    def request_repaint(self) -> None: ...
+    def request_repaint_after(self, seconds: float) -> None: ...
+    def allocate_painter(self, desired_size: Point, sense: str = "hover") -> tuple[PyResponse, PyPainter]: ...
+    def table(self, id: str, columns: list[str], rows: Sequence[Sequence[object]], widths: list[float | str | None] | None = None, striped: bool = True, sortable: bool = True, selectable: bool = True, height: float | None = None, row_height: float | None = None, key: int = 0) -> PyTableResponse: ...
+    def plot(self, id: str, height: float | None = None, width: float | None = None, legend: bool = True, x_label: str | None = None, y_label: str | None = None, link: str | None = None, allow_zoom: bool = True, allow_drag: bool = True, data_aspect: float | None = None) -> PyPlot: ...
+
+class PyPainter:
+    def line(self, points: Sequence[Point], stroke_width: float = 1.0, color: Color | None = None) -> None: ...
+    def circle(self, center: Point, radius: float, fill: Color | None = None, stroke_width: float | None = None, stroke_color: Color | None = None) -> None: ...
+    def text(self, pos: Point, anchor: str, text: Text, text_color: Color | None = None, family: str | None = None) -> Rect: ...
+
+class PyResponse:
+    def clicked(self) -> bool: ...
+    def rect(self) -> Rect: ...
+
+class Vec2:
+    def __init__(self, x: float | Point, y: float = ...) -> None: ...
+    x: float
+    y: float
+    def length(self) -> float: ...
+    def normalized(self) -> Vec2: ...
+    def rot90(self) -> Vec2: ...
+    def dot(self, other: Point) -> float: ...
+    def lerp(self, other: Point, t: float) -> Vec2: ...
+    def to_pos2(self) -> Pos2: ...
+    def to_tuple(self) -> tuple[float, float]: ...
+
+class Pos2:
+    def __init__(self, x: float | Point, y: float = ...) -> None: ...
+    x: float
+    y: float
+    def distance(self, other: Point) -> float: ...
+    def lerp(self, other: Point, t: float) -> Pos2: ...
+    def to_vec2(self) -> Vec2: ...
+    def to_tuple(self) -> tuple[float, float]: ...
+
+class Rect:
+    def __init__(self, min: Point, max: Point) -> None: ...
+    @staticmethod
+    def from_min_size(min: Point, size: Point) -> Rect: ...
+    @staticmethod
+    def from_center_size(center: Point, size: Point) -> Rect: ...
+    def min(self) -> Pos2: ...
+    def max(self) -> Pos2: ...
+    def center(self) -> Pos2: ...
+    def width(self) -> float: ...
+    def height(self) -> float: ...
+    def size(self) -> Vec2: ...
+    def contains(self, point: Point) -> bool: ...
+    def expand(self, amount: float) -> Rect: ...
+    def translate(self, offset: Point) -> Rect: ...
+    def intersects(self, other: Rect) -> bool: ...
+    def intersect(self, other: Rect) -> Rect: ...
+    def union(self, other: Rect) -> Rect: ...
+    def lerp(self, t: Point) -> Pos2: ...
+
+class Color32:
+    def __init__(self, color: Color | int, g: int = ..., b: int = ..., a: int = ...) -> None: ...
+    @staticmethod
+    def from_rgb(r: int, g: int, b: int) -> Color32: ...
+    @staticmethod
+    def from_rgba_premultiplied(r: int, g: int, b: int, a: int) -> Color32: ...
+    @staticmethod
+    def from_rgba_unmultiplied(r: int, g: int, b: int, a: int) -> Color32: ...
+    @staticmethod
+    def from_gray(l: int) -> Color32: ...
+    @staticmethod
+    def from_hsv(h: float, s: float, v: float) -> Color32: ...
+    @staticmethod
+    def from_hsva(h: float, s: float, v: float, a: float) -> Color32: ...
+    @staticmethod
+    def from_hex(hex: str) -> Color32: ...
+    r: int
+    g: int
+    b: int
+    a: int
+    def to_tuple(self) -> tuple[int, int, int, int]: ...
+    def to_rgba_unmultiplied(self) -> tuple[int, int, int, int]: ...
+    def to_hsva(self) -> tuple[float, float, float, float]: ...
+    def to_hex(self) -> str: ...
+    def lerp(self, other: Color, t: float) -> Color32: ...
+    def gamma_multiply(self, factor: float) -> Color32: ...
+    def linear_multiply(self, factor: float) -> Color32: ...
+    def to_opaque(self) -> Color32: ...
+
+class PyInput:
+    def key_pressed(self, name: str) -> bool: ...
+    def key_down(self, name: str) -> bool: ...
+    def key_released(self, name: str) -> bool: ...
+    modifiers: PyModifiers
+    pointer: PyPointer
+    scroll_delta: Vec2
+    time: float
+    stable_dt: float
+    screen_rect: Rect
+    text_events: list[str]
+
+class PyPointer:
+    pos: Pos2 | None
+    delta: Vec2
+    velocity: Vec2
+    primary_down: bool
+    secondary_down: bool
+    primary_pressed: bool
+    primary_released: bool
+
+class PyModifiers:
+    alt: bool
+    ctrl: bool
+    shift: bool
+    command: bool
+
+class RichText:
+    text: str
+    def size(self, size: float) -> RichText: ...
+    def strong(self) -> RichText: ...
+    def weak(self) -> RichText: ...
+    def italics(self) -> RichText: ...
+    def underline(self) -> RichText: ...
+    def strikethrough(self) -> RichText: ...
+    def monospace(self) -> RichText: ...
+    def family(self, name: str) -> RichText: ...
+    def heading(self) -> RichText: ...
+    def small(self) -> RichText: ...
+    def color(self, color: Color) -> RichText: ...
+    def background_color(self, color: Color) -> RichText: ...
+
+class LayoutJob:
+    def append(self, text: str | RichText) -> None: ...
+    wrap_width: float | None
+
+class PyStyle:
+    dark_mode: bool
+    text_color: Color32 | None
+    selection_color: Color32
+    hyperlink_color: Color32
+    button_fill: Color32
+    extreme_bg_color: Color32
+    widget_rounding: float
+    item_spacing: Vec2
+    button_padding: Vec2
+    indent: float
+    text_style: str | None
+    def text_size(self, name: str) -> float: ...
+    def set_text_size(self, name: str, size: float) -> None: ...
+
+class PyTableResponse:
+    selected: list[int]
+    clicked: int | None
+    sort: tuple[int, bool] | None
+
+class PyPlot:
+    def line(self, x: Sequence[float], y: Sequence[float] | None = None, name: str | None = None, color: Color | None = None, width: float | None = None) -> None: ...
+    def scatter(self, x: Sequence[float], y: Sequence[float] | None = None, name: str | None = None, color: Color | None = None, width: float | None = None) -> None: ...
+    def bar(self, heights: Sequence[float], x: Sequence[float] | None = None, name: str | None = None, color: Color | None = None, width: float | None = None) -> None: ...
+    def histogram(self, values: Sequence[float], bins: int = 10, name: str | None = None, color: Color | None = None) -> None: ...
+    def box_plot(self, values: Sequence[float], x: float = 0.0, name: str | None = None, color: Color | None = None) -> None: ...
+    def heatmap(self, rows: Sequence[Sequence[float]], x: float = 0.0, y: float = 0.0, name: str | None = None) -> None: ...
+    def set_bounds(self, x_min: float, x_max: float, y_min: float, y_max: float) -> None: ...
+    def reset(self) -> None: ...
+    def show(self) -> PyPlotResponse: ...
+
+class PyPlotResponse:
+    response: PyResponse
+    bounds: tuple[float, float, float, float]
+    pointer: tuple[float, float] | None
+
+egui: PyEgui
@@ -0,0 +1,187 @@
+//! Find and replace for the code editor. Positions are byte offsets into the code.
+
+use std::ops::Range;
+
+use egui::{
+    text::{LayoutJob, LayoutSection},
+    Color32,
+};
+use regex::{Regex, RegexBuilder};
+
+/// What the find bar looks for
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Query {
+    pub text: String,
+    /// `text` is a regular expression, and `$1` or `$name` in replacements refer to
+    /// its groups
+    pub regex: bool,
+    pub case_sensitive: bool,
+    /// Only matches that don't start or end in the middle of a word
+    pub whole_word: bool,
+}
+
+impl Query {
+    /// Every non-empty match in order, or why the regex is invalid
+    pub fn find_all(&self, code: &str) -> Result<Vec<Range<usize>>, String> {
+        let Some(regex) = self.compile()? else {
+            return Ok(vec![]);
+        };
+        Ok(regex
+            .find_iter(code)
+            .map(|found| found.range())
+            .filter(|range| self.accepts(code, range))
+            .collect())
+    }
+
+    /// What the match at exactly `at` becomes, or `None` if `at` isn't a match
+    pub fn replacement(
+        &self,
+        code: &str,
+        at: Range<usize>,
+        replacement: &str,
+    ) -> Result<Option<String>, String> {
+        let Some(regex) = self.compile()? else {
+            return Ok(None);
+        };
+        let Some(captures) = regex.captures_at(code, at.start) else {
+            return Ok(None);
+        };
+        let found = captures.get(0).expect("Group 0 is the whole match");
+        if found.range() != at || !self.accepts(code, &at) {
+            return Ok(None);
+        }
+        Ok(Some(self.expand(&captures, replacement)))
+    }
+
+    /// `code` with every match replaced, and how many there were
+    pub fn replace_all(&self, code: &str, replacement: &str) -> Result<(String, usize), String> {
+        let Some(regex) = self.compile()? else {
+            return Ok((code.to_owned(), 0));
+        };
+        let mut replaced = String::with_capacity(code.len());
+        let mut count = 0;
+        let mut last = 0;
+        for captures in regex.captures_iter(code) {
+            let found = captures.get(0).expect("Group 0 is the whole match");
+            if !self.accepts(code, &found.range()) {
+                continue;
+            }
+            replaced += &code[last..found.start()];
+            replaced += &self.expand(&captures, replacement);
+            last = found.end();
+            count += 1;
+        }
+        replaced += &code[last..];
+        Ok((replaced, count))
+    }
+
+    /// `Ok(None)` when there's nothing to look for
+    fn compile(&self) -> Result<Option<Regex>, String> {
+        if self.text.is_empty() {
+            return Ok(None);
+        }
+        let pattern = match self.regex {
+            true => self.text.clone(),
+            false => regex::escape(&self.text),
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .multi_line(true)
+            .build()
+            .map(Some)
+            .map_err(|err| err.to_string())
+    }
+
+    fn accepts(&self, code: &str, range: &Range<usize>) -> bool {
+        if range.is_empty() {
+            return false;
+        }
+        if !self.whole_word {
+            return true;
+        }
+        // A word continuing past either end of the match
+        let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+        let found = &code[range.clone()];
+        let joined_before =
+            is_word(found.chars().next()) && is_word(code[..range.start].chars().next_back());
+        let joined_after =
+            is_word(found.chars().next_back()) && is_word(code[range.end..].chars().next());
+        !joined_before && !joined_after
+    }
+
+    fn expand(&self, captures: &regex::Captures<'_>, replacement: &str) -> String {
+        if !self.regex {
+            return replacement.to_owned();
+        }
+        let mut expanded = String::new();
+        captures.expand(replacement, &mut expanded);
+        expanded
+    }
+}
+
+/// The first match starting at or after `from`, wrapping around to the first one
+pub fn next(matches: &[Range<usize>], from: usize) -> Option<&Range<usize>> {
+    let after = matches.partition_point(|found| found.start < from);
+    matches.get(after).or(matches.first())
+}
+
+/// The last match starting before `before`, wrapping around to the last one
+pub fn previous(matches: &[Range<usize>], before: usize) -> Option<&Range<usize>> {
+    let after = matches.partition_point(|found| found.start < before);
+    match after {
+        0 => matches.last(),
+        after => matches.get(after - 1),
+    }
+}
+
+/// Give the text of `matches` a `background`, and the `current` one a stronger one,
+/// splitting the highlighted sections where needed
+pub fn mark_matches(
+    job: &mut LayoutJob,
+    matches: &[Range<usize>],
+    current: Option<&Range<usize>>,
+    background: Color32,
+    current_background: Color32,
+) {
+    if matches.is_empty() {
+        return;
+    }
+    let mut sections = Vec::with_capacity(job.sections.len() + 2 * matches.len());
+    for section in job.sections.drain(..) {
+        let end = section.byte_range.end;
+        let mut start = section.byte_range.start;
+        let mut leading_space = section.leading_space;
+        let mut push = |range: Range<usize>, background: Option<Color32>| {
+            let mut format = section.format.clone();
+            if let Some(background) = background {
+                format.background = background;
+            }
+            sections.push(LayoutSection {
+                leading_space: std::mem::take(&mut leading_space),
+                byte_range: range,
+                format,
+            });
+        };
+
+        let first = matches.partition_point(|found| found.end <= start);
+        for found in matches[first..]
+            .iter()
+            .take_while(|found| found.start < end)
+        {
+            let (from, to) = (found.start.max(start), found.end.min(end));
+            if from > start {
+                push(start..from, None);
+            }
+            let background = match current == Some(found) {
+                true => current_background,
+                false => background,
+            };
+            push(from..to, Some(background));
+            start = to;
+        }
+        if start < end {
+            push(start..end, None);
+        }
+    }
+    job.sections = sections;
+}
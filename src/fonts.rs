@@ -0,0 +1,120 @@
+//! Fonts added by the project or the script, on top of egui's defaults
+
+use std::collections::BTreeMap;
+
+use egui::{FontData, FontDefinitions, FontFamily};
+
+/// The largest font file a project may embed, enough for a CJK font. The project is
+/// stored whole on every save, so this keeps a few fonts from slowing every save down.
+pub const MAX_PROJECT_FONT_SIZE: usize = 32 * 1024 * 1024;
+
+/// A font embedded in the project file
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProjectFont {
+    /// Also the name of the `FontFamily` it provides
+    pub name: String,
+    /// TTF or OTF file contents, stored as base64 since serde would write a list of numbers
+    #[serde(with = "base64_data")]
+    pub data: Vec<u8>,
+}
+
+mod base64_data {
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+    use serde::{Deserialize as _, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(data))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD.decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct FontRegistry {
+    fonts: BTreeMap<String, Vec<u8>>,
+}
+
+impl FontRegistry {
+    /// Returns whether anything changed. Fails if `data` isn't a font egui can load,
+    /// since egui would panic on it instead.
+    pub fn insert(&mut self, name: &str, data: Vec<u8>) -> Result<bool, String> {
+        if name.is_empty() || is_builtin_family(name) {
+            return Err(format!("{name:?} can't be used as a font name"));
+        }
+        ab_glyph::FontRef::try_from_slice(&data)
+            .map_err(|e| format!("{name} is not a TTF or OTF font: {e}"))?;
+
+        if self.fonts.get(name) == Some(&data) {
+            return Ok(false);
+        }
+        self.fonts.insert(name.to_owned(), data);
+        Ok(true)
+    }
+
+    pub fn clear(&mut self) {
+        self.fonts.clear();
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.fonts.keys().map(|name| name.as_str())
+    }
+
+    /// The default fonts, plus one family per registered font. Registered fonts are also
+    /// fallbacks for the default families, for glyphs like CJK which those lack.
+    pub fn definitions(&self) -> FontDefinitions {
+        let mut definitions = FontDefinitions::default();
+        let proportional = definitions.families[&FontFamily::Proportional].clone();
+
+        for (name, data) in &self.fonts {
+            definitions
+                .font_data
+                .insert(name.clone(), FontData::from_owned(data.clone()));
+
+            let family = std::iter::once(name.clone())
+                .chain(proportional.iter().cloned())
+                .collect();
+            definitions
+                .families
+                .insert(FontFamily::Name(name.as_str().into()), family);
+
+            for fallbacks in [FontFamily::Proportional, FontFamily::Monospace] {
+                definitions
+                    .families
+                    .entry(fallbacks)
+                    .or_default()
+                    .push(name.clone());
+            }
+        }
+
+        definitions
+    }
+
+    /// Takes effect from the next frame
+    pub fn apply(&self, ctx: &egui::Context) {
+        ctx.set_fonts(self.definitions());
+    }
+}
+
+fn is_builtin_family(name: &str) -> bool {
+    matches!(name, "Proportional" | "Monospace")
+}
+
+/// The family called `name`. Fonts only become usable the frame after they are added,
+/// and egui panics on unknown families, so until then this is the proportional font.
+pub fn resolve_family(ctx: &egui::Context, name: &str) -> FontFamily {
+    match name {
+        "Proportional" => FontFamily::Proportional,
+        "Monospace" => FontFamily::Monospace,
+        name => {
+            let family = FontFamily::Name(name.into());
+            if ctx.fonts(|fonts| fonts.families().contains(&family)) {
+                family
+            } else {
+                FontFamily::Proportional
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Running scripts against an offscreen `egui::Context`, with no window or GPU
+
+use egui::{CentralPanel, ColorImage, Context, Event, Frame, FullOutput, RawInput, Rect, Vec2};
+
+use crate::{
+    completion::{Global, Topic},
+    snapshot::SoftwareRenderer,
+    ProjectFont, Runtime, RuntimePolicy,
+};
+
+/// Frames are spaced as if the app was running at this rate
+const FRAME_DT: f32 = 1.0 / 60.0;
+
+/// An input event to feed to the script on a given frame
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub struct ScriptedEvent {
+    pub frame: usize,
+    pub event: Event,
+}
+
+/// What the script did during one frame
+pub struct FrameReport {
+    pub frame: usize,
+    pub stdout: String,
+    pub error: Option<String>,
+    pub notice: Option<String>,
+    pub output: FullOutput,
+}
+
+pub struct Headless {
+    ctx: Context,
+    runtime: Runtime,
+    screen_size: Vec2,
+    frame: usize,
+    renderer: SoftwareRenderer,
+}
+
+impl Headless {
+    pub fn new(code: String, policy: RuntimePolicy, screen_size: Vec2) -> Self {
+        let mut runtime = Runtime::new(policy);
+        runtime.load(code);
+
+        Self {
+            ctx: Context::default(),
+            runtime,
+            screen_size,
+            frame: 0,
+            renderer: SoftwareRenderer::default(),
+        }
+    }
+
+    pub fn ctx(&self) -> &Context {
+        &self.ctx
+    }
+
+    /// Where the script is drawn: the screen, inside the central panel's margin
+    pub fn script_area(&self) -> Rect {
+        let screen_rect = Rect::from_min_size(Default::default(), self.screen_size);
+        screen_rect - Frame::central_panel(&self.ctx.style()).inner_margin
+    }
+
+    /// Resize the screen so the script's area is `size`, like that of a recording
+    pub fn fit_script_area(&mut self, size: Vec2) {
+        self.screen_size = size + Frame::central_panel(&self.ctx.style()).inner_margin.sum();
+    }
+
+    /// Run the script for one frame, with the given input
+    pub fn step(&mut self, events: Vec<Event>) -> FrameReport {
+        let raw_input = RawInput {
+            screen_rect: Some(Rect::from_min_size(Default::default(), self.screen_size)),
+            time: Some(self.frame as f64 * FRAME_DT as f64),
+            predicted_dt: FRAME_DT,
+            events,
+            ..Default::default()
+        };
+
+        let runtime = &mut self.runtime;
+        let output = self.ctx.run(raw_input, |ctx| {
+            CentralPanel::default().show(ctx, |ui| {
+                runtime.set_egui(ui);
+                runtime.run_loaded_code();
+                runtime.take_up_egui_space(ui);
+            });
+        });
+        self.renderer.update_textures(&output.textures_delta);
+
+        let report = FrameReport {
+            frame: self.frame,
+            stdout: self.runtime.stdout().borrow().clone(),
+            error: self.runtime.error().map(str::to_owned),
+            notice: self.runtime.notice().map(str::to_owned),
+            output,
+        };
+        self.frame += 1;
+        report
+    }
+
+    /// Load the project's fonts, as the app does when a project is opened. They can be
+    /// used from the next frame.
+    pub fn load_fonts(&self, fonts: &[ProjectFont]) -> Result<(), String> {
+        self.runtime.load_fonts(fonts, &self.ctx)
+    }
+
+    /// The script's globals after the last frame, as the editor completes them
+    pub fn globals(&self) -> Vec<Global> {
+        self.runtime.globals().to_vec()
+    }
+
+    /// What the docs panel shows for `topic`
+    pub fn help(&self, topic: &Topic) -> Option<String> {
+        self.runtime.help(topic)
+    }
+
+    /// Rasterize a frame's output in software
+    pub fn render(&self, output: &FullOutput) -> ColorImage {
+        let pixels_per_point = output.pixels_per_point;
+        let primitives = self.ctx.tessellate(output.shapes.clone(), pixels_per_point);
+        let size = (self.screen_size * pixels_per_point).round();
+        self.renderer.render(
+            &primitives,
+            pixels_per_point,
+            [size.x as usize, size.y as usize],
+        )
+    }
+
+    /// Run `frames` frames, feeding in each scripted event on its frame
+    pub fn run(&mut self, frames: usize, events: &[ScriptedEvent]) -> Vec<FrameReport> {
+        (0..frames)
+            .map(|_| {
+                let frame_events = events
+                    .iter()
+                    .filter(|scripted| scripted.frame == self.frame)
+                    .map(|scripted| scripted.event.clone())
+                    .collect();
+                self.step(frame_events)
+            })
+            .collect()
+    }
+}
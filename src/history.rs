@@ -0,0 +1,366 @@
+//! Edit history of the project's code: undo and redo that are saved with the project,
+//! named checkpoints, and a window for going back to any of them
+
+use egui::{Button, RichText, ScrollArea, Ui};
+
+/// Edits closer together than this are one step of undo
+const MERGE_SECONDS: f64 = 1.0;
+
+/// Oldest versions are dropped beyond this, since the project is saved as a whole
+const MAX_VERSIONS: usize = 100;
+
+/// Most bytes of code kept in versions and checkpoints together, for the same reason
+const MAX_BYTES: usize = 2 << 20;
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq)]
+pub struct Version {
+    pub code: String,
+    /// Seconds since the Unix epoch of the last edit in this version
+    pub time: f64,
+}
+
+/// A version in the history window
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Entry {
+    Version(usize),
+    Checkpoint(usize),
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Default)]
+#[serde(from = "Saved")]
+pub struct History {
+    /// Undo steps, oldest first
+    versions: Vec<Version>,
+    /// The version the editor shows. Later versions are redo steps.
+    current: usize,
+    /// Named versions, kept until removed
+    checkpoints: Vec<(String, Version)>,
+    /// When the last edit was recorded, if the next one may join its version
+    #[serde(skip)]
+    last_edit: Option<f64>,
+}
+
+/// A history as saved, which may not be consistent if the save was edited or corrupted
+#[derive(serde::Deserialize, Default)]
+#[serde(default)]
+struct Saved {
+    versions: Vec<Version>,
+    current: usize,
+    checkpoints: Vec<(String, Version)>,
+}
+
+impl From<Saved> for History {
+    fn from(saved: Saved) -> Self {
+        let mut history = Self {
+            current: saved.current.min(saved.versions.len().saturating_sub(1)),
+            versions: saved.versions,
+            checkpoints: saved.checkpoints,
+            last_edit: None,
+        };
+        history.trim();
+        history
+    }
+}
+
+impl History {
+    /// Make `code` the current version, as a step of its own, e.g. for a project
+    /// saved before it had a history
+    pub fn sync(&mut self, code: &str, now: f64) {
+        self.push(code, now, false);
+    }
+
+    /// Record an edit in the editor. Edits in quick succession are undone together.
+    pub fn edit(&mut self, code: &str, now: f64) {
+        self.push(code, now, true);
+    }
+
+    /// Go back to `code` from the history window, which can be undone like an edit
+    pub fn restore(&mut self, code: &str, now: f64) {
+        self.push(code, now, false);
+    }
+
+    /// The code one step back, if there is any
+    pub fn undo(&mut self) -> Option<&str> {
+        self.last_edit = None;
+        self.current = self.current.checked_sub(1)?;
+        Some(&self.versions[self.current].code)
+    }
+
+    /// The code one step forward again, if an undo hasn't been followed by an edit
+    pub fn redo(&mut self) -> Option<&str> {
+        self.last_edit = None;
+        if self.current + 1 >= self.versions.len() {
+            return None;
+        }
+        self.current += 1;
+        Some(&self.versions[self.current].code)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.current > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.current + 1 < self.versions.len()
+    }
+
+    /// Undo steps oldest first, with the index of the current one
+    pub fn versions(&self) -> (&[Version], usize) {
+        (&self.versions, self.current)
+    }
+
+    pub fn checkpoints(&self) -> &[(String, Version)] {
+        &self.checkpoints
+    }
+
+    /// Save `code` under `name`, replacing a checkpoint with that name. Returns false if
+    /// there is no room for it, even after dropping every undo step but the current one.
+    pub fn checkpoint(&mut self, name: &str, code: &str, now: f64) -> bool {
+        let others: usize = self
+            .checkpoints
+            .iter()
+            .filter(|(n, _)| n != name)
+            .map(|(n, version)| n.len() + version.code.len())
+            .sum();
+        let current = self.versions.get(self.current).map_or(0, |v| v.code.len());
+        if others + name.len() + code.len() + current > MAX_BYTES {
+            return false;
+        }
+
+        let version = Version {
+            code: code.to_owned(),
+            time: now,
+        };
+        match self.checkpoints.iter_mut().find(|(n, _)| n == name) {
+            Some((_, existing)) => *existing = version,
+            None => self.checkpoints.push((name.to_owned(), version)),
+        }
+        self.trim();
+        true
+    }
+
+    pub fn remove_checkpoint(&mut self, index: usize) {
+        if index < self.checkpoints.len() {
+            self.checkpoints.remove(index);
+        }
+    }
+
+    pub fn get(&self, entry: Entry) -> Option<&Version> {
+        match entry {
+            Entry::Version(i) => self.versions.get(i),
+            Entry::Checkpoint(i) => self.checkpoints.get(i).map(|(_, version)| version),
+        }
+    }
+
+    fn push(&mut self, code: &str, now: f64, merge: bool) {
+        let last_edit = std::mem::replace(&mut self.last_edit, merge.then_some(now));
+        if self
+            .versions
+            .get(self.current)
+            .is_some_and(|v| v.code == code)
+        {
+            return;
+        }
+        // Editing after undoing drops what could have been redone
+        self.versions.truncate(self.current + 1);
+
+        let recent = last_edit.is_some_and(|last| now - last <= MERGE_SECONDS);
+        // The first version is what the code was before any edit, so keep it
+        if merge && recent && self.current > 0 {
+            let version = &mut self.versions[self.current];
+            version.code = code.to_owned();
+            version.time = now;
+            return;
+        }
+
+        self.versions.push(Version {
+            code: code.to_owned(),
+            time: now,
+        });
+        self.current = self.versions.len() - 1;
+        self.trim();
+    }
+
+    /// Drop the oldest undo steps, then redo steps, until the history is within its
+    /// limits. The current version is always kept.
+    fn trim(&mut self) {
+        let mut bytes: usize = self
+            .checkpoints
+            .iter()
+            .map(|(name, version)| name.len() + version.code.len())
+            .chain(self.versions.iter().map(|version| version.code.len()))
+            .sum();
+        let over = |bytes: usize, versions: usize| bytes > MAX_BYTES || versions > MAX_VERSIONS;
+        while self.current > 0 && over(bytes, self.versions.len()) {
+            bytes -= self.versions.remove(0).code.len();
+            self.current -= 1;
+        }
+        while self.versions.len() > self.current + 1 && over(bytes, self.versions.len()) {
+            bytes -= self.versions.pop().map_or(0, |version| version.code.len());
+        }
+    }
+
+    /// The history window's contents. Returns the code to switch the editor to, which
+    /// has been recorded already.
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        browser: &mut Browser,
+        code: &str,
+        now: f64,
+    ) -> Option<String> {
+        let mut switch_to = None;
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(self.can_undo(), Button::new("Undo (CTRL + Z)"))
+                .clicked()
+            {
+                switch_to = self.undo().map(str::to_owned);
+            }
+            if ui
+                .add_enabled(self.can_redo(), Button::new("Redo (CTRL + Y)"))
+                .clicked()
+            {
+                switch_to = self.redo().map(str::to_owned);
+            }
+        });
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut browser.checkpoint_name)
+                    .hint_text("Checkpoint name")
+                    .desired_width(160.0),
+            );
+            let name = browser.checkpoint_name.trim();
+            if ui
+                .add_enabled(!name.is_empty(), Button::new("Save checkpoint"))
+                .clicked()
+            {
+                browser.status = (!self.checkpoint(name, code, now))
+                    .then(|| "No room left, remove some checkpoints first".to_owned());
+                browser.checkpoint_name.clear();
+            }
+        });
+        if let Some(status) = &browser.status {
+            ui.colored_label(ui.visuals().warn_fg_color, status);
+        }
+
+        ui.separator();
+        ui.columns(2, |columns| {
+            ScrollArea::vertical()
+                .id_salt("entries")
+                .show(&mut columns[0], |ui| {
+                    ui.strong("Checkpoints");
+                    let mut remove = None;
+                    if self.checkpoints.is_empty() {
+                        ui.weak("None yet");
+                    }
+                    for (i, (name, version)) in self.checkpoints.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            let entry = Entry::Checkpoint(i);
+                            let label = format!("{name}, {}", ago(now - version.time));
+                            if ui
+                                .selectable_label(browser.selected == Some(entry), label)
+                                .clicked()
+                            {
+                                browser.selected = Some(entry);
+                            }
+                            if ui.small_button("Remove").clicked() {
+                                remove = Some(i);
+                            }
+                        });
+                    }
+                    if let Some(i) = remove {
+                        self.remove_checkpoint(i);
+                        browser.selected = None;
+                    }
+
+                    ui.separator();
+                    ui.strong("Edits");
+                    for (i, version) in self.versions.iter().enumerate().rev() {
+                        let entry = Entry::Version(i);
+                        let mut label = format!(
+                            "{}, {} lines",
+                            ago(now - version.time),
+                            version.code.lines().count()
+                        );
+                        if i == self.current {
+                            label += " (current)";
+                        }
+                        if ui
+                            .selectable_label(browser.selected == Some(entry), label)
+                            .clicked()
+                        {
+                            browser.selected = Some(entry);
+                        }
+                    }
+                });
+
+            let ui = &mut columns[1];
+            match browser.selected.and_then(|entry| self.get(entry)).cloned() {
+                Some(version) => {
+                    if ui
+                        .add_enabled(version.code != code, Button::new("Restore this version"))
+                        .clicked()
+                    {
+                        self.restore(&version.code, now);
+                        switch_to = Some(version.code.clone());
+                        browser.selected = None;
+                    }
+                    ScrollArea::both().id_salt("preview").show(ui, |ui| {
+                        ui.label(RichText::new(version.code).monospace());
+                    });
+                }
+                None => {
+                    ui.weak("Pick a version to see its code");
+                }
+            }
+        });
+        switch_to
+    }
+}
+
+/// What the history window shows, apart from the project
+#[derive(Debug, Default)]
+pub struct Browser {
+    pub open: bool,
+    checkpoint_name: String,
+    selected: Option<Entry>,
+    /// Why the last checkpoint couldn't be saved
+    status: Option<String>,
+}
+
+/// The byte in `after` just past what changed from `before`, for putting the cursor
+/// there after an undo or redo
+pub fn edit_end(before: &str, after: &str) -> usize {
+    let prefix = before
+        .char_indices()
+        .zip(after.chars())
+        .find(|((_, a), b)| a != b)
+        .map_or(before.len().min(after.len()), |((i, _), _)| i);
+    let suffix = before[prefix..]
+        .chars()
+        .rev()
+        .zip(after[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum::<usize>();
+    after.len() - suffix
+}
+
+/// Seconds as "just now", "5 min ago" and so on
+pub fn ago(seconds: f64) -> String {
+    // Truncation is intended, and clocks going backwards are "just now"
+    let seconds = seconds.max(0.0) as u64;
+    match seconds {
+        0..=9 => "just now".to_owned(),
+        10..=59 => format!("{seconds} s ago"),
+        60..=3599 => format!("{} min ago", seconds / 60),
+        3600..=86399 => format!("{} h ago", seconds / 3600),
+        _ => match seconds / 86400 {
+            1 => "1 day ago".to_owned(),
+            days => format!("{days} days ago"),
+        },
+    }
+}
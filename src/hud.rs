@@ -0,0 +1,143 @@
+use std::collections::VecDeque;
+
+use egui::{Color32, Pos2, Rect, Sense, Shape, Stroke, Ui};
+use web_time::{Duration, Instant};
+
+/// How many frames of history the graph keeps
+const HISTORY_LEN: usize = 240;
+
+/// Timings gathered by the runtime for the most recent load and run
+#[derive(Default, Clone, Copy, Debug)]
+pub struct RuntimeTimings {
+    /// Time spent compiling in `Runtime::load`
+    pub compile: Duration,
+    /// Time spent executing in `Runtime::run_loaded_code` and the `Runtime::run_steps`
+    /// after it
+    pub execute: Duration,
+    /// Number of shapes the script emitted into its child `Ui` during the last run
+    pub shapes: usize,
+}
+
+/// Overlay showing script timings and the app frame rate
+#[derive(Default)]
+pub struct PerfHud {
+    last_frame: Option<Instant>,
+    /// Seconds between frames
+    frame_times: VecDeque<f32>,
+    /// Seconds spent executing the script, one entry per run
+    execute_times: VecDeque<f32>,
+}
+
+impl PerfHud {
+    /// Call once at the start of each app frame
+    pub fn begin_frame(&mut self) {
+        let now = Instant::now();
+        if let Some(last) = self.last_frame.replace(now) {
+            push_history(&mut self.frame_times, (now - last).as_secs_f32());
+        }
+    }
+
+    /// Call after the script has been run this frame
+    pub fn record_run(&mut self, timings: &RuntimeTimings) {
+        push_history(&mut self.execute_times, timings.execute.as_secs_f32());
+    }
+
+    pub fn show(&self, ctx: &egui::Context, open: &mut bool, timings: &RuntimeTimings) {
+        egui::Window::new("Performance")
+            .open(open)
+            .resizable(false)
+            .default_width(260.0)
+            .anchor(egui::Align2::RIGHT_TOP, [-8.0, 32.0])
+            .show(ctx, |ui| {
+                egui::Grid::new("perf_hud_grid")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Frame rate");
+                        ui.monospace(format!("{:.1} fps", self.fps()));
+                        ui.end_row();
+
+                        ui.label("Compile");
+                        ui.monospace(format_ms(timings.compile));
+                        ui.end_row();
+
+                        ui.label("Execute");
+                        ui.monospace(format_ms(timings.execute));
+                        ui.end_row();
+
+                        ui.label("Shapes");
+                        ui.monospace(timings.shapes.to_string());
+                        ui.end_row();
+                    });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.colored_label(FRAME_COLOR, "frame");
+                    ui.colored_label(EXECUTE_COLOR, "execute");
+                    ui.weak(format!("(max {:.1} ms)", self.graph_max() * 1000.0));
+                });
+                self.graph(ui);
+            });
+    }
+
+    fn fps(&self) -> f32 {
+        if self.frame_times.is_empty() {
+            return 0.0;
+        }
+        let mean = self.frame_times.iter().sum::<f32>() / self.frame_times.len() as f32;
+        if mean > 0.0 {
+            1.0 / mean
+        } else {
+            0.0
+        }
+    }
+
+    fn graph_max(&self) -> f32 {
+        self.frame_times
+            .iter()
+            .chain(&self.execute_times)
+            .copied()
+            .fold(1.0 / 60.0, f32::max)
+    }
+
+    fn graph(&self, ui: &mut Ui) {
+        let desired_size = egui::vec2(ui.available_width(), 64.0);
+        let (resp, paint) = ui.allocate_painter(desired_size, Sense::hover());
+        let rect = resp.rect;
+
+        paint.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+        let max = self.graph_max();
+        paint.add(history_line(rect, &self.frame_times, max, FRAME_COLOR));
+        paint.add(history_line(rect, &self.execute_times, max, EXECUTE_COLOR));
+    }
+}
+
+const FRAME_COLOR: Color32 = Color32::LIGHT_BLUE;
+const EXECUTE_COLOR: Color32 = Color32::LIGHT_RED;
+
+fn push_history(history: &mut VecDeque<f32>, value: f32) {
+    if history.len() == HISTORY_LEN {
+        history.pop_front();
+    }
+    history.push_back(value);
+}
+
+fn history_line(rect: Rect, history: &VecDeque<f32>, max: f32, color: Color32) -> Shape {
+    let dx = rect.width() / (HISTORY_LEN - 1) as f32;
+    // Right-align the history so the newest sample is always at the edge
+    let offset = HISTORY_LEN - history.len();
+    let points = history
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let x = rect.left() + (offset + i) as f32 * dx;
+            let y = rect.bottom() - (value / max).min(1.0) * rect.height();
+            Pos2::new(x, y)
+        })
+        .collect();
+    Shape::line(points, Stroke::new(1.0_f32, color))
+}
+
+fn format_ms(duration: Duration) -> String {
+    format!("{:.2} ms", duration.as_secs_f64() * 1000.0)
+}
@@ -1,61 +1,164 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 mod app;
-use std::{cell::RefCell, rc::Rc};
+mod color;
+pub mod completion;
+mod docs;
+pub mod editing;
+mod editor;
+pub mod find;
+mod fonts;
+pub mod headless;
+pub mod history;
+mod hud;
+mod memory;
+pub mod playback;
+mod plot;
+mod policy;
+pub mod simulation;
+pub mod snapshot;
+mod table;
+use std::{
+    any::Any,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    panic::{catch_unwind, AssertUnwindSafe},
+    rc::Rc,
+};
 
 pub use app::TemplateApp;
-use egui::{Stroke, Ui};
+use completion::{Global, Topic};
+use egui::{epaint::ClippedShape, Stroke, Ui};
+use fonts::FontRegistry;
+pub use fonts::ProjectFont;
+use hud::RuntimeTimings;
+pub use memory::CountingAllocator;
+use memory::MemoryBudget;
+pub use policy::RuntimePolicy;
 use rust_py_module::PyEgui;
 use rustpython_vm::{
-    builtins::{PyCode, PyStrRef},
+    builtins::{PyCode, PyStr, PyStrRef},
     compiler::Mode,
-    function::IntoPyNativeFn,
     pyclass, pymodule,
     scope::Scope,
-    Interpreter, PyObjectRef, PyPayload, PyRef, PyResult, VirtualMachine,
+    AsObject, Interpreter, PyObjectRef, PyPayload, PyRef, PyResult, VirtualMachine,
 };
+use web_time::Instant;
 
 struct Runtime {
     interpreter: Interpreter,
     scope: Scope,
     output: Rc<RefCell<String>>,
     error: Option<String>,
+    /// Explains why the runtime was rebuilt behind the user's back
+    notice: Option<String>,
+    /// Explains why the runtime isn't set up as asked. Unlike `notice`, loading new code
+    /// doesn't clear it.
+    setup_notice: Option<String>,
     code: String,
     code_obj: Option<PyRef<PyCode>>,
     child_ui: Option<Rc<RefCell<Ui>>>,
+    timings: RuntimeTimings,
+    policy: RuntimePolicy,
+    /// Keeps `policy` in force for the native types the interpreter shares
+    _installed_policy: policy::InstalledPolicy,
+    memory: MemoryBudget,
+    /// When the script last asked to be run again, in `egui::InputState::time` seconds
+    next_run: Rc<Cell<Option<f64>>>,
+    last_output: Option<ScriptOutput>,
+    /// Whether runs keep what they painted in `last_output`, which costs a copy of it
+    keep_output: bool,
+    fonts: Rc<RefCell<FontRegistry>>,
+    /// Outlives each frame's `PyEgui`, but not the interpreter, since it holds its objects
+    tables: Rc<RefCell<HashMap<String, rust_py_module::TableCache>>>,
+    /// The globals as of the last run, worked out when first asked for
+    globals: RefCell<Option<Rc<[Global]>>>,
+}
+
+/// What the script painted on its last run, so it can be shown again without running it
+struct ScriptOutput {
+    origin: egui::Pos2,
+    size: egui::Vec2,
+    shapes: Vec<ClippedShape>,
 }
 
 //use rust_py_module::PyEguiResponse;
 
-trait UnwrapException<T> {
-    fn unwrap_exception(self, vm: &VirtualMachine) -> T;
+/// A failure in the host's glue code around the interpreter, as opposed to an
+/// exception raised by the script itself
+#[derive(Debug)]
+pub struct RuntimeError {
+    traceback: String,
 }
 
-impl<T> UnwrapException<T> for PyResult<T> {
-    #[track_caller]
-    fn unwrap_exception(self, vm: &VirtualMachine) -> T {
-        match self {
-            Ok(v) => v,
-            Err(e) => {
-                let mut s = String::new();
-                vm.write_exception(&mut s, &e)
-                    .expect("Failed to write exception");
-                panic!("{}", s);
+impl std::fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Internal runtime error:\n{}", self.traceback)
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+trait OrRuntimeError<T> {
+    fn or_runtime_error(self, vm: &VirtualMachine) -> Result<T, RuntimeError>;
+}
+
+impl<T> OrRuntimeError<T> for PyResult<T> {
+    fn or_runtime_error(self, vm: &VirtualMachine) -> Result<T, RuntimeError> {
+        self.map_err(|e| {
+            let mut traceback = String::new();
+            if vm.write_exception(&mut traceback, &e).is_err() {
+                traceback = "<failed to write exception>".to_owned();
             }
-        }
+            RuntimeError { traceback }
+        })
     }
 }
 
-fn anon_object(vm: &VirtualMachine, name: &str) -> PyObjectRef {
-    let py_type = vm.builtins.get_attr("type", vm).unwrap_exception(vm);
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(|s| s.as_str()))
+        .unwrap_or("unknown panic")
+}
+
+fn anon_object(vm: &VirtualMachine, name: &str) -> Result<PyObjectRef, RuntimeError> {
+    let py_type = vm.builtins.get_attr("type", vm).or_runtime_error(vm)?;
     let args = (name, vm.ctx.new_tuple(vec![]), vm.ctx.new_dict());
-    py_type.call(args, vm).unwrap_exception(vm)
+    py_type.call(args, vm).or_runtime_error(vm)
 }
 
 impl Runtime {
-    pub fn new() -> Self {
+    /// Falls back to the default policy if the interpreter can't be set up under `policy`
+    pub fn new(policy: RuntimePolicy) -> Self {
+        match Self::try_new(policy) {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                let mut runtime = Self::try_new(RuntimePolicy::default())
+                    .expect("Failed to set up the interpreter with the default policy");
+                runtime.setup_notice = Some(format!(
+                    "Could not apply the project's sandbox policy, using the default\n{e}"
+                ));
+                runtime
+            }
+        }
+    }
+
+    pub fn try_new(policy: RuntimePolicy) -> Result<Self, RuntimeError> {
+        let mut memory = None;
         let interpreter = Interpreter::with_init(Default::default(), |vm| {
-            vm.add_native_modules(rustpython_stdlib::get_module_inits());
+            memory = Some(MemoryBudget::new(
+                policy.max_memory_mb.saturating_mul(1024 * 1024),
+                vm,
+            ));
+
+            vm.add_native_modules(
+                rustpython_stdlib::get_module_inits()
+                    .filter(|(name, _)| policy.allows_module(name)),
+            );
+            // The VM brings its own `os`, `_io` and friends, whatever the policy says
+            policy.strip_modules(vm);
             vm.add_native_module(
                 "rust_py_module".to_owned(),
                 Box::new(rust_py_module::make_module),
@@ -68,34 +171,39 @@ impl Runtime {
 
         let output = Rc::new(RefCell::new(String::new()));
 
-        let scope = interpreter.enter(|vm| {
+        let (scope, installed_policy) = interpreter.enter(|vm| {
             // Create scope
             let scope = vm.new_scope_with_builtins();
 
             // Set stdout hook
-            let sys = vm.import("sys", 0).unwrap_exception(vm);
-            let _ = vm.import("rust_py_module", 0).unwrap_exception(vm);
-            let _ = vm.import("ndarray", 0).unwrap_exception(vm);
+            let sys = vm.import("sys", 0).or_runtime_error(vm)?;
+            let _ = vm.import("rust_py_module", 0).or_runtime_error(vm)?;
+            let _ = vm.import("ndarray", 0).or_runtime_error(vm)?;
 
-            let stdout = anon_object(vm, "InternalStdout");
+            let stdout = anon_object(vm, "InternalStdout")?;
 
             let output_c = output.clone();
             let writer = vm.new_function("write", move |s: String| {
                 *output_c.borrow_mut() += &s;
             });
 
-            stdout.set_attr("write", writer, vm).unwrap_exception(vm);
+            stdout.set_attr("write", writer, vm).or_runtime_error(vm)?;
 
             sys.set_attr("stdout", stdout.clone(), vm)
-                .unwrap_exception(vm);
+                .or_runtime_error(vm)?;
 
             // Import a library
-            //import_source(vm, "euclid", include_str!("./euclid/euclid.py")).unwrap_exception(vm);
+            //import_source(vm, "euclid", include_str!("./euclid/euclid.py")).or_runtime_error(vm)?;
 
-            scope
-        });
+            docs::install(vm).or_runtime_error(vm)?;
+
+            // Everything the host needs is imported, so lock the rest down
+            let installed_policy = policy.install(vm).or_runtime_error(vm)?;
+
+            Ok::<_, RuntimeError>((scope, installed_policy))
+        })?;
 
-        Self {
+        Ok(Self {
             child_ui: None,
             code: r#"# The same scope is used each frame (unless reset)
 # So we can declare variables using something like:
@@ -114,11 +222,24 @@ print(f"You cannot {s}")"#
             scope,
             output,
             error: None,
+            notice: None,
+            setup_notice: None,
             code_obj: None,
-        }
+            timings: RuntimeTimings::default(),
+            policy,
+            _installed_policy: installed_policy,
+            memory: memory.expect("Interpreter init was not run"),
+            next_run: Rc::new(Cell::new(None)),
+            last_output: None,
+            keep_output: false,
+            fonts: Default::default(),
+            tables: Default::default(),
+            globals: Default::default(),
+        })
     }
 
     pub fn load(&mut self, code: String) {
+        let start = Instant::now();
         self.interpreter.enter(|vm| {
             let code_obj = vm.compile(&code, Mode::Exec, "the code you just wrote in the thingy".to_owned());
             match code_obj {
@@ -130,7 +251,9 @@ print(f"You cannot {s}")"#
                 }
             }
         });
+        self.timings.compile = start.elapsed();
         self.code = code;
+        self.notice = None;
     }
 
     pub fn run_loaded_code(&mut self) {
@@ -140,31 +263,188 @@ print(f"You cannot {s}")"#
 
         self.output.borrow_mut().clear();
         self.error = None;
+        // The script asks again during this run if it wants another one
+        self.next_run.set(None);
+
+        let shapes_before = self.emitted_shape_count();
+        let start = Instant::now();
 
         let scope = self.scope.clone();
-        self.error = self.interpreter.enter(move |vm| {
-            if let Err(exec_err) = vm.run_code_obj(code, scope) {
-                let mut s = String::new();
-                vm.write_exception(&mut s, &exec_err).unwrap();
-                Some(s)
-            } else {
-                None
+        self.execute(move |vm| vm.run_code_obj(code, scope).map(drop));
+
+        self.timings.execute = start.elapsed();
+        self.timings.shapes = self.emitted_shape_count().saturating_sub(shapes_before);
+        if self.keep_output {
+            self.last_output = self.capture_output(shapes_before);
+        }
+    }
+
+    /// Call the script's `step(dt)` function `steps` times, appending to this frame's stdout.
+    /// Does nothing if the script has no `step`, or already failed this frame.
+    pub fn run_steps(&mut self, dt: f64, steps: usize) {
+        if steps == 0 || self.error.is_some() || self.code_obj.is_none() {
+            return;
+        }
+
+        let start = Instant::now();
+        let scope = self.scope.clone();
+        self.execute(move |vm| {
+            let Some(step) = scope.globals.get_item_opt("step", vm)? else {
+                return Ok(());
+            };
+            for _ in 0..steps {
+                step.call((dt,), vm)?;
+            }
+            Ok(())
+        });
+        self.timings.execute += start.elapsed();
+    }
+
+    /// Run `f` in the interpreter under the memory budget, storing any exception in
+    /// `self.error` and resetting the runtime if the interpreter crashed
+    fn execute(&mut self, f: impl FnOnce(&VirtualMachine) -> PyResult<()>) {
+        let interpreter = &self.interpreter;
+        self.memory.enter();
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            interpreter.enter(move |vm| {
+                f(vm).err().map(|exec_err| {
+                    let mut s = String::new();
+                    vm.write_exception(&mut s, &exec_err).unwrap();
+                    s
+                })
+            })
+        }));
+        let over_budget = self.memory.exit();
+        self.globals.take();
+
+        match result {
+            Ok(error) => self.error = error,
+            Err(panic) => {
+                let message = format!(
+                    "The interpreter crashed ({}), so the runtime was reset",
+                    panic_message(&*panic)
+                );
+                self.recover(message);
+                return;
+            }
+        }
+
+        if over_budget {
+            self.recover(
+                "The script exceeded its memory budget, so the runtime was reset".to_owned(),
+            );
+        }
+    }
+
+    /// Throw away an interpreter that can't be trusted anymore, keeping the code and
+    /// the last error, and explaining what happened in the notice
+    fn recover(&mut self, notice: String) {
+        let error = self.error.take();
+        self.reset_state();
+        self.error = error;
+        self.notice = Some(notice);
+    }
+
+    /// Number of shapes currently painted on the child `Ui`'s layer
+    fn emitted_shape_count(&self) -> usize {
+        let Some(child) = &self.child_ui else {
+            return 0;
+        };
+        let child = child.borrow();
+        let layer_id = child.layer_id();
+        child
+            .ctx()
+            .graphics(|layers| layers.get(layer_id).map_or(0, |list| list.next_idx().0))
+    }
+
+    fn capture_output(&self, shapes_before: usize) -> Option<ScriptOutput> {
+        let child = self.child_ui.as_ref()?.borrow();
+        let layer_id = child.layer_id();
+        let shapes = child.ctx().graphics(|layers| {
+            layers.get(layer_id).map_or(vec![], |list| {
+                list.all_entries().skip(shapes_before).cloned().collect()
+            })
+        });
+        Some(ScriptOutput {
+            origin: child.min_rect().min,
+            size: child.min_size(),
+            shapes,
+        })
+    }
+
+    /// Paint what the script painted on its last run, without running it.
+    /// Nothing is interactive, so this is only right for frames without input.
+    pub fn show_last_output(&self, ui: &mut Ui) {
+        let Some(output) = &self.last_output else {
+            return;
+        };
+        let (rect, _) = ui.allocate_exact_size(output.size, egui::Sense::hover());
+        let offset = rect.min - output.origin;
+        let clip_rect = ui.clip_rect();
+        ui.ctx().graphics_mut(|layers| {
+            let list = layers.entry(ui.layer_id());
+            for clipped in &output.shapes {
+                let mut shape = clipped.shape.clone();
+                shape.translate(offset);
+                list.add(
+                    clipped.clip_rect.translate(offset).intersect(clip_rect),
+                    shape,
+                );
             }
         });
     }
 
+    /// Keep what each run paints, for showing it again with `show_last_output`
+    pub fn set_keep_output(&mut self, keep: bool) {
+        self.keep_output = keep;
+        if !keep {
+            self.last_output = None;
+        }
+    }
+
+    /// Whether there is output from a run since the last reset, if output is kept
+    pub fn has_run(&self) -> bool {
+        self.last_output.is_some()
+    }
+
+    /// When the script asked to be run again with `egui.request_repaint()` or
+    /// `egui.request_repaint_after()`, in `egui::InputState::time` seconds
+    pub fn scheduled_run(&self) -> Option<f64> {
+        self.next_run.get()
+    }
+
     pub fn set_egui(&mut self, ui: &mut Ui) {
+        if let Err(e) = self.try_set_egui(ui) {
+            self.recover(format!(
+                "Could not provide `egui` to the script, so the runtime was reset\n{e}"
+            ));
+            // A fresh interpreter has nothing the script could have broken
+            if let Err(e) = self.try_set_egui(ui) {
+                self.error = Some(e.to_string());
+            }
+        }
+    }
+
+    fn try_set_egui(&mut self, ui: &mut Ui) -> Result<(), RuntimeError> {
         let ui = Rc::new(RefCell::new(ui.new_child(Default::default())));
         self.child_ui = Some(ui.clone());
 
+        let next_run = self.next_run.clone();
+        let fonts = self.fonts.clone();
+        let tables = self.tables.clone();
         let scope = self.scope.clone();
         self.interpreter.enter(move |vm| {
-            let py_ui = vm.new_pyobj(PyEgui { ui });
+            let py_ui = vm.new_pyobj(PyEgui {
+                ui,
+                next_run,
+                fonts,
+                tables,
+            });
             scope
                 .globals
                 .set_item("egui", py_ui, vm)
-                .unwrap_exception(vm);
-        });
+                .or_runtime_error(vm)
+        })
     }
 
     pub fn take_up_egui_space(&self, ui: &mut Ui) {
@@ -175,31 +455,114 @@ print(f"You cannot {s}")"#
     }
 
     pub fn reset_state(&mut self) {
-        let old = std::mem::replace(self, Self::new());
+        let policy = self.policy.clone();
+        let old = std::mem::replace(self, Self::new(policy));
+        // egui can only swap all fonts at once, so they outlive the interpreter
+        self.fonts = old.fonts;
+        self.keep_output = old.keep_output;
         self.load(old.code);
     }
 
+    /// Replace every registered font with the project's, including ones the script added.
+    /// Fonts that fail to load are skipped and reported.
+    pub fn load_fonts(&self, fonts: &[ProjectFont], ctx: &egui::Context) -> Result<(), String> {
+        let mut registry = self.fonts.borrow_mut();
+        registry.clear();
+        let errors: Vec<String> = fonts
+            .iter()
+            .filter_map(|font| registry.insert(&font.name, font.data.clone()).err())
+            .collect();
+        registry.apply(ctx);
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors.join("\n")),
+        }
+    }
+
+    /// Rebuild the interpreter under a new policy, keeping the code
+    pub fn set_policy(&mut self, policy: RuntimePolicy) {
+        self.policy = policy;
+        self.reset_state();
+    }
+
+    /// The script's globals and the builtins, for completion. Only looked up again after
+    /// the script has run.
+    pub fn globals(&self) -> Rc<[Global]> {
+        self.globals
+            .borrow_mut()
+            .get_or_insert_with(|| self.lookup_globals().into())
+            .clone()
+    }
+
+    fn lookup_globals(&self) -> Vec<Global> {
+        self.interpreter.enter(|vm| {
+            let dicts = [
+                Some(self.scope.globals.clone()),
+                vm.builtins.as_object().dict(),
+            ];
+            let mut globals = vec![];
+            for dict in dicts.iter().flatten() {
+                for (name, value) in dict {
+                    if let Some(name) = name.payload::<PyStr>() {
+                        globals.push(Global {
+                            name: name.as_str().to_owned(),
+                            class: value.class().name().to_string(),
+                        });
+                    }
+                }
+            }
+            globals
+        })
+    }
+
+    /// Help for a name under the editor's cursor, as `help()` would print it
+    pub fn help(&self, topic: &Topic) -> Option<String> {
+        self.interpreter
+            .enter(|vm| docs::topic_help(topic, &self.scope, vm))
+    }
+
     pub fn error(&self) -> Option<&str> {
-        self.error.as_ref().map(|x| x.as_str())
+        self.error.as_deref()
+    }
+
+    pub fn notice(&self) -> Option<&str> {
+        self.notice.as_deref().or(self.setup_notice.as_deref())
     }
 
     pub fn stdout(&mut self) -> Rc<RefCell<String>> {
         self.output.clone()
     }
+
+    pub fn timings(&self) -> &RuntimeTimings {
+        &self.timings
+    }
 }
 
 #[pymodule]
 mod rust_py_module {
     use egui::Align2;
-    use rustpython_vm::builtins::PyBaseExceptionRef;
+    use rustpython_vm::{
+        builtins::{PyBaseExceptionRef, PyFloat, PyInt, PyList, PyListRef, PyTuple, PyTypeRef},
+        convert::ToPyObject,
+        function::{ArgBytesLike, ArgIntoFloat, ArgIterable, FuncArgs, PyComparisonValue},
+        protocol::PyNumberMethods,
+        types::{AsNumber, Comparable, Constructor, Iterable, PyComparisonOp, Representable},
+        AsObject, FromArgs, Py, PyObject, TryFromObject,
+    };
 
     use super::*;
 
+    /// The `egui` global: widgets, painting, input and style for the script's area
     #[pyattr]
     #[derive(PyPayload, Clone)]
     #[pyclass(module = "rust_py_module", name = "PyEgui")]
     pub struct PyEgui {
         pub ui: Rc<RefCell<Ui>>,
+        pub next_run: Rc<Cell<Option<f64>>>,
+        pub fonts: Rc<RefCell<FontRegistry>>,
+        /// Sort orders and row keys of the script's tables, by table id
+        pub tables: Rc<RefCell<HashMap<String, TableCache>>>,
     }
 
     fn parse_align2_from_str(
@@ -235,13 +598,14 @@ mod rust_py_module {
 
         for substr in s.split('|') {
             match substr {
+                "hover" => {}
                 "click" => sense.click = true,
                 "drag" => sense.drag = true,
                 "focusable" => sense.focusable = true,
                 _ => {
                     return Err(vm.new_exception_msg(
                         vm.ctx.exceptions.runtime_error.to_owned(),
-                        "Must be click, drag, or focusable".to_string(),
+                        "Must be hover, click, drag, or focusable".to_string(),
                     ));
                 }
             }
@@ -250,199 +614,2552 @@ mod rust_py_module {
         Ok(sense)
     }
 
+    /// A `Color32`, a hex string like "#ff8800" or "#ff880080", a color name like "red",
+    /// 3 or 4 ints from 0 to 255, or 3 or 4 floats from 0 to 1, as RGB or unmultiplied
+    /// RGBA. Premultiplied and HSV colors go through the `Color32` constructors.
     fn parse_color(
-        color: &Vec<u8>,
+        color: &PyObjectRef,
         vm: &VirtualMachine,
     ) -> Result<egui::Color32, PyBaseExceptionRef> {
-        if color.len() != 4 {
-            Err(vm.new_exception_msg(
-                vm.ctx.exceptions.runtime_error.to_owned(),
-                "Colors are from premultiplied RGBA".to_owned(),
-            ))
+        if let Some(color) = color.payload::<PyColor32>() {
+            return Ok(color.color);
+        }
+        if let Some(s) = color.payload::<rustpython_vm::builtins::PyStr>() {
+            return color::parse_str(s.as_str()).map_err(|e| vm.new_value_error(e));
+        }
+
+        let items = as_list(color.clone(), vm)
+            .and_then(|list| Vec::<PyObjectRef>::try_from_object(vm, list))
+            .map_err(|_| {
+                vm.new_type_error(format!(
+                    "Expected a color as a Color32, hex string, color name, or 3 or 4 ints from \
+                     0 to 255 or floats from 0 to 1 (RGB or unmultiplied RGBA), not {}. Use \
+                     Color32.from_hsv or Color32.from_rgba_premultiplied for other formats",
+                    color.class().name()
+                ))
+            })?;
+        if !(3..=4).contains(&items.len()) {
+            return Err(vm.new_value_error(color::component_count_error(items.len())));
+        }
+
+        let result = if items
+            .iter()
+            .any(|item| item.payload_is::<rustpython_vm::builtins::PyFloat>())
+        {
+            let values = items
+                .into_iter()
+                .map(|item| ArgIntoFloat::try_from_object(vm, item).map(f64::from))
+                .collect::<PyResult<Vec<_>>>()?;
+            color::from_floats(&values)
         } else {
-            Ok(egui::Color32::from_rgba_premultiplied(
-                color[0], color[1], color[2], color[3],
-            ))
+            let values = items
+                .into_iter()
+                .map(|item| i64::try_from_object(vm, item))
+                .collect::<PyResult<Vec<_>>>()?;
+            color::from_ints(&values)
+        };
+        result.map_err(|e| vm.new_value_error(e))
+    }
+
+    /// An sRGB color with premultiplied alpha
+    #[pyattr]
+    #[pyclass(module = "rust_py_module", name = "Color32")]
+    #[derive(Debug, Clone, PyPayload)]
+    pub struct PyColor32 {
+        pub color: egui::Color32,
+    }
+
+    impl From<egui::Color32> for PyColor32 {
+        fn from(color: egui::Color32) -> Self {
+            Self { color }
         }
     }
 
-    fn parse_vec2(value: &Vec<f32>, vm: &VirtualMachine) -> Result<egui::Vec2, PyBaseExceptionRef> {
-        if value.len() != 2 {
-            Err(vm.new_exception_msg(
-                vm.ctx.exceptions.runtime_error.to_owned(),
-                "Points must be of dimension 2".to_owned(),
-            ))
-        } else {
-            Ok(egui::Vec2::new(value[0], value[1]))
+    /// `Color32(color)` accepts any color format, `Color32(r, g, b[, a])` takes the
+    /// components directly
+    impl Constructor for PyColor32 {
+        type Args = FuncArgs;
+
+        fn py_new(cls: PyTypeRef, args: FuncArgs, vm: &VirtualMachine) -> PyResult {
+            if !args.kwargs.is_empty() {
+                return Err(vm.new_type_error("Color32() takes no keyword arguments".to_owned()));
+            }
+            let color = match &args.args[..] {
+                [color] => parse_color(color, vm)?,
+                components => parse_color(&vm.ctx.new_tuple(components.to_vec()).into(), vm)?,
+            };
+            PyColor32 { color }
+                .into_ref_with_type(vm, cls)
+                .map(Into::into)
+        }
+    }
+
+    impl Comparable for PyColor32 {
+        fn cmp(
+            zelf: &Py<Self>,
+            other: &PyObject,
+            op: PyComparisonOp,
+            _vm: &VirtualMachine,
+        ) -> PyResult<PyComparisonValue> {
+            op.eq_only(|| {
+                Ok(match other.payload::<Self>() {
+                    Some(other) => (zelf.color == other.color).into(),
+                    None => PyComparisonValue::NotImplemented,
+                })
+            })
         }
     }
 
-    fn parse_pos2(value: &Vec<f32>, vm: &VirtualMachine) -> Result<egui::Pos2, PyBaseExceptionRef> {
-        parse_vec2(value, vm).map(|v| v.to_pos2())
+    impl Representable for PyColor32 {
+        fn repr_str(zelf: &Py<Self>, _vm: &VirtualMachine) -> PyResult<String> {
+            Ok(format!("Color32({:?})", color::to_hex(zelf.color)))
+        }
     }
 
-    #[pyclass]
-    impl PyEgui {
+    #[pyclass(with(Constructor, Comparable, Representable))]
+    impl PyColor32 {
+        /// Opaque color from red, green and blue from 0 to 255
+        #[pystaticmethod]
+        fn from_rgb(r: u8, g: u8, b: u8) -> Self {
+            egui::Color32::from_rgb(r, g, b).into()
+        }
+
+        /// Color from components already multiplied by alpha, from 0 to 255
+        #[pystaticmethod]
+        fn from_rgba_premultiplied(r: u8, g: u8, b: u8, a: u8) -> Self {
+            egui::Color32::from_rgba_premultiplied(r, g, b, a).into()
+        }
+
+        /// Color from components not yet multiplied by alpha, from 0 to 255
+        #[pystaticmethod]
+        fn from_rgba_unmultiplied(r: u8, g: u8, b: u8, a: u8) -> Self {
+            egui::Color32::from_rgba_unmultiplied(r, g, b, a).into()
+        }
+
+        /// Opaque gray, from 0 for black to 255 for white
+        #[pystaticmethod]
+        fn from_gray(l: u8) -> Self {
+            egui::Color32::from_gray(l).into()
+        }
+
+        /// Hue, saturation and value from 0 to 1
+        #[pystaticmethod]
+        fn from_hsv(h: ArgIntoFloat, s: ArgIntoFloat, v: ArgIntoFloat) -> Self {
+            let hsva = egui::ecolor::Hsva::new(*h as f32, *s as f32, *v as f32, 1.0);
+            egui::Color32::from(hsva).into()
+        }
+
+        /// Hue, saturation, value and unmultiplied alpha from 0 to 1
+        #[pystaticmethod]
+        fn from_hsva(h: ArgIntoFloat, s: ArgIntoFloat, v: ArgIntoFloat, a: ArgIntoFloat) -> Self {
+            let hsva = egui::ecolor::Hsva::new(*h as f32, *s as f32, *v as f32, *a as f32);
+            egui::Color32::from(hsva).into()
+        }
+
+        /// Parse "#rgb", "#rgba", "#rrggbb" or "#rrggbbaa"
+        #[pystaticmethod]
+        fn from_hex(hex: PyStrRef, vm: &VirtualMachine) -> PyResult<Self> {
+            match hex.as_str().starts_with('#') {
+                true => color::parse_str(hex.as_str())
+                    .map(Self::from)
+                    .map_err(|e| vm.new_value_error(e)),
+                false => {
+                    Err(vm.new_value_error(format!("{:?} does not start with #", hex.as_str())))
+                }
+            }
+        }
+
+        /// Red, premultiplied
+        #[pygetset]
+        fn r(&self) -> u8 {
+            self.color.r()
+        }
+
+        /// Green, premultiplied
+        #[pygetset]
+        fn g(&self) -> u8 {
+            self.color.g()
+        }
+
+        /// Blue, premultiplied
+        #[pygetset]
+        fn b(&self) -> u8 {
+            self.color.b()
+        }
+
+        /// Alpha, from 0 for transparent to 255 for opaque
+        #[pygetset]
+        fn a(&self) -> u8 {
+            self.color.a()
+        }
+
+        /// Premultiplied `(r, g, b, a)`
         #[pymethod]
-        fn button(&self, text: PyStrRef) -> PyResponse {
-            PyResponse::from(self.ui.borrow_mut().button(text.as_str()))
+        fn to_tuple(&self) -> (u8, u8, u8, u8) {
+            self.color.to_tuple()
         }
 
+        /// Unmultiplied `(r, g, b, a)`
         #[pymethod]
-        fn text_edit_singleline(&self, text: PyStrRef) -> (String, PyResponse) {
-            let mut editable = text.to_string();
-            let ret = self.ui.borrow_mut().text_edit_singleline(&mut editable);
-            (editable, PyResponse::from(ret))
+        fn to_rgba_unmultiplied(&self) -> (u8, u8, u8, u8) {
+            let [r, g, b, a] = self.color.to_srgba_unmultiplied();
+            (r, g, b, a)
         }
 
+        /// `(h, s, v, a)` from 0 to 1
         #[pymethod]
-        fn painter(&self) -> PyPainter {
-            PyPainter {
-                paint: self.ui.borrow().painter().clone(),
-            }
+        fn to_hsva(&self) -> (f32, f32, f32, f32) {
+            let hsva = egui::ecolor::Hsva::from(self.color);
+            (hsva.h, hsva.s, hsva.v, hsva.a)
         }
 
+        /// Like "#ff8800", or "#ff880080" when not opaque
         #[pymethod]
-        fn allocate_painter(
-            &self,
-            desired_size: Vec<f32>,
-            sense: String,
-            vm: &VirtualMachine,
-        ) -> Result<(PyResponse, PyPainter), PyBaseExceptionRef> {
-            let sense = parse_sense_from_str(&sense, vm)?;
-            let desired_size = parse_vec2(&desired_size, vm)?;
+        fn to_hex(&self) -> String {
+            color::to_hex(self.color)
+        }
 
-            let (resp, paint) = self.ui.borrow_mut().allocate_painter(desired_size, sense);
+        /// Blend towards `other` in gamma space, `t` from 0 to 1
+        #[pymethod]
+        fn lerp(&self, other: PyObjectRef, t: ArgIntoFloat, vm: &VirtualMachine) -> PyResult<Self> {
+            let other = parse_color(&other, vm)?;
+            Ok(self.color.lerp_to_gamma(other, *t as f32).into())
+        }
 
-            Ok((PyResponse { resp }, PyPainter { paint }))
+        /// Fade towards transparent, multiplying in gamma space
+        #[pymethod]
+        fn gamma_multiply(&self, factor: ArgIntoFloat) -> Self {
+            self.color.gamma_multiply(*factor as f32).into()
+        }
+
+        /// Fade towards transparent, multiplying in linear space
+        #[pymethod]
+        fn linear_multiply(&self, factor: ArgIntoFloat) -> Self {
+            self.color.linear_multiply(*factor as f32).into()
+        }
+
+        /// The same color with full alpha
+        #[pymethod]
+        fn to_opaque(&self) -> Self {
+            self.color.to_opaque().into()
         }
     }
 
-    impl std::fmt::Debug for PyEgui {
-        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-            let ptr = self.ui.as_ptr();
-            writeln!(f, "PyEgui {:?}", ptr)
+    /// Operands of the arithmetic `Vec2` and `Pos2` support
+    enum Operand {
+        Vec2(egui::Vec2),
+        Pos2(egui::Pos2),
+        Number(f32),
+        Other,
+    }
+
+    impl Operand {
+        fn from_object(obj: &PyObject, vm: &VirtualMachine) -> Self {
+            if let Some(v) = obj.payload::<PyVec2>() {
+                Self::Vec2(v.vec)
+            } else if let Some(p) = obj.payload::<PyPos2>() {
+                Self::Pos2(p.pos)
+            } else if obj.payload_is::<PyInt>() || obj.payload_is::<PyFloat>() {
+                ArgIntoFloat::try_from_object(vm, obj.to_owned())
+                    .map_or(Self::Other, |n| Self::Number(*n as f32))
+            } else {
+                Self::Other
+            }
         }
     }
 
-    #[pyattr]
-    #[pyclass(module = "rust_py_module", name = "PyResponse")]
-    #[derive(Debug, PyPayload)]
-    pub struct PyResponse {
-        pub resp: egui::Response,
+    #[derive(Clone, Copy)]
+    enum Arithmetic {
+        Add,
+        Sub,
+        Mul,
+        Div,
+    }
+
+    /// The same operators as egui: positions move by vectors, and the difference of
+    /// two positions is a vector. Anything else is `NotImplemented`.
+    fn arithmetic(a: &PyObject, b: &PyObject, op: Arithmetic, vm: &VirtualMachine) -> PyResult {
+        use Arithmetic::*;
+        use Operand::*;
+        let result = match (op, Operand::from_object(a, vm), Operand::from_object(b, vm)) {
+            (Add, Vec2(a), Vec2(b)) => PyVec2::from(a + b).into_pyobject(vm),
+            (Add, Pos2(a), Vec2(b)) | (Add, Vec2(b), Pos2(a)) => {
+                PyPos2::from(a + b).into_pyobject(vm)
+            }
+            (Sub, Vec2(a), Vec2(b)) => PyVec2::from(a - b).into_pyobject(vm),
+            (Sub, Pos2(a), Pos2(b)) => PyVec2::from(a - b).into_pyobject(vm),
+            (Sub, Pos2(a), Vec2(b)) => PyPos2::from(a - b).into_pyobject(vm),
+            (Mul, Vec2(a), Vec2(b)) => PyVec2::from(a * b).into_pyobject(vm),
+            (Mul, Vec2(a), Number(b)) | (Mul, Number(b), Vec2(a)) => {
+                PyVec2::from(a * b).into_pyobject(vm)
+            }
+            (Div, Vec2(a), Vec2(b)) => PyVec2::from(a / b).into_pyobject(vm),
+            (Div, Vec2(a), Number(b)) => PyVec2::from(a / b).into_pyobject(vm),
+            _ => vm.ctx.not_implemented(),
+        };
+        Ok(result)
+    }
+
+    /// Operators shared by `Vec2` and `Pos2`
+    const ARITHMETIC: PyNumberMethods = PyNumberMethods {
+        add: Some(|a, b, vm| arithmetic(a, b, Arithmetic::Add, vm)),
+        subtract: Some(|a, b, vm| arithmetic(a, b, Arithmetic::Sub, vm)),
+        multiply: Some(|a, b, vm| arithmetic(a, b, Arithmetic::Mul, vm)),
+        true_divide: Some(|a, b, vm| arithmetic(a, b, Arithmetic::Div, vm)),
+        ..PyNumberMethods::NOT_IMPLEMENTED
+    };
+
+    /// Iterating over `x` and `y` lets points unpack like the tuples they replace
+    fn iter_xy(x: f32, y: f32, vm: &VirtualMachine) -> PyResult {
+        let pair = vm.ctx.new_tuple(vec![x.to_pyobject(vm), y.to_pyobject(vm)]);
+        Ok(pair.as_object().get_iter(vm)?.into())
+    }
+
+    /// The arguments of `Vec2(x, y)`, `Vec2((x, y))` and the same for `Pos2`
+    fn xy_args(args: FuncArgs, name: &str, vm: &VirtualMachine) -> PyResult<egui::Vec2> {
+        if !args.kwargs.is_empty() {
+            return Err(vm.new_type_error(format!("{name}() takes no keyword arguments")));
+        }
+        match &args.args[..] {
+            [point] => parse_vec2(point, vm),
+            xy => parse_vec2(&vm.ctx.new_tuple(xy.to_vec()).into(), vm),
+        }
+    }
+
+    /// A point or size given as a `Vec2`, a `Pos2`, or two numbers in a tuple, list or
+    /// `ndarray` row
+    fn parse_vec2(obj: &PyObjectRef, vm: &VirtualMachine) -> PyResult<egui::Vec2> {
+        if let Some(v) = obj.payload::<PyVec2>() {
+            return Ok(v.vec);
+        }
+        if let Some(p) = obj.payload::<PyPos2>() {
+            return Ok(p.pos.to_vec2());
+        }
+        let values = float_list(obj.clone(), vm).map_err(|_| {
+            vm.new_type_error(format!(
+                "Expected a Vec2, Pos2 or pair of numbers, not {}",
+                obj.class().name()
+            ))
+        })?;
+        match values[..] {
+            [x, y] => Ok(egui::vec2(x as f32, y as f32)),
+            _ => Err(vm.new_value_error(format!(
+                "Points must be of dimension 2, not {}",
+                values.len()
+            ))),
+        }
+    }
+
+    fn parse_pos2(obj: &PyObjectRef, vm: &VirtualMachine) -> PyResult<egui::Pos2> {
+        parse_vec2(obj, vm).map(|v| v.to_pos2())
     }
 
+    fn parse_rect(obj: &PyObjectRef, vm: &VirtualMachine) -> PyResult<egui::Rect> {
+        obj.payload::<PyRect>().map(|r| r.rect).ok_or_else(|| {
+            vm.new_type_error(format!("Expected a Rect, not {}", obj.class().name()))
+        })
+    }
+
+    /// A size or an offset. Supports `+`, `-`, `*` and `/` like egui's `Vec2`.
     #[pyattr]
-    #[pyclass(module = "rust_py_module", name = "PyRect")]
-    #[derive(Debug, PyPayload)]
-    pub struct PyRect {
-        pub rect: egui::Rect,
+    #[pyclass(module = "rust_py_module", name = "Vec2")]
+    #[derive(Debug, Clone, PyPayload)]
+    pub struct PyVec2 {
+        pub vec: egui::Vec2,
     }
 
-    #[pyclass]
-    impl PyResponse {
-        #[pymethod]
-        fn clicked(&self) -> bool {
-            self.resp.clicked()
+    impl From<egui::Vec2> for PyVec2 {
+        fn from(vec: egui::Vec2) -> Self {
+            Self { vec }
         }
+    }
 
-        #[pymethod]
-        fn rect(&self) -> PyRect {
-            PyRect {
-                rect: self.resp.rect,
-            }
+    impl Constructor for PyVec2 {
+        type Args = FuncArgs;
+
+        fn py_new(cls: PyTypeRef, args: FuncArgs, vm: &VirtualMachine) -> PyResult {
+            let vec = xy_args(args, "Vec2", vm)?;
+            PyVec2 { vec }.into_ref_with_type(vm, cls).map(Into::into)
         }
     }
 
-    #[pyclass]
-    impl PyRect {
-        #[pymethod]
-        fn min(&self) -> (f32, f32) {
-            (self.rect.min.x, self.rect.min.y)
+    impl Comparable for PyVec2 {
+        fn cmp(
+            zelf: &Py<Self>,
+            other: &PyObject,
+            op: PyComparisonOp,
+            _vm: &VirtualMachine,
+        ) -> PyResult<PyComparisonValue> {
+            op.eq_only(|| {
+                Ok(match other.payload::<Self>() {
+                    Some(other) => (zelf.vec == other.vec).into(),
+                    None => PyComparisonValue::NotImplemented,
+                })
+            })
         }
+    }
 
-        #[pymethod]
-        fn max(&self) -> (f32, f32) {
-            (self.rect.max.x, self.rect.max.y)
+    impl Representable for PyVec2 {
+        fn repr_str(zelf: &Py<Self>, _vm: &VirtualMachine) -> PyResult<String> {
+            Ok(format!("Vec2({:?}, {:?})", zelf.vec.x, zelf.vec.y))
         }
     }
 
-    impl From<egui::Response> for PyResponse {
-        fn from(resp: egui::Response) -> Self {
-            Self { resp }
+    impl AsNumber for PyVec2 {
+        fn as_number() -> &'static PyNumberMethods {
+            static AS_NUMBER: PyNumberMethods = ARITHMETIC;
+            &AS_NUMBER
         }
     }
 
-    #[pyattr]
-    #[pyclass(module = "rust_py_module", name = "PyPainter")]
-    #[derive(PyPayload)]
-    pub struct PyPainter {
-        pub paint: egui::Painter,
+    impl Iterable for PyVec2 {
+        fn iter(zelf: PyRef<Self>, vm: &VirtualMachine) -> PyResult {
+            iter_xy(zelf.vec.x, zelf.vec.y, vm)
+        }
     }
 
-    #[pyclass]
-    impl PyPainter {
+    #[pyclass(with(Constructor, Comparable, Representable, AsNumber, Iterable))]
+    impl PyVec2 {
+        /// Horizontal component, positive to the right
+        #[pygetset]
+        fn x(&self) -> f32 {
+            self.vec.x
+        }
+
+        /// Vertical component, positive downwards
+        #[pygetset]
+        fn y(&self) -> f32 {
+            self.vec.y
+        }
+
+        /// Euclidean length
         #[pymethod]
-        fn line(
-            &self,
-            points: Vec<Vec<f32>>,
-            stroke_width: f32,
-            color: Vec<u8>,
-            vm: &VirtualMachine,
-        ) -> Result<(), PyBaseExceptionRef> {
-            let color = parse_color(&color, vm)?;
-            for pair in points.windows(2) {
-                self.paint.line_segment(
-                    [parse_pos2(&pair[0], vm)?, parse_pos2(&pair[1], vm)?],
-                    Stroke::new(stroke_width, color),
-                );
-            }
+        fn length(&self) -> f32 {
+            self.vec.length()
+        }
 
-            Ok(())
+        /// The same direction with length 1, or NaN for a zero vector
+        #[pymethod]
+        fn normalized(&self) -> Self {
+            self.vec.normalized().into()
         }
 
+        /// Unary minus is looked up as a method, not through the number slots
+        #[pymethod(magic)]
+        fn neg(&self) -> Self {
+            (-self.vec).into()
+        }
+
+        /// Rotated a quarter turn clockwise, since y points down
         #[pymethod]
-        fn circle(
-            &self,
-            center: Vec<f32>,
-            radius: f32,
-            fill_color: Vec<u8>,
-            stroke_width: f32,
-            stroke_color: Vec<u8>,
-            vm: &VirtualMachine,
-        ) -> Result<(), PyBaseExceptionRef> {
-            self.paint.circle(
-                parse_pos2(&center, vm)?,
-                radius,
-                parse_color(&fill_color, vm)?,
-                Stroke::new(stroke_width, parse_color(&stroke_color, vm)?),
-            );
-            Ok(())
+        fn rot90(&self) -> Self {
+            self.vec.rot90().into()
         }
 
+        /// Dot product with `other`
         #[pymethod]
-        fn text(
-            &self,
-            pos: Vec<f32>,
-            anchor: String,
-            text: PyStrRef,
-            text_color: Vec<u8>,
-            vm: &VirtualMachine,
-        ) -> Result<PyRect, PyBaseExceptionRef> {
-            let rect = self.paint.text(
-                parse_pos2(&pos, vm)?,
-                parse_align2_from_str(&anchor, vm)?,
-                text.as_str(),
-                Default::default(),
-                parse_color(&text_color, vm)?,
-            );
-            Ok(PyRect { rect })
+        fn dot(&self, other: PyObjectRef, vm: &VirtualMachine) -> PyResult<f32> {
+            Ok(self.vec.dot(parse_vec2(&other, vm)?))
         }
-    }
 
-    impl std::fmt::Debug for PyPainter {
-        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        /// Interpolate towards `other`, `t` from 0 to 1
+        #[pymethod]
+        fn lerp(&self, other: PyObjectRef, t: ArgIntoFloat, vm: &VirtualMachine) -> PyResult<Self> {
+            let other = parse_vec2(&other, vm)?;
+            Ok(egui::lerp(self.vec..=other, *t as f32).into())
+        }
+
+        /// The point this far from the origin
+        #[pymethod]
+        fn to_pos2(&self) -> PyPos2 {
+            self.vec.to_pos2().into()
+        }
+
+        /// `(x, y)`
+        #[pymethod]
+        fn to_tuple(&self) -> (f32, f32) {
+            (self.vec.x, self.vec.y)
+        }
+    }
+
+    /// A position on screen. Adding a `Vec2` moves it, subtracting another `Pos2` gives a `Vec2`.
+    #[pyattr]
+    #[pyclass(module = "rust_py_module", name = "Pos2")]
+    #[derive(Debug, Clone, PyPayload)]
+    pub struct PyPos2 {
+        pub pos: egui::Pos2,
+    }
+
+    impl From<egui::Pos2> for PyPos2 {
+        fn from(pos: egui::Pos2) -> Self {
+            Self { pos }
+        }
+    }
+
+    impl Constructor for PyPos2 {
+        type Args = FuncArgs;
+
+        fn py_new(cls: PyTypeRef, args: FuncArgs, vm: &VirtualMachine) -> PyResult {
+            let pos = xy_args(args, "Pos2", vm)?.to_pos2();
+            PyPos2 { pos }.into_ref_with_type(vm, cls).map(Into::into)
+        }
+    }
+
+    impl Comparable for PyPos2 {
+        fn cmp(
+            zelf: &Py<Self>,
+            other: &PyObject,
+            op: PyComparisonOp,
+            _vm: &VirtualMachine,
+        ) -> PyResult<PyComparisonValue> {
+            op.eq_only(|| {
+                Ok(match other.payload::<Self>() {
+                    Some(other) => (zelf.pos == other.pos).into(),
+                    None => PyComparisonValue::NotImplemented,
+                })
+            })
+        }
+    }
+
+    impl Representable for PyPos2 {
+        fn repr_str(zelf: &Py<Self>, _vm: &VirtualMachine) -> PyResult<String> {
+            Ok(format!("Pos2({:?}, {:?})", zelf.pos.x, zelf.pos.y))
+        }
+    }
+
+    impl AsNumber for PyPos2 {
+        fn as_number() -> &'static PyNumberMethods {
+            static AS_NUMBER: PyNumberMethods = ARITHMETIC;
+            &AS_NUMBER
+        }
+    }
+
+    impl Iterable for PyPos2 {
+        fn iter(zelf: PyRef<Self>, vm: &VirtualMachine) -> PyResult {
+            iter_xy(zelf.pos.x, zelf.pos.y, vm)
+        }
+    }
+
+    #[pyclass(with(Constructor, Comparable, Representable, AsNumber, Iterable))]
+    impl PyPos2 {
+        /// Points from the left edge
+        #[pygetset]
+        fn x(&self) -> f32 {
+            self.pos.x
+        }
+
+        /// Points from the top edge
+        #[pygetset]
+        fn y(&self) -> f32 {
+            self.pos.y
+        }
+
+        /// Straight line distance to `other`
+        #[pymethod]
+        fn distance(&self, other: PyObjectRef, vm: &VirtualMachine) -> PyResult<f32> {
+            Ok(self.pos.distance(parse_pos2(&other, vm)?))
+        }
+
+        /// Interpolate towards `other`, `t` from 0 to 1
+        #[pymethod]
+        fn lerp(&self, other: PyObjectRef, t: ArgIntoFloat, vm: &VirtualMachine) -> PyResult<Self> {
+            Ok(self.pos.lerp(parse_pos2(&other, vm)?, *t as f32).into())
+        }
+
+        /// The offset from the origin
+        #[pymethod]
+        fn to_vec2(&self) -> PyVec2 {
+            self.pos.to_vec2().into()
+        }
+
+        /// `(x, y)`
+        #[pymethod]
+        fn to_tuple(&self) -> (f32, f32) {
+            (self.pos.x, self.pos.y)
+        }
+    }
+
+    /// A rectangle from its top left corner `min` to its bottom right corner `max`
+    #[pyattr]
+    #[pyclass(module = "rust_py_module", name = "Rect")]
+    #[derive(Debug, Clone, PyPayload)]
+    pub struct PyRect {
+        pub rect: egui::Rect,
+    }
+
+    impl From<egui::Rect> for PyRect {
+        fn from(rect: egui::Rect) -> Self {
+            Self { rect }
+        }
+    }
+
+    /// `Rect(min, max)`, both corners as points
+    impl Constructor for PyRect {
+        type Args = (PyObjectRef, PyObjectRef);
+
+        fn py_new(cls: PyTypeRef, (min, max): Self::Args, vm: &VirtualMachine) -> PyResult {
+            let rect = egui::Rect::from_min_max(parse_pos2(&min, vm)?, parse_pos2(&max, vm)?);
+            PyRect { rect }.into_ref_with_type(vm, cls).map(Into::into)
+        }
+    }
+
+    impl Comparable for PyRect {
+        fn cmp(
+            zelf: &Py<Self>,
+            other: &PyObject,
+            op: PyComparisonOp,
+            _vm: &VirtualMachine,
+        ) -> PyResult<PyComparisonValue> {
+            op.eq_only(|| {
+                Ok(match other.payload::<Self>() {
+                    Some(other) => (zelf.rect == other.rect).into(),
+                    None => PyComparisonValue::NotImplemented,
+                })
+            })
+        }
+    }
+
+    impl Representable for PyRect {
+        fn repr_str(zelf: &Py<Self>, _vm: &VirtualMachine) -> PyResult<String> {
+            let egui::Rect { min, max } = zelf.rect;
+            Ok(format!(
+                "Rect(Pos2({:?}, {:?}), Pos2({:?}, {:?}))",
+                min.x, min.y, max.x, max.y
+            ))
+        }
+    }
+
+    #[pyclass(with(Constructor, Comparable, Representable))]
+    impl PyRect {
+        /// The rect with its top left corner at `min`
+        #[pystaticmethod]
+        fn from_min_size(
+            min: PyObjectRef,
+            size: PyObjectRef,
+            vm: &VirtualMachine,
+        ) -> PyResult<Self> {
+            Ok(egui::Rect::from_min_size(parse_pos2(&min, vm)?, parse_vec2(&size, vm)?).into())
+        }
+
+        /// The rect centered on `center`
+        #[pystaticmethod]
+        fn from_center_size(
+            center: PyObjectRef,
+            size: PyObjectRef,
+            vm: &VirtualMachine,
+        ) -> PyResult<Self> {
+            let center = parse_pos2(&center, vm)?;
+            Ok(egui::Rect::from_center_size(center, parse_vec2(&size, vm)?).into())
+        }
+
+        /// The top left corner
+        #[pymethod]
+        fn min(&self) -> PyPos2 {
+            self.rect.min.into()
+        }
+
+        /// The bottom right corner
+        #[pymethod]
+        fn max(&self) -> PyPos2 {
+            self.rect.max.into()
+        }
+
+        /// The point halfway between `min` and `max`
+        #[pymethod]
+        fn center(&self) -> PyPos2 {
+            self.rect.center().into()
+        }
+
+        /// Horizontal size in points
+        #[pymethod]
+        fn width(&self) -> f32 {
+            self.rect.width()
+        }
+
+        /// Vertical size in points
+        #[pymethod]
+        fn height(&self) -> f32 {
+            self.rect.height()
+        }
+
+        /// `(width, height)` as a `Vec2`
+        #[pymethod]
+        fn size(&self) -> PyVec2 {
+            self.rect.size().into()
+        }
+
+        /// Whether `point` is inside or on the edge
+        #[pymethod]
+        fn contains(&self, point: PyObjectRef, vm: &VirtualMachine) -> PyResult<bool> {
+            Ok(self.rect.contains(parse_pos2(&point, vm)?))
+        }
+
+        /// Grown by `amount` on every side, or shrunk if it is negative
+        #[pymethod]
+        fn expand(&self, amount: ArgIntoFloat) -> Self {
+            self.rect.expand(*amount as f32).into()
+        }
+
+        /// Moved by `offset`
+        #[pymethod]
+        fn translate(&self, offset: PyObjectRef, vm: &VirtualMachine) -> PyResult<Self> {
+            Ok(self.rect.translate(parse_vec2(&offset, vm)?).into())
+        }
+
+        /// Whether the two rects overlap
+        #[pymethod]
+        fn intersects(&self, other: PyObjectRef, vm: &VirtualMachine) -> PyResult<bool> {
+            Ok(self.rect.intersects(parse_rect(&other, vm)?))
+        }
+
+        /// The overlap with `other`, which has negative size if there is none
+        #[pymethod]
+        fn intersect(&self, other: PyObjectRef, vm: &VirtualMachine) -> PyResult<Self> {
+            Ok(self.rect.intersect(parse_rect(&other, vm)?).into())
+        }
+
+        /// The smallest rect containing both
+        #[pymethod]
+        fn union(&self, other: PyObjectRef, vm: &VirtualMachine) -> PyResult<Self> {
+            Ok(self.rect.union(parse_rect(&other, vm)?).into())
+        }
+
+        /// The point `t` of the way across, with (0, 0) at `min` and (1, 1) at `max`
+        #[pymethod]
+        fn lerp(&self, t: PyObjectRef, vm: &VirtualMachine) -> PyResult<PyPos2> {
+            Ok(self.rect.lerp_inside(parse_vec2(&t, vm)?).into())
+        }
+    }
+
+    #[pyclass]
+    impl PyEgui {
+        /// A clickable button. Check `clicked()` on the response to act on it.
+        /// `fill` and `rounding` change its look, `min_size` makes it at least that big.
+        #[pymethod]
+        fn button(&self, args: ButtonArgs, vm: &VirtualMachine) -> PyResult<PyResponse> {
+            let style = self.ui.borrow().style().clone();
+            let text = widget_text(&args.text, &style, &self.ctx(), vm)?;
+            let mut button = egui::Button::new(text);
+            if let Some(fill) = parse_optional_color(args.fill, vm)? {
+                button = button.fill(fill);
+            }
+            if let Some(min_size) = args.min_size {
+                button = button.min_size(parse_vec2(&min_size, vm)?);
+            }
+            if let Some(rounding) = args.rounding {
+                button = button.rounding(*rounding as f32);
+            }
+            Ok(PyResponse::from(self.ui.borrow_mut().add(button)))
+        }
+
+        /// The `Color32` class, for building colors and converting between formats
+        #[pygetset(name = "Color32")]
+        fn color32_class(&self, vm: &VirtualMachine) -> PyTypeRef {
+            PyColor32::class(&vm.ctx).to_owned()
+        }
+
+        /// The `Vec2` class, for sizes and offsets
+        #[pygetset(name = "Vec2")]
+        fn vec2_class(&self, vm: &VirtualMachine) -> PyTypeRef {
+            PyVec2::class(&vm.ctx).to_owned()
+        }
+
+        /// The `Pos2` class, for positions on screen
+        #[pygetset(name = "Pos2")]
+        fn pos2_class(&self, vm: &VirtualMachine) -> PyTypeRef {
+            PyPos2::class(&vm.ctx).to_owned()
+        }
+
+        /// The `Rect` class
+        #[pygetset(name = "Rect")]
+        fn rect_class(&self, vm: &VirtualMachine) -> PyTypeRef {
+            PyRect::class(&vm.ctx).to_owned()
+        }
+
+        /// Text that isn't interactive. Takes a str, `RichText` or `LayoutJob`.
+        #[pymethod]
+        fn label(&self, args: LabelArgs, vm: &VirtualMachine) -> PyResult<PyResponse> {
+            let style = self.ui.borrow().style().clone();
+            let text = widget_text(&args.text, &style, &self.ctx(), vm)?;
+            Ok(PyResponse::from(self.ui.borrow_mut().label(text)))
+        }
+
+        /// Styled text for labels, buttons and the painter
+        #[pymethod]
+        fn rich_text(&self, args: StrArgs) -> PyRichText {
+            PyRichText {
+                rich: egui::RichText::new(args.text.as_str()),
+                family: None,
+            }
+        }
+
+        /// Make the TTF or OTF font in `data` available as the font family `name`,
+        /// from the next frame on. Its glyphs also fill in for ones the default fonts lack.
+        #[pymethod]
+        fn add_font(&self, args: AddFontArgs, vm: &VirtualMachine) -> PyResult<()> {
+            let data = args.data.borrow_buf().to_vec();
+            let mut fonts = self.fonts.borrow_mut();
+            let changed = fonts
+                .insert(args.name.as_str(), data)
+                .map_err(|e| vm.new_value_error(e))?;
+            if changed {
+                fonts.apply(&self.ctx());
+            }
+            Ok(())
+        }
+
+        /// Names of the fonts added by the project or with `add_font`
+        #[pymethod]
+        fn fonts(&self, vm: &VirtualMachine) -> PyListRef {
+            let names = self
+                .fonts
+                .borrow()
+                .names()
+                .map(|name| vm.ctx.new_str(name).into())
+                .collect();
+            vm.ctx.new_list(names)
+        }
+
+        /// A paragraph made of differently styled pieces
+        #[pymethod]
+        fn layout_job(&self) -> PyLayoutJob {
+            PyLayoutJob {
+                sections: RefCell::new(vec![]),
+                wrap_width: Cell::new(None),
+            }
+        }
+
+        /// The style of the script's own widgets, for the rest of this run.
+        /// Changing it leaves the rest of the app alone.
+        #[pymethod]
+        fn style(&self) -> PyStyle {
+            PyStyle {
+                ui: self.ui.clone(),
+            }
+        }
+
+        /// A one line text box showing `text`. Returns the edited text and the response,
+        /// so keep the text in a variable between runs: `s, resp = egui.text_edit_singleline(s)`
+        #[pymethod]
+        fn text_edit_singleline(&self, args: StrArgs) -> (String, PyResponse) {
+            let mut editable = args.text.to_string();
+            let ret = self.ui.borrow_mut().text_edit_singleline(&mut editable);
+            (editable, PyResponse::from(ret))
+        }
+
+        /// Draws anywhere in the script's area, on top of its widgets
+        #[pymethod]
+        fn painter(&self) -> PyPainter {
+            PyPainter {
+                paint: self.ui.borrow().painter().clone(),
+            }
+        }
+
+        /// Keys, pointer and time for this frame
+        #[pymethod]
+        fn input(&self) -> PyInput {
+            PyInput { ctx: self.ctx() }
+        }
+
+        /// Seconds since the app started
+        #[pymethod]
+        fn time(&self) -> f64 {
+            self.ctx().input(|i| i.time)
+        }
+
+        /// Seconds between frames, smoothed
+        #[pymethod]
+        fn dt(&self) -> f32 {
+            self.ctx().input(|i| i.stable_dt)
+        }
+
+        /// Counts up by one every frame the app draws
+        #[pymethod]
+        fn frame_number(&self) -> u64 {
+            self.ctx().cumulative_pass_nr()
+        }
+
+        /// Goes from 0 to 1 when `value` becomes true, and back again when it becomes false
+        #[pymethod]
+        fn animate_bool(&self, args: AnimateBoolArgs) -> f32 {
+            self.ctx()
+                .animate_bool(animation_id(args.id.as_str()), args.value)
+        }
+
+        /// Moves smoothly towards `target` over `duration` seconds, by default the
+        /// style's animation time
+        #[pymethod]
+        fn animate_value(&self, args: AnimateValueArgs, vm: &VirtualMachine) -> PyResult<f32> {
+            let ctx = self.ctx();
+            let duration = args
+                .duration
+                .map_or_else(|| ctx.style().animation_time, |duration| *duration as f32);
+            if duration.is_nan() || duration < 0.0 {
+                return Err(vm.new_value_error("duration must not be negative".to_owned()));
+            }
+            let id = animation_id(args.id.as_str());
+            Ok(ctx.animate_value_with_time(id, *args.target as f32, duration))
+        }
+
+        /// Run the script again next frame, even without input
+        #[pymethod]
+        fn request_repaint(&self) {
+            self.ctx().request_repaint();
+            self.schedule_run(0.0);
+        }
+
+        /// Run the script again after `seconds`, even without input
+        #[pymethod]
+        fn request_repaint_after(
+            &self,
+            args: RepaintAfterArgs,
+            vm: &VirtualMachine,
+        ) -> PyResult<()> {
+            let seconds = *args.seconds;
+            let duration = std::time::Duration::try_from_secs_f64(seconds)
+                .map_err(|_| vm.new_value_error(format!("Cannot repaint after {seconds} s")))?;
+            self.ctx().request_repaint_after(duration);
+            self.schedule_run(duration.as_secs_f64());
+            Ok(())
+        }
+
+        /// Space for custom drawing. `sense` is "hover" unless given, like "click|drag".
+        #[pymethod]
+        fn allocate_painter(
+            &self,
+            args: AllocatePainterArgs,
+            vm: &VirtualMachine,
+        ) -> Result<(PyResponse, PyPainter), PyBaseExceptionRef> {
+            let sense = args.sense.as_ref().map_or("hover", |sense| sense.as_str());
+            let sense = parse_sense_from_str(sense, vm)?;
+            let desired_size = parse_vec2(&args.desired_size, vm)?;
+
+            let (resp, paint) = self.ui.borrow_mut().allocate_painter(desired_size, sense);
+
+            Ok((PyResponse { resp }, PyPainter { paint }))
+        }
+
+        /// A table with one list of cells per row, shown with `str()`.
+        /// Only the rows scrolled into view are rendered, so long tables stay fast, and
+        /// sorting is only redone when the sort or the cells it depends on change.
+        /// Selection follows the values in the `key` column.
+        #[pymethod]
+        fn table(&self, args: TableArgs, vm: &VirtualMachine) -> PyResult<PyTableResponse> {
+            let columns: Vec<String> = args.columns.iter().map(|c| c.to_string()).collect();
+            let rows = Vec::<PyObjectRef>::try_from_object(vm, as_list(args.rows, vm)?)?;
+            let sizes = match args.widths {
+                Some(widths) => widths
+                    .into_iter()
+                    .map(|width| parse_column(width, vm))
+                    .collect::<PyResult<Vec<_>>>()?,
+                None => (0..columns.len())
+                    .map(|i| match i + 1 == columns.len() {
+                        true => egui_extras::Column::remainder(),
+                        false => egui_extras::Column::auto(),
+                    })
+                    .collect(),
+            };
+            if sizes.len() != columns.len() {
+                return Err(vm.new_value_error(format!(
+                    "Got {} widths for {} columns",
+                    sizes.len(),
+                    columns.len()
+                )));
+            }
+
+            if args.key >= columns.len().max(1) {
+                return Err(vm.new_value_error(format!(
+                    "Key column {} is out of range for {} columns",
+                    args.key,
+                    columns.len()
+                )));
+            }
+
+            let ctx = self.ctx();
+            let id = egui::Id::new(("script_table", args.id.as_str()));
+            let mut state = table::TableState::load(&ctx, id);
+            let sort = state.sort.filter(|&(column, _)| column < columns.len());
+
+            // Out of the map while in use, since cells' `__str__` could show another table
+            let name = args.id.to_string();
+            let mut cache = self.tables.borrow_mut().remove(&name).unwrap_or_default();
+            cache.update(&rows, args.key, sort, vm)?;
+            let (order, keys) = (&cache.order, &cache.keys);
+
+            let mut ui = self.ui.borrow_mut();
+            let row_height = args.row_height.map_or_else(
+                || ui.text_style_height(&egui::TextStyle::Body),
+                |height| *height as f32,
+            );
+            let mut builder = egui_extras::TableBuilder::new(&mut ui)
+                .id_salt(id)
+                .striped(args.striped)
+                .cell_layout(egui::Layout::left_to_right(egui::Align::Center));
+            if args.selectable {
+                builder = builder.sense(egui::Sense::click());
+            }
+            if let Some(height) = args.height {
+                builder = builder.max_scroll_height(*height as f32);
+            }
+            for size in sizes {
+                builder = builder.column(size.resizable(true).clip(true));
+            }
+
+            let mut error = None;
+            let mut clicked = None;
+            builder
+                .header(row_height, |mut header| {
+                    for (i, name) in columns.iter().enumerate() {
+                        header.col(|ui| {
+                            let arrow = match state.sort {
+                                Some((column, true)) if column == i => " ⬆",
+                                Some((column, false)) if column == i => " ⬇",
+                                _ => "",
+                            };
+                            let label = egui::Label::new(
+                                egui::RichText::new(format!("{name}{arrow}")).strong(),
+                            );
+                            if !args.sortable {
+                                ui.add(label);
+                            } else if ui.add(label.sense(egui::Sense::click())).clicked() {
+                                state.toggle_sort(i);
+                            }
+                        });
+                    }
+                })
+                .body(|body| {
+                    body.rows(row_height, order.len(), |mut row| {
+                        let index = order[row.index()];
+                        row.set_selected(state.selected.contains(&keys[index]));
+                        for column in 0..columns.len() {
+                            row.col(|ui| {
+                                let cell = match table_cell(&rows[index], column, vm) {
+                                    Ok(Some(cell)) => cell,
+                                    Ok(None) => return,
+                                    Err(e) => {
+                                        error.get_or_insert(e);
+                                        return;
+                                    }
+                                };
+                                match cell.str(vm) {
+                                    Ok(text) => {
+                                        // Selectable text would take the row's clicks
+                                        ui.add(egui::Label::new(text.as_str()).selectable(false));
+                                    }
+                                    Err(e) => {
+                                        error.get_or_insert(e);
+                                    }
+                                }
+                            });
+                        }
+                        if args.selectable && row.response().clicked() {
+                            clicked = Some(index);
+                        }
+                    });
+                });
+            drop(ui);
+
+            if let Some(index) = clicked {
+                state.click_row(&keys[index], ctx.input(|i| i.modifiers.command));
+            }
+            let response = PyTableResponse {
+                selected: (0..rows.len())
+                    .filter(|&index| state.selected.contains(&keys[index]))
+                    .collect(),
+                clicked,
+                sort: state.sort,
+            };
+            state.store(&ctx, id);
+            self.tables.borrow_mut().insert(name, cache);
+
+            match error {
+                Some(e) => Err(e),
+                None => Ok(response),
+            }
+        }
+
+        /// Start a plot. Add series to it, then call `show()`.
+        #[pymethod]
+        fn plot(&self, args: PlotArgs) -> PyPlot {
+            PyPlot {
+                ui: self.ui.clone(),
+                args,
+                items: RefCell::new(vec![]),
+                bounds: Cell::new(None),
+                reset: Cell::new(false),
+            }
+        }
+    }
+
+    impl PyEgui {
+        fn ctx(&self) -> egui::Context {
+            self.ui.borrow().ctx().clone()
+        }
+
+        /// The earliest request wins
+        fn schedule_run(&self, delay: f64) {
+            let at = self.ctx().input(|i| i.time) + delay;
+            let at = self.next_run.get().map_or(at, |next| next.min(at));
+            self.next_run.set(Some(at));
+        }
+    }
+
+    /// Keeps script animations from colliding with the host's
+    fn animation_id(name: &str) -> egui::Id {
+        egui::Id::new(("script_animation", name))
+    }
+
+    impl std::fmt::Debug for PyEgui {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            let ptr = self.ui.as_ptr();
+            writeln!(f, "PyEgui {:?}", ptr)
+        }
+    }
+
+    /// What happened to a widget this frame
+    #[pyattr]
+    #[pyclass(module = "rust_py_module", name = "PyResponse")]
+    #[derive(Debug, PyPayload)]
+    pub struct PyResponse {
+        pub resp: egui::Response,
+    }
+
+    #[pyclass]
+    impl PyResponse {
+        /// Whether the widget was clicked this frame
+        #[pymethod]
+        fn clicked(&self) -> bool {
+            self.resp.clicked()
+        }
+
+        /// Where the widget is on screen
+        #[pymethod]
+        fn rect(&self) -> PyRect {
+            self.resp.rect.into()
+        }
+    }
+
+    impl From<egui::Response> for PyResponse {
+        fn from(resp: egui::Response) -> Self {
+            Self { resp }
+        }
+    }
+
+    fn parse_key(name: &str, vm: &VirtualMachine) -> Result<egui::Key, PyBaseExceptionRef> {
+        egui::Key::from_name(name)
+            .or_else(|| {
+                egui::Key::ALL
+                    .iter()
+                    .copied()
+                    .find(|key| key.name().eq_ignore_ascii_case(name))
+            })
+            .ok_or_else(|| {
+                vm.new_value_error(format!(
+                    "Unknown key {name:?}, expected a name like \"A\", \"Space\" or \"ArrowUp\""
+                ))
+            })
+    }
+
+    /// Input for this frame, from `egui.input()`
+    #[pyattr]
+    #[pyclass(module = "rust_py_module", name = "PyInput")]
+    #[derive(Debug, PyPayload)]
+    pub struct PyInput {
+        pub ctx: egui::Context,
+    }
+
+    #[pyclass]
+    impl PyInput {
+        /// Whether the key named like "A", "Space" or "ArrowUp" went down this frame
+        #[pymethod]
+        fn key_pressed(&self, name: PyStrRef, vm: &VirtualMachine) -> PyResult<bool> {
+            let key = parse_key(name.as_str(), vm)?;
+            Ok(self.ctx.input(|i| i.key_pressed(key)))
+        }
+
+        /// Whether the named key is held down
+        #[pymethod]
+        fn key_down(&self, name: PyStrRef, vm: &VirtualMachine) -> PyResult<bool> {
+            let key = parse_key(name.as_str(), vm)?;
+            Ok(self.ctx.input(|i| i.key_down(key)))
+        }
+
+        /// Whether the named key went up this frame
+        #[pymethod]
+        fn key_released(&self, name: PyStrRef, vm: &VirtualMachine) -> PyResult<bool> {
+            let key = parse_key(name.as_str(), vm)?;
+            Ok(self.ctx.input(|i| i.key_released(key)))
+        }
+
+        /// Which of Alt, Ctrl, Shift and Command are held
+        #[pygetset]
+        fn modifiers(&self) -> PyModifiers {
+            PyModifiers {
+                modifiers: self.ctx.input(|i| i.modifiers),
+            }
+        }
+
+        /// The mouse or touch pointer
+        #[pygetset]
+        fn pointer(&self) -> PyPointer {
+            PyPointer {
+                pointer: self.ctx.input(|i| i.pointer.clone()),
+            }
+        }
+
+        /// How far to scroll this frame, smoothed
+        #[pygetset]
+        fn scroll_delta(&self) -> PyVec2 {
+            self.ctx.input(|i| i.smooth_scroll_delta).into()
+        }
+
+        /// Seconds since the app started
+        #[pygetset]
+        fn time(&self) -> f64 {
+            self.ctx.input(|i| i.time)
+        }
+
+        /// Seconds between frames, smoothed
+        #[pygetset]
+        fn stable_dt(&self) -> f32 {
+            self.ctx.input(|i| i.stable_dt)
+        }
+
+        /// The whole window, in points
+        #[pygetset]
+        fn screen_rect(&self) -> PyRect {
+            self.ctx.input(|i| i.screen_rect()).into()
+        }
+
+        /// Text typed this frame, one string per event
+        #[pygetset]
+        fn text_events(&self, vm: &VirtualMachine) -> PyListRef {
+            let texts = self.ctx.input(|i| {
+                i.events
+                    .iter()
+                    .filter_map(|event| match event {
+                        egui::Event::Text(text) => Some(vm.ctx.new_str(text.as_str()).into()),
+                        _ => None,
+                    })
+                    .collect()
+            });
+            vm.ctx.new_list(texts)
+        }
+    }
+
+    /// The state of the mouse or touch pointer
+    #[pyattr]
+    #[pyclass(module = "rust_py_module", name = "PyPointer")]
+    #[derive(Debug, PyPayload)]
+    pub struct PyPointer {
+        pub pointer: egui::PointerState,
+    }
+
+    #[pyclass]
+    impl PyPointer {
+        /// Where the pointer is, or None if it isn't over the window
+        #[pygetset]
+        fn pos(&self) -> Option<PyPos2> {
+            self.pointer.hover_pos().map(PyPos2::from)
+        }
+
+        /// How far the pointer moved since last frame
+        #[pygetset]
+        fn delta(&self) -> PyVec2 {
+            self.pointer.delta().into()
+        }
+
+        /// Points per second
+        #[pygetset]
+        fn velocity(&self) -> PyVec2 {
+            self.pointer.velocity().into()
+        }
+
+        /// Whether the left mouse button is held
+        #[pygetset]
+        fn primary_down(&self) -> bool {
+            self.pointer.primary_down()
+        }
+
+        /// Whether the right mouse button is held
+        #[pygetset]
+        fn secondary_down(&self) -> bool {
+            self.pointer.secondary_down()
+        }
+
+        /// Whether the left mouse button went down this frame
+        #[pygetset]
+        fn primary_pressed(&self) -> bool {
+            self.pointer.primary_pressed()
+        }
+
+        /// Whether the left mouse button went up this frame
+        #[pygetset]
+        fn primary_released(&self) -> bool {
+            self.pointer.primary_released()
+        }
+    }
+
+    /// Modifier keys held this frame
+    #[pyattr]
+    #[pyclass(module = "rust_py_module", name = "PyModifiers")]
+    #[derive(Debug, PyPayload)]
+    pub struct PyModifiers {
+        pub modifiers: egui::Modifiers,
+    }
+
+    #[pyclass]
+    impl PyModifiers {
+        /// Alt, or Option on Mac
+        #[pygetset]
+        fn alt(&self) -> bool {
+            self.modifiers.alt
+        }
+
+        /// Ctrl on every platform
+        #[pygetset]
+        fn ctrl(&self) -> bool {
+            self.modifiers.ctrl
+        }
+
+        /// Either Shift key
+        #[pygetset]
+        fn shift(&self) -> bool {
+            self.modifiers.shift
+        }
+
+        /// Ctrl on Windows and Linux, Cmd on Mac
+        #[pygetset]
+        fn command(&self) -> bool {
+            self.modifiers.command
+        }
+    }
+
+    /// Draws shapes and text, in points from the top left of the window
+    #[pyattr]
+    #[pyclass(module = "rust_py_module", name = "PyPainter")]
+    #[derive(PyPayload)]
+    pub struct PyPainter {
+        pub paint: egui::Painter,
+    }
+
+    impl PyPainter {
+        /// What colors default to when not given
+        fn text_color(&self) -> egui::Color32 {
+            self.paint.ctx().style().visuals.text_color()
+        }
+    }
+
+    #[pyclass]
+    impl PyPainter {
+        /// Connect `points` with straight lines, in the text color unless `color` is given
+        #[pymethod]
+        fn line(&self, args: LineArgs, vm: &VirtualMachine) -> Result<(), PyBaseExceptionRef> {
+            let color = parse_optional_color(args.color, vm)?.unwrap_or_else(|| self.text_color());
+            let points = as_list(args.points, vm)?;
+            let points = ArgIterable::<PyObjectRef>::try_from_object(vm, points)?
+                .iter(vm)?
+                .map(|point| parse_pos2(&point?, vm))
+                .collect::<PyResult<Vec<_>>>()?;
+            let stroke_width = args.stroke_width.map_or(1.0, |width| *width as f32);
+            for pair in points.windows(2) {
+                self.paint
+                    .line_segment([pair[0], pair[1]], Stroke::new(stroke_width, color));
+            }
+
+            Ok(())
+        }
+
+        /// Without a `fill`, only the outline is drawn
+        #[pymethod]
+        fn circle(&self, args: CircleArgs, vm: &VirtualMachine) -> Result<(), PyBaseExceptionRef> {
+            let fill = parse_optional_color(args.fill, vm)?;
+            let stroke_width = args
+                .stroke_width
+                .map_or(if fill.is_some() { 0.0 } else { 1.0 }, |width| {
+                    *width as f32
+                });
+            let stroke_color =
+                parse_optional_color(args.stroke_color, vm)?.unwrap_or_else(|| self.text_color());
+            self.paint.circle(
+                parse_pos2(&args.center, vm)?,
+                *args.radius as f32,
+                fill.unwrap_or(egui::Color32::TRANSPARENT),
+                Stroke::new(stroke_width, stroke_color),
+            );
+            Ok(())
+        }
+
+        /// `text` may be a str, `RichText` or `LayoutJob`. Parts without their own
+        /// color use `text_color`. `family` names the font for a plain str.
+        #[pymethod]
+        fn text(
+            &self,
+            args: PainterTextArgs,
+            vm: &VirtualMachine,
+        ) -> Result<PyRect, PyBaseExceptionRef> {
+            let pos = parse_pos2(&args.pos, vm)?;
+            let anchor = parse_align2_from_str(args.anchor.as_str(), vm)?;
+            let text_color =
+                parse_optional_color(args.text_color, vm)?.unwrap_or_else(|| self.text_color());
+            let ctx = self.paint.ctx();
+
+            if let Some(text) = args.text.payload::<rustpython_vm::builtins::PyStr>() {
+                let font = egui::FontId {
+                    family: match args.family {
+                        Some(name) => fonts::resolve_family(ctx, name.as_str()),
+                        None => egui::FontFamily::Proportional,
+                    },
+                    ..Default::default()
+                };
+                let rect = self
+                    .paint
+                    .text(pos, anchor, text.as_str(), font, text_color);
+                return Ok(PyRect { rect });
+            }
+
+            let style = ctx.style();
+            let job = widget_text(&args.text, &style, ctx, vm)?.into_layout_job(
+                &style,
+                egui::FontSelection::Default,
+                egui::Align::Center,
+            );
+            let galley = self.paint.layout_job(job);
+            let rect = anchor.anchor_size(pos, galley.size());
+            self.paint.galley(rect.min, galley, text_color);
+            Ok(PyRect { rect })
+        }
+    }
+
+    impl std::fmt::Debug for PyPainter {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
             writeln!(f, "PyPainter")
         }
     }
+
+    /// Lists, or anything with a `tolist()` method such as `ndarray` arrays
+    fn float_list(obj: PyObjectRef, vm: &VirtualMachine) -> PyResult<Vec<f64>> {
+        let obj = as_list(obj, vm)?;
+        ArgIterable::<ArgIntoFloat>::try_from_object(vm, obj)?
+            .iter(vm)?
+            .map(|v| v.map(f64::from))
+            .collect()
+    }
+
+    /// A list of rows, as lists or a 2D `ndarray` array
+    fn float_rows(obj: PyObjectRef, vm: &VirtualMachine) -> PyResult<Vec<Vec<f64>>> {
+        let obj = as_list(obj, vm)?;
+        ArgIterable::<PyObjectRef>::try_from_object(vm, obj)?
+            .iter(vm)?
+            .map(|row| float_list(row?, vm))
+            .collect()
+    }
+
+    fn as_list(obj: PyObjectRef, vm: &VirtualMachine) -> PyResult {
+        match vm.get_attribute_opt(obj.clone(), "tolist")? {
+            Some(tolist) => tolist.call((), vm),
+            None => Ok(obj),
+        }
+    }
+
+    fn parse_optional_color(
+        color: Option<PyObjectRef>,
+        vm: &VirtualMachine,
+    ) -> Result<Option<egui::Color32>, PyBaseExceptionRef> {
+        color.map(|color| parse_color(&color, vm)).transpose()
+    }
+
+    /// A str, `RichText` or `LayoutJob` as text for a widget
+    fn widget_text(
+        obj: &PyObjectRef,
+        style: &egui::Style,
+        ctx: &egui::Context,
+        vm: &VirtualMachine,
+    ) -> PyResult<egui::WidgetText> {
+        if let Some(text) = obj.payload::<rustpython_vm::builtins::PyStr>() {
+            Ok(text.as_str().into())
+        } else if let Some(rich) = obj.payload::<PyRichText>() {
+            Ok(rich.resolve(ctx).into())
+        } else if let Some(job) = obj.payload::<PyLayoutJob>() {
+            Ok(job.build(style, ctx).into())
+        } else {
+            Err(vm.new_type_error(format!(
+                "Expected str, RichText or LayoutJob, not {}",
+                obj.class().name()
+            )))
+        }
+    }
+
+    /// Text with styling, from `egui.rich_text()`
+    #[pyattr]
+    #[pyclass(module = "rust_py_module", name = "RichText")]
+    #[derive(Clone, PyPayload)]
+    pub struct PyRichText {
+        pub rich: egui::RichText,
+        /// Resolved when the text is used, since fonts can be added later
+        pub family: Option<String>,
+    }
+
+    /// Each method returns a new `RichText`, so they can be chained
+    #[pyclass]
+    impl PyRichText {
+        fn with(&self, f: impl FnOnce(egui::RichText) -> egui::RichText) -> Self {
+            Self {
+                rich: f(self.rich.clone()),
+                family: self.family.clone(),
+            }
+        }
+
+        /// The plain text, without styling
+        #[pygetset]
+        fn text(&self) -> String {
+            self.rich.text().to_owned()
+        }
+
+        /// Font size in points
+        #[pymethod]
+        fn size(&self, size: ArgIntoFloat) -> Self {
+            self.with(|rich| rich.size(*size as f32))
+        }
+
+        /// Use the strong text color. egui has no bold fonts by default, so for bold text
+        /// add one with `add_font` and use it with `family`.
+        #[pymethod]
+        fn strong(&self) -> Self {
+            self.with(|rich| rich.strong())
+        }
+
+        /// Use the faint text color
+        #[pymethod]
+        fn weak(&self) -> Self {
+            self.with(|rich| rich.weak())
+        }
+
+        /// Slanted text
+        #[pymethod]
+        fn italics(&self) -> Self {
+            self.with(|rich| rich.italics())
+        }
+
+        /// A line under the text
+        #[pymethod]
+        fn underline(&self) -> Self {
+            self.with(|rich| rich.underline())
+        }
+
+        /// A line through the text
+        #[pymethod]
+        fn strikethrough(&self) -> Self {
+            self.with(|rich| rich.strikethrough())
+        }
+
+        /// The monospace text style
+        #[pymethod]
+        fn monospace(&self) -> Self {
+            self.with(|rich| rich.monospace())
+        }
+
+        /// A font family by name: "Proportional", "Monospace", or one added with `add_font`
+        #[pymethod]
+        fn family(&self, name: PyStrRef) -> Self {
+            Self {
+                family: Some(name.to_string()),
+                ..self.clone()
+            }
+        }
+
+        /// The heading text style
+        #[pymethod]
+        fn heading(&self) -> Self {
+            self.with(|rich| rich.heading())
+        }
+
+        /// The small text style
+        #[pymethod]
+        fn small(&self) -> Self {
+            self.with(|rich| rich.small())
+        }
+
+        /// Text color, in any color format
+        #[pymethod]
+        fn color(&self, color: PyObjectRef, vm: &VirtualMachine) -> PyResult<Self> {
+            let color = parse_color(&color, vm)?;
+            Ok(self.with(|rich| rich.color(color)))
+        }
+
+        /// Color behind the text, in any color format
+        #[pymethod]
+        fn background_color(&self, color: PyObjectRef, vm: &VirtualMachine) -> PyResult<Self> {
+            let color = parse_color(&color, vm)?;
+            Ok(self.with(|rich| rich.background_color(color)))
+        }
+    }
+
+    impl std::fmt::Debug for PyRichText {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            writeln!(f, "PyRichText {:?}", self.rich.text())
+        }
+    }
+
+    impl PyRichText {
+        fn resolve(&self, ctx: &egui::Context) -> egui::RichText {
+            match &self.family {
+                Some(name) => self.rich.clone().family(fonts::resolve_family(ctx, name)),
+                None => self.rich.clone(),
+            }
+        }
+    }
+
+    /// A paragraph of differently styled pieces, from `egui.layout_job()`
+    #[pyattr]
+    #[pyclass(module = "rust_py_module", name = "LayoutJob")]
+    #[derive(Debug, PyPayload)]
+    pub struct PyLayoutJob {
+        pub sections: RefCell<Vec<PyRichText>>,
+        pub wrap_width: Cell<Option<f32>>,
+    }
+
+    #[pyclass]
+    impl PyLayoutJob {
+        /// Add a str or `RichText` to the end of the paragraph
+        #[pymethod]
+        fn append(&self, text: PyObjectRef, vm: &VirtualMachine) -> PyResult<()> {
+            let rich = if let Some(text) = text.payload::<rustpython_vm::builtins::PyStr>() {
+                PyRichText {
+                    rich: egui::RichText::new(text.as_str()),
+                    family: None,
+                }
+            } else if let Some(rich) = text.payload::<PyRichText>() {
+                rich.clone()
+            } else {
+                return Err(vm.new_type_error(format!(
+                    "Expected str or RichText, not {}",
+                    text.class().name()
+                )));
+            };
+            self.sections.borrow_mut().push(rich);
+            Ok(())
+        }
+
+        /// Wrap lines longer than this many points when painted, or None to never wrap.
+        /// Labels wrap to fit the layout instead.
+        #[pygetset]
+        fn wrap_width(&self) -> Option<f32> {
+            self.wrap_width.get()
+        }
+
+        #[pygetset(setter)]
+        fn set_wrap_width(&self, width: Option<ArgIntoFloat>) {
+            self.wrap_width.set(width.map(|width| *width as f32));
+        }
+    }
+
+    impl PyLayoutJob {
+        fn build(&self, style: &egui::Style, ctx: &egui::Context) -> egui::text::LayoutJob {
+            let mut job = egui::text::LayoutJob::default();
+            for section in self.sections.borrow().iter() {
+                section.resolve(ctx).append_to(
+                    &mut job,
+                    style,
+                    egui::FontSelection::Default,
+                    egui::Align::Center,
+                );
+            }
+            if let Some(width) = self.wrap_width.get() {
+                job.wrap.max_width = width;
+            }
+            job
+        }
+    }
+
+    #[derive(FromArgs)]
+    struct ButtonArgs {
+        /// A str, `RichText` or `LayoutJob`
+        #[pyarg(any)]
+        text: PyObjectRef,
+        #[pyarg(any, default)]
+        fill: Option<PyObjectRef>,
+        #[pyarg(any, default)]
+        min_size: Option<PyObjectRef>,
+        #[pyarg(any, default)]
+        rounding: Option<ArgIntoFloat>,
+    }
+
+    #[derive(FromArgs)]
+    struct LabelArgs {
+        /// A str, `RichText` or `LayoutJob`
+        #[pyarg(any)]
+        text: PyObjectRef,
+    }
+
+    #[derive(FromArgs)]
+    struct StrArgs {
+        #[pyarg(any)]
+        text: PyStrRef,
+    }
+
+    #[derive(FromArgs)]
+    struct AddFontArgs {
+        #[pyarg(any)]
+        name: PyStrRef,
+        /// TTF or OTF file contents
+        #[pyarg(any)]
+        data: ArgBytesLike,
+    }
+
+    #[derive(FromArgs)]
+    struct AnimateBoolArgs {
+        #[pyarg(any)]
+        id: PyStrRef,
+        #[pyarg(any)]
+        value: bool,
+    }
+
+    #[derive(FromArgs)]
+    struct AnimateValueArgs {
+        #[pyarg(any)]
+        id: PyStrRef,
+        #[pyarg(any)]
+        target: ArgIntoFloat,
+        #[pyarg(any, default)]
+        duration: Option<ArgIntoFloat>,
+    }
+
+    #[derive(FromArgs)]
+    struct RepaintAfterArgs {
+        #[pyarg(any)]
+        seconds: ArgIntoFloat,
+    }
+
+    #[derive(FromArgs)]
+    struct AllocatePainterArgs {
+        #[pyarg(any)]
+        desired_size: PyObjectRef,
+        #[pyarg(any, default)]
+        sense: Option<PyStrRef>,
+    }
+
+    #[derive(FromArgs)]
+    struct LineArgs {
+        #[pyarg(any)]
+        points: PyObjectRef,
+        /// 1 unless given
+        #[pyarg(any, default)]
+        stroke_width: Option<ArgIntoFloat>,
+        /// The text color unless given
+        #[pyarg(any, default)]
+        color: Option<PyObjectRef>,
+    }
+
+    #[derive(FromArgs)]
+    struct CircleArgs {
+        #[pyarg(any)]
+        center: PyObjectRef,
+        #[pyarg(any)]
+        radius: ArgIntoFloat,
+        #[pyarg(any, default)]
+        fill: Option<PyObjectRef>,
+        /// 0 for filled circles and 1 for outlines unless given
+        #[pyarg(any, default)]
+        stroke_width: Option<ArgIntoFloat>,
+        /// The text color unless given
+        #[pyarg(any, default)]
+        stroke_color: Option<PyObjectRef>,
+    }
+
+    #[derive(FromArgs)]
+    struct PainterTextArgs {
+        #[pyarg(any)]
+        pos: PyObjectRef,
+        /// Which part of the text is at `pos`, like "LEFT_TOP" or "CENTER_CENTER"
+        #[pyarg(any)]
+        anchor: PyStrRef,
+        /// A str, `RichText` or `LayoutJob`
+        #[pyarg(any)]
+        text: PyObjectRef,
+        /// The style's text color unless given
+        #[pyarg(any, default)]
+        text_color: Option<PyObjectRef>,
+        /// Font family for a plain str
+        #[pyarg(any, default)]
+        family: Option<PyStrRef>,
+    }
+
+    fn parse_text_style(name: &str) -> egui::TextStyle {
+        match name {
+            "Small" => egui::TextStyle::Small,
+            "Body" => egui::TextStyle::Body,
+            "Monospace" => egui::TextStyle::Monospace,
+            "Button" => egui::TextStyle::Button,
+            "Heading" => egui::TextStyle::Heading,
+            name => egui::TextStyle::Name(name.into()),
+        }
+    }
+
+    /// The look of the script's widgets, from `egui.style()`
+    #[pyattr]
+    #[pyclass(module = "rust_py_module", name = "PyStyle")]
+    #[derive(PyPayload)]
+    pub struct PyStyle {
+        pub ui: Rc<RefCell<Ui>>,
+    }
+
+    #[pyclass]
+    impl PyStyle {
+        /// Setting this replaces all colors with the dark or light defaults
+        #[pygetset]
+        fn dark_mode(&self) -> bool {
+            self.ui.borrow().visuals().dark_mode
+        }
+
+        #[pygetset(setter)]
+        fn set_dark_mode(&self, dark_mode: bool) {
+            self.ui.borrow_mut().style_mut().visuals = match dark_mode {
+                true => egui::Visuals::dark(),
+                false => egui::Visuals::light(),
+            };
+        }
+
+        /// Color of all text, or None for each widget's own
+        #[pygetset]
+        fn text_color(&self) -> Option<PyColor32> {
+            let color = self.ui.borrow().visuals().override_text_color;
+            color.map(PyColor32::from)
+        }
+
+        #[pygetset(setter)]
+        fn set_text_color(&self, color: Option<PyObjectRef>, vm: &VirtualMachine) -> PyResult<()> {
+            let color = parse_optional_color(color, vm)?;
+            self.ui.borrow_mut().visuals_mut().override_text_color = color;
+            Ok(())
+        }
+
+        /// Background of selected text and selected widgets
+        #[pygetset]
+        fn selection_color(&self) -> PyColor32 {
+            self.ui.borrow().visuals().selection.bg_fill.into()
+        }
+
+        #[pygetset(setter)]
+        fn set_selection_color(&self, color: PyObjectRef, vm: &VirtualMachine) -> PyResult<()> {
+            let color = parse_color(&color, vm)?;
+            self.ui.borrow_mut().visuals_mut().selection.bg_fill = color;
+            Ok(())
+        }
+
+        /// Color of links
+        #[pygetset]
+        fn hyperlink_color(&self) -> PyColor32 {
+            self.ui.borrow().visuals().hyperlink_color.into()
+        }
+
+        #[pygetset(setter)]
+        fn set_hyperlink_color(&self, color: PyObjectRef, vm: &VirtualMachine) -> PyResult<()> {
+            let color = parse_color(&color, vm)?;
+            self.ui.borrow_mut().visuals_mut().hyperlink_color = color;
+            Ok(())
+        }
+
+        /// Background of buttons that aren't hovered or pressed
+        #[pygetset]
+        fn button_fill(&self) -> PyColor32 {
+            self.ui
+                .borrow()
+                .visuals()
+                .widgets
+                .inactive
+                .weak_bg_fill
+                .into()
+        }
+
+        #[pygetset(setter)]
+        fn set_button_fill(&self, color: PyObjectRef, vm: &VirtualMachine) -> PyResult<()> {
+            let color = parse_color(&color, vm)?;
+            self.ui
+                .borrow_mut()
+                .visuals_mut()
+                .widgets
+                .inactive
+                .weak_bg_fill = color;
+            Ok(())
+        }
+
+        /// Background of text edits
+        #[pygetset]
+        fn extreme_bg_color(&self) -> PyColor32 {
+            self.ui.borrow().visuals().extreme_bg_color.into()
+        }
+
+        #[pygetset(setter)]
+        fn set_extreme_bg_color(&self, color: PyObjectRef, vm: &VirtualMachine) -> PyResult<()> {
+            let color = parse_color(&color, vm)?;
+            self.ui.borrow_mut().visuals_mut().extreme_bg_color = color;
+            Ok(())
+        }
+
+        /// Corner radius of every widget, in every state
+        #[pygetset]
+        fn widget_rounding(&self) -> f32 {
+            self.ui.borrow().visuals().widgets.inactive.rounding.nw
+        }
+
+        #[pygetset(setter)]
+        fn set_widget_rounding(&self, rounding: ArgIntoFloat) {
+            let mut ui = self.ui.borrow_mut();
+            let widgets = &mut ui.visuals_mut().widgets;
+            for visuals in [
+                &mut widgets.noninteractive,
+                &mut widgets.inactive,
+                &mut widgets.hovered,
+                &mut widgets.active,
+                &mut widgets.open,
+            ] {
+                visuals.rounding = egui::Rounding::same(*rounding as f32);
+            }
+        }
+
+        /// Gap between widgets, `(x, y)`
+        #[pygetset]
+        fn item_spacing(&self) -> PyVec2 {
+            self.ui.borrow().spacing().item_spacing.into()
+        }
+
+        #[pygetset(setter)]
+        fn set_item_spacing(&self, spacing: PyObjectRef, vm: &VirtualMachine) -> PyResult<()> {
+            let spacing = parse_vec2(&spacing, vm)?;
+            self.ui.borrow_mut().spacing_mut().item_spacing = spacing;
+            Ok(())
+        }
+
+        /// Padding between a button's frame and its text, `(x, y)`
+        #[pygetset]
+        fn button_padding(&self) -> PyVec2 {
+            self.ui.borrow().spacing().button_padding.into()
+        }
+
+        #[pygetset(setter)]
+        fn set_button_padding(&self, padding: PyObjectRef, vm: &VirtualMachine) -> PyResult<()> {
+            let padding = parse_vec2(&padding, vm)?;
+            self.ui.borrow_mut().spacing_mut().button_padding = padding;
+            Ok(())
+        }
+
+        /// How far indented content is moved right, in points
+        #[pygetset]
+        fn indent(&self) -> f32 {
+            self.ui.borrow().spacing().indent
+        }
+
+        #[pygetset(setter)]
+        fn set_indent(&self, indent: ArgIntoFloat) {
+            self.ui.borrow_mut().spacing_mut().indent = *indent as f32;
+        }
+
+        /// Text style every widget uses, like "Monospace", or None for each widget's own
+        #[pygetset]
+        fn text_style(&self) -> Option<String> {
+            let style = self.ui.borrow().style().override_text_style.clone();
+            style.map(|style| style.to_string())
+        }
+
+        #[pygetset(setter)]
+        fn set_text_style(&self, name: Option<PyStrRef>) {
+            self.ui.borrow_mut().style_mut().override_text_style =
+                name.map(|name| parse_text_style(name.as_str()));
+        }
+
+        /// Font size of a text style such as "Body" or "Heading"
+        #[pymethod]
+        fn text_size(&self, name: PyStrRef, vm: &VirtualMachine) -> PyResult<f32> {
+            let style = parse_text_style(name.as_str());
+            self.ui
+                .borrow()
+                .style()
+                .text_styles
+                .get(&style)
+                .map(|font| font.size)
+                .ok_or_else(|| vm.new_value_error(format!("No text style named {name}")))
+        }
+
+        /// Change the font size of a text style such as "Body" or "Heading"
+        #[pymethod]
+        fn set_text_size(
+            &self,
+            name: PyStrRef,
+            size: ArgIntoFloat,
+            vm: &VirtualMachine,
+        ) -> PyResult<()> {
+            let style = parse_text_style(name.as_str());
+            let mut ui = self.ui.borrow_mut();
+            let font = ui
+                .style_mut()
+                .text_styles
+                .get_mut(&style)
+                .ok_or_else(|| vm.new_value_error(format!("No text style named {name}")))?;
+            font.size = *size as f32;
+            Ok(())
+        }
+    }
+
+    impl std::fmt::Debug for PyStyle {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            writeln!(f, "PyStyle")
+        }
+    }
+
+    #[derive(FromArgs)]
+    struct TableArgs {
+        #[pyarg(any)]
+        id: PyStrRef,
+        #[pyarg(any)]
+        columns: Vec<PyStrRef>,
+        #[pyarg(any)]
+        rows: PyObjectRef,
+        /// Per column: a width in points, "auto" or "remainder"
+        #[pyarg(any, default)]
+        widths: Option<Vec<PyObjectRef>>,
+        #[pyarg(any, default = "true")]
+        striped: bool,
+        #[pyarg(any, default = "true")]
+        sortable: bool,
+        #[pyarg(any, default = "true")]
+        selectable: bool,
+        /// Scroll instead of growing past this height
+        #[pyarg(any, default)]
+        height: Option<ArgIntoFloat>,
+        #[pyarg(any, default)]
+        row_height: Option<ArgIntoFloat>,
+        /// Column whose values identify rows, so the selection stays on the same rows when
+        /// they are sorted, added or removed. Rows whose keys have the same `repr` are
+        /// selected together.
+        #[pyarg(any, default = "0")]
+        key: usize,
+    }
+
+    fn parse_column(width: PyObjectRef, vm: &VirtualMachine) -> PyResult<egui_extras::Column> {
+        if vm.is_none(&width) {
+            return Ok(egui_extras::Column::auto());
+        }
+        if let Some(name) = width.payload::<rustpython_vm::builtins::PyStr>() {
+            return match name.as_str() {
+                "auto" => Ok(egui_extras::Column::auto()),
+                "remainder" => Ok(egui_extras::Column::remainder()),
+                other => Err(vm.new_value_error(format!(
+                    "Column widths are numbers, \"auto\" or \"remainder\", not {other:?}"
+                ))),
+            };
+        }
+        let width = ArgIntoFloat::try_from_object(vm, width)?;
+        Ok(egui_extras::Column::initial(*width as f32))
+    }
+
+    fn sort_key(cell: &PyObjectRef, vm: &VirtualMachine) -> PyResult<table::SortKey> {
+        // Other types aren't tried as numbers, since a failed conversion builds an exception
+        if cell.payload_is::<PyInt>() || cell.payload_is::<PyFloat>() {
+            if let Ok(number) = ArgIntoFloat::try_from_object(vm, cell.clone()) {
+                return Ok(table::SortKey::Number(*number));
+            }
+        }
+        Ok(table::SortKey::from_text(cell.str(vm)?.to_string()))
+    }
+
+    /// Cell `column` of `row`, or None if the row is shorter. Lists and tuples are read
+    /// in place, other sequences are copied.
+    fn table_cell(
+        row: &PyObjectRef,
+        column: usize,
+        vm: &VirtualMachine,
+    ) -> PyResult<Option<PyObjectRef>> {
+        if let Some(list) = row.payload::<PyList>() {
+            return Ok(list.borrow_vec().get(column).cloned());
+        }
+        if let Some(tuple) = row.payload::<PyTuple>() {
+            return Ok(tuple.as_slice().get(column).cloned());
+        }
+        let cells = Vec::<PyObjectRef>::try_from_object(vm, row.clone())?;
+        Ok(cells.into_iter().nth(column))
+    }
+
+    /// What a table worked out from the script's rows, reused while the cells it was
+    /// worked out from are the same objects
+    #[derive(Default)]
+    pub struct TableCache {
+        /// Each row's key cell and sort cell. Holding on to them keeps comparing them
+        /// by identity valid.
+        cells: Vec<(Option<PyObjectRef>, Option<PyObjectRef>)>,
+        key: usize,
+        sort: Option<(usize, bool)>,
+        /// Rows in display order
+        order: Vec<usize>,
+        /// `repr` of each row's key, which unlike its hash is the same in every interpreter
+        keys: Vec<String>,
+    }
+
+    impl TableCache {
+        fn update(
+            &mut self,
+            rows: &[PyObjectRef],
+            key: usize,
+            sort: Option<(usize, bool)>,
+            vm: &VirtualMachine,
+        ) -> PyResult<()> {
+            let sort_column = sort.map(|(column, _)| column);
+            let row_cells = |row: &PyObjectRef| -> PyResult<_> {
+                let sort_cell = match sort_column {
+                    Some(column) => table_cell(row, column, vm)?,
+                    None => None,
+                };
+                Ok((table_cell(row, key, vm)?, sort_cell))
+            };
+
+            if key == self.key && sort == self.sort && rows.len() == self.cells.len() {
+                let same = |a: &Option<PyObjectRef>, b: &Option<PyObjectRef>| match (a, b) {
+                    (Some(a), Some(b)) => a.is(b),
+                    (a, b) => a.is_none() && b.is_none(),
+                };
+                let mut unchanged = true;
+                for (row, (key_cell, sort_cell)) in rows.iter().zip(&self.cells) {
+                    let (new_key_cell, new_sort_cell) = row_cells(row)?;
+                    if !same(&new_key_cell, key_cell) || !same(&new_sort_cell, sort_cell) {
+                        unchanged = false;
+                        break;
+                    }
+                }
+                if unchanged {
+                    return Ok(());
+                }
+            }
+
+            let cells = rows.iter().map(row_cells).collect::<PyResult<Vec<_>>>()?;
+            let keys = cells
+                .iter()
+                .map(|(key_cell, _)| match key_cell {
+                    Some(cell) => Ok(cell.repr(vm)?.to_string()),
+                    None => Ok(String::new()),
+                })
+                .collect::<PyResult<Vec<_>>>()?;
+            let order = match sort {
+                Some((_, ascending)) => {
+                    let sort_keys = cells
+                        .iter()
+                        .map(|(_, sort_cell)| match sort_cell {
+                            Some(cell) => sort_key(cell, vm),
+                            None => Ok(table::SortKey::Text(String::new())),
+                        })
+                        .collect::<PyResult<Vec<_>>>()?;
+                    table::sorted_order(&sort_keys, ascending)
+                }
+                None => (0..rows.len()).collect(),
+            };
+
+            *self = Self {
+                cells,
+                key,
+                sort,
+                order,
+                keys,
+            };
+            Ok(())
+        }
+    }
+
+    /// What the user did with a table this frame
+    #[pyattr]
+    #[pyclass(module = "rust_py_module", name = "PyTableResponse")]
+    #[derive(Debug, PyPayload)]
+    pub struct PyTableResponse {
+        pub selected: Vec<usize>,
+        pub clicked: Option<usize>,
+        pub sort: Option<(usize, bool)>,
+    }
+
+    #[pyclass]
+    impl PyTableResponse {
+        /// Indices into the rows that were passed in, whatever the sort order
+        #[pygetset]
+        fn selected(&self, vm: &VirtualMachine) -> PyListRef {
+            let rows = self
+                .selected
+                .iter()
+                .map(|&row| vm.ctx.new_int(row).into())
+                .collect();
+            vm.ctx.new_list(rows)
+        }
+
+        /// The row clicked this frame, if any
+        #[pygetset]
+        fn clicked(&self) -> Option<usize> {
+            self.clicked
+        }
+
+        /// `(column, ascending)`, or None if unsorted
+        #[pygetset]
+        fn sort(&self) -> Option<(usize, bool)> {
+            self.sort
+        }
+    }
+
+    #[derive(FromArgs)]
+    pub struct PlotArgs {
+        #[pyarg(any)]
+        id: PyStrRef,
+        #[pyarg(any, default)]
+        height: Option<ArgIntoFloat>,
+        #[pyarg(any, default)]
+        width: Option<ArgIntoFloat>,
+        #[pyarg(any, default = "true")]
+        legend: bool,
+        #[pyarg(any, default)]
+        x_label: Option<PyStrRef>,
+        #[pyarg(any, default)]
+        y_label: Option<PyStrRef>,
+        /// Plots with the same link group share their axes and cursor
+        #[pyarg(any, default)]
+        link: Option<PyStrRef>,
+        #[pyarg(any, default = "true")]
+        allow_zoom: bool,
+        #[pyarg(any, default = "true")]
+        allow_drag: bool,
+        /// Fixed ratio of y units to x units
+        #[pyarg(any, default)]
+        data_aspect: Option<ArgIntoFloat>,
+    }
+
+    #[derive(FromArgs)]
+    struct SeriesArgs {
+        #[pyarg(positional)]
+        x: PyObjectRef,
+        #[pyarg(positional, default)]
+        y: Option<PyObjectRef>,
+        #[pyarg(any, default)]
+        name: Option<PyStrRef>,
+        #[pyarg(any, default)]
+        color: Option<PyObjectRef>,
+        /// Line width, or point radius
+        #[pyarg(any, default)]
+        width: Option<ArgIntoFloat>,
+    }
+
+    impl SeriesArgs {
+        fn points(&self, vm: &VirtualMachine) -> PyResult<Vec<[f64; 2]>> {
+            let x = float_list(self.x.clone(), vm)?;
+            let y = self.y.clone().map(|y| float_list(y, vm)).transpose()?;
+            plot::points(x, y).map_err(|e| vm.new_value_error(e))
+        }
+    }
+
+    #[derive(FromArgs)]
+    struct BarArgs {
+        #[pyarg(positional)]
+        heights: PyObjectRef,
+        /// Where the bars go, one per height. Defaults to 0, 1, 2...
+        #[pyarg(any, default)]
+        x: Option<PyObjectRef>,
+        #[pyarg(any, default)]
+        name: Option<PyStrRef>,
+        #[pyarg(any, default)]
+        color: Option<PyObjectRef>,
+        #[pyarg(any, default)]
+        width: Option<ArgIntoFloat>,
+    }
+
+    #[derive(FromArgs)]
+    struct HistogramArgs {
+        #[pyarg(positional)]
+        values: PyObjectRef,
+        #[pyarg(any, default = "10")]
+        bins: usize,
+        #[pyarg(any, default)]
+        name: Option<PyStrRef>,
+        #[pyarg(any, default)]
+        color: Option<PyObjectRef>,
+    }
+
+    #[derive(FromArgs)]
+    struct BoxPlotArgs {
+        #[pyarg(positional)]
+        values: PyObjectRef,
+        #[pyarg(any, default)]
+        x: Option<ArgIntoFloat>,
+        #[pyarg(any, default)]
+        name: Option<PyStrRef>,
+        #[pyarg(any, default)]
+        color: Option<PyObjectRef>,
+    }
+
+    #[derive(FromArgs)]
+    struct HeatmapArgs {
+        #[pyarg(positional)]
+        rows: PyObjectRef,
+        /// Bottom left corner of the heatmap
+        #[pyarg(any, default)]
+        x: Option<ArgIntoFloat>,
+        #[pyarg(any, default)]
+        y: Option<ArgIntoFloat>,
+        #[pyarg(any, default)]
+        name: Option<PyStrRef>,
+    }
+
+    /// A plot being built, from `egui.plot()`
+    #[pyattr]
+    #[pyclass(module = "rust_py_module", name = "PyPlot")]
+    #[derive(PyPayload)]
+    pub struct PyPlot {
+        pub ui: Rc<RefCell<Ui>>,
+        pub args: PlotArgs,
+        pub items: RefCell<Vec<plot::PlotItem>>,
+        /// `[x_min, x_max, y_min, y_max]` to move the view to
+        pub bounds: Cell<Option<[f64; 4]>>,
+        pub reset: Cell<bool>,
+    }
+
+    #[pyclass]
+    impl PyPlot {
+        /// `line(y)` plots against indices, `line(x, y)` against `x`
+        #[pymethod]
+        fn line(&self, args: SeriesArgs, vm: &VirtualMachine) -> PyResult<()> {
+            let mut line = egui_plot::Line::new(args.points(vm)?);
+            if let Some(name) = &args.name {
+                line = line.name(name.as_str());
+            }
+            if let Some(color) = parse_optional_color(args.color, vm)? {
+                line = line.color(color);
+            }
+            if let Some(width) = args.width {
+                line = line.width(*width as f32);
+            }
+            self.items.borrow_mut().push(plot::PlotItem::Line(line));
+            Ok(())
+        }
+
+        /// Like `line`, but unconnected points
+        #[pymethod]
+        fn scatter(&self, args: SeriesArgs, vm: &VirtualMachine) -> PyResult<()> {
+            let mut points = egui_plot::Points::new(args.points(vm)?);
+            if let Some(name) = &args.name {
+                points = points.name(name.as_str());
+            }
+            if let Some(color) = parse_optional_color(args.color, vm)? {
+                points = points.color(color);
+            }
+            if let Some(radius) = args.width {
+                points = points.radius(*radius as f32);
+            }
+            self.items.borrow_mut().push(plot::PlotItem::Points(points));
+            Ok(())
+        }
+
+        /// A bar chart, one bar per height
+        #[pymethod]
+        fn bar(&self, args: BarArgs, vm: &VirtualMachine) -> PyResult<()> {
+            let heights = float_list(args.heights, vm)?;
+            let x = match args.x {
+                Some(x) => float_list(x, vm)?,
+                None => (0..heights.len()).map(|i| i as f64).collect(),
+            };
+            if x.len() != heights.len() {
+                return Err(vm.new_value_error(format!(
+                    "x and heights must be the same length, not {} and {}",
+                    x.len(),
+                    heights.len()
+                )));
+            }
+
+            let bars = x
+                .into_iter()
+                .zip(heights)
+                .map(|(x, height)| egui_plot::Bar::new(x, height))
+                .collect();
+            let mut chart = egui_plot::BarChart::new(bars);
+            if let Some(width) = args.width {
+                chart = chart.width(*width);
+            }
+            self.push_chart(chart, args.name, args.color, vm)
+        }
+
+        /// Bars counting how many `values` fall in each of `bins` equal ranges, at most 10000
+        #[pymethod]
+        fn histogram(&self, args: HistogramArgs, vm: &VirtualMachine) -> PyResult<()> {
+            let values = float_list(args.values, vm)?;
+            let bars = plot::histogram(&values, args.bins).map_err(|e| vm.new_value_error(e))?;
+            let chart = egui_plot::BarChart::new(bars);
+            self.push_chart(chart, args.name, args.color, vm)
+        }
+
+        /// A box and whiskers summarizing `values`, centered on `x`
+        #[pymethod]
+        fn box_plot(&self, args: BoxPlotArgs, vm: &VirtualMachine) -> PyResult<()> {
+            let values = float_list(args.values, vm)?;
+            let x = args.x.map_or(0.0, f64::from);
+            let mut boxes =
+                egui_plot::BoxPlot::new(plot::box_elem(&values, x).into_iter().collect());
+            if let Some(name) = &args.name {
+                boxes = boxes.name(name.as_str());
+            }
+            if let Some(color) = parse_optional_color(args.color, vm)? {
+                boxes = boxes.color(color);
+            }
+            self.items.borrow_mut().push(plot::PlotItem::Boxes(boxes));
+            Ok(())
+        }
+
+        /// A grid of unit squares colored by value, first row on top
+        #[pymethod]
+        fn heatmap(&self, args: HeatmapArgs, vm: &VirtualMachine) -> PyResult<()> {
+            let rows = float_rows(args.rows, vm)?;
+            let mut items = self.items.borrow_mut();
+            let id = egui::Id::new(("script_heatmap", self.args.id.as_str(), items.len()));
+            let ctx = self.ui.borrow().ctx().clone();
+            let corner = [args.x, args.y].map(|v| v.map_or(0.0, f64::from));
+            if let Some(mut image) = plot::heatmap(&ctx, id, &rows, corner) {
+                if let Some(name) = &args.name {
+                    image = image.name(name.as_str());
+                }
+                items.push(plot::PlotItem::Image(image));
+            }
+            Ok(())
+        }
+
+        /// Move the view, overriding zoom and pan
+        #[pymethod]
+        fn set_bounds(
+            &self,
+            x_min: ArgIntoFloat,
+            x_max: ArgIntoFloat,
+            y_min: ArgIntoFloat,
+            y_max: ArgIntoFloat,
+        ) {
+            let bounds = [x_min, x_max, y_min, y_max].map(f64::from);
+            self.bounds.set(Some(bounds));
+        }
+
+        /// Forget zoom and pan, and fit the view to the data again
+        #[pymethod]
+        fn reset(&self) {
+            self.reset.set(true);
+        }
+
+        /// Draw the plot with everything added since it was started
+        #[pymethod]
+        fn show(&self) -> PyPlotResponse {
+            let args = &self.args;
+            let mut plot = egui_plot::Plot::new(("script_plot", args.id.as_str()))
+                .allow_zoom(args.allow_zoom)
+                .allow_drag(args.allow_drag);
+            if let Some(height) = &args.height {
+                plot = plot.height(**height as f32);
+            }
+            if let Some(width) = &args.width {
+                plot = plot.width(**width as f32);
+            }
+            if args.legend {
+                plot = plot.legend(egui_plot::Legend::default());
+            }
+            if let Some(label) = &args.x_label {
+                plot = plot.x_axis_label(label.as_str());
+            }
+            if let Some(label) = &args.y_label {
+                plot = plot.y_axis_label(label.as_str());
+            }
+            if let Some(group) = &args.link {
+                let group = egui::Id::new(("script_plot_link", group.as_str()));
+                plot = plot
+                    .link_axis(group, true, true)
+                    .link_cursor(group, true, true);
+            }
+            if let Some(aspect) = &args.data_aspect {
+                plot = plot.data_aspect(**aspect as f32);
+            }
+            if self.reset.take() {
+                plot = plot.reset();
+            }
+
+            let items = self.items.take();
+            let bounds = self.bounds.take();
+            let shown = plot.show(&mut self.ui.borrow_mut(), |plot_ui| {
+                for item in items {
+                    item.add_to(plot_ui);
+                }
+                if let Some([x_min, x_max, y_min, y_max]) = bounds {
+                    plot_ui.set_plot_bounds(egui_plot::PlotBounds::from_min_max(
+                        [x_min, y_min],
+                        [x_max, y_max],
+                    ));
+                }
+            });
+
+            let bounds = shown.transform.bounds();
+            let pointer = shown
+                .response
+                .hover_pos()
+                .map(|pos| shown.transform.value_from_position(pos));
+            PyPlotResponse {
+                resp: shown.response,
+                bounds: [
+                    bounds.min()[0],
+                    bounds.max()[0],
+                    bounds.min()[1],
+                    bounds.max()[1],
+                ],
+                pointer: pointer.map(|p| (p.x, p.y)),
+            }
+        }
+    }
+
+    impl PyPlot {
+        fn push_chart(
+            &self,
+            mut chart: egui_plot::BarChart,
+            name: Option<PyStrRef>,
+            color: Option<PyObjectRef>,
+            vm: &VirtualMachine,
+        ) -> PyResult<()> {
+            if let Some(name) = &name {
+                chart = chart.name(name.as_str());
+            }
+            if let Some(color) = parse_optional_color(color, vm)? {
+                chart = chart.color(color);
+            }
+            self.items.borrow_mut().push(plot::PlotItem::Bars(chart));
+            Ok(())
+        }
+    }
+
+    impl std::fmt::Debug for PyPlot {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            writeln!(f, "PyPlot {}", self.args.id.as_str())
+        }
+    }
+
+    /// What `show()` returns for a plot
+    #[pyattr]
+    #[pyclass(module = "rust_py_module", name = "PyPlotResponse")]
+    #[derive(Debug, PyPayload)]
+    pub struct PyPlotResponse {
+        pub resp: egui::Response,
+        pub bounds: [f64; 4],
+        pub pointer: Option<(f64, f64)>,
+    }
+
+    #[pyclass]
+    impl PyPlotResponse {
+        /// The response of the plot widget, for hover and clicks
+        #[pygetset]
+        fn response(&self) -> PyResponse {
+            PyResponse::from(self.resp.clone())
+        }
+
+        /// `(x_min, x_max, y_min, y_max)` of the view, after zoom and pan
+        #[pygetset]
+        fn bounds(&self) -> (f64, f64, f64, f64) {
+            let [x_min, x_max, y_min, y_max] = self.bounds;
+            (x_min, x_max, y_min, y_max)
+        }
+
+        /// The pointer in plot coordinates, or None if it isn't over the plot
+        #[pygetset]
+        fn pointer(&self) -> Option<(f64, f64)> {
+            self.pointer
+        }
+    }
 }
@@ -1,3 +1,7 @@
+/// Counts script allocations, so the policy's memory budget applies
+#[global_allocator]
+static ALLOCATOR: eframe_template::CountingAllocator = eframe_template::CountingAllocator;
+
 // When compiling natively:
 #[cfg(not(target_arch = "wasm32"))]
 fn main() -> eframe::Result {
@@ -0,0 +1,216 @@
+//! Approximate allocation budget for scripts.
+//!
+//! [`CountingAllocator`] counts what is allocated and freed on a thread while a script
+//! runs there, against the [`MemoryBudget`] of the running [`crate::Runtime`]. What one
+//! run keeps alive still counts in the next, until the runtime is reset, and the host's
+//! own allocations between runs, and those of other threads, don't count. When a run goes over its budget, a `MemoryError` is raised
+//! inside the interpreter at the next bytecode instruction, so a runaway script unwinds
+//! like any other Python exception instead of taking the whole app down.
+//!
+//! The allocator is opt-in, since a library can't choose the global allocator for its
+//! users. Binaries install it with
+//! `#[global_allocator] static ALLOCATOR: CountingAllocator = CountingAllocator;`,
+//! and without it budgets are never exceeded.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    sync::{
+        atomic::{AtomicBool, AtomicIsize, Ordering},
+        Arc,
+    },
+};
+
+use rustpython_vm::VirtualMachine;
+
+pub struct CountingAllocator;
+
+/// What one runtime's runs have allocated
+struct Account {
+    /// Bytes allocated minus bytes freed during all runs so far
+    live: AtomicIsize,
+    limit: isize,
+    exceeded: AtomicBool,
+    /// Whether a run is in progress that the watchdog hasn't interrupted yet
+    armed: AtomicBool,
+}
+
+thread_local! {
+    /// The account of the run in progress on this thread, if any
+    static CURRENT: Cell<*const Account> = const { Cell::new(std::ptr::null()) };
+}
+
+/// rustpython asks for zero-sized blocks, which `System` must never be given, so those
+/// get a dangling pointer that is never freed
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.size() == 0 {
+            return dangling(layout);
+        }
+        count(layout.size() as isize);
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        if layout.size() == 0 {
+            return dangling(layout);
+        }
+        count(layout.size() as isize);
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if layout.size() == 0 {
+            return;
+        }
+        count(-(layout.size() as isize));
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        if layout.size() == 0 {
+            return self.alloc(new_layout);
+        }
+        if new_size == 0 {
+            self.dealloc(ptr, layout);
+            return dangling(new_layout);
+        }
+        count(new_size as isize - layout.size() as isize);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+fn dangling(layout: Layout) -> *mut u8 {
+    std::ptr::without_provenance_mut(layout.align())
+}
+
+/// Must not allocate, since it is called from inside the allocator
+fn count(bytes: isize) {
+    // The thread local is gone while the thread shuts down, and nothing runs then
+    let _ = CURRENT.try_with(|current| {
+        let account = current.get();
+        if account.is_null() {
+            return;
+        }
+        // SAFETY: Only set between `MemoryBudget::enter` and `exit`, and the budget owns
+        // the account for as long as it exists
+        let account = unsafe { &*account };
+        let live = account.live.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        if bytes > 0 && live > account.limit {
+            account.exceeded.store(true, Ordering::Relaxed);
+        }
+    });
+}
+
+/// Enforces a budget of `budget` bytes on what the runs of one runtime allocate and don't free
+pub struct MemoryBudget {
+    account: Arc<Account>,
+    #[cfg(not(target_arch = "wasm32"))]
+    watchdog: Option<std::thread::JoinHandle<()>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    stop: Arc<AtomicBool>,
+}
+
+impl MemoryBudget {
+    /// A budget of zero means unlimited
+    pub fn new(budget: usize, vm: &mut VirtualMachine) -> Self {
+        let account = Arc::new(Account {
+            live: AtomicIsize::new(0),
+            limit: match budget {
+                0 => isize::MAX,
+                budget => budget.min(isize::MAX as usize) as isize,
+            },
+            exceeded: AtomicBool::new(false),
+            armed: AtomicBool::new(false),
+        });
+
+        let (sender, receiver) = rustpython_vm::signal::user_signal_channel();
+        vm.set_user_signal_channel(receiver);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let stop = Arc::new(AtomicBool::new(false));
+            let watchdog = std::thread::Builder::new()
+                .name("memory watchdog".into())
+                .spawn({
+                    let stop = stop.clone();
+                    let account = account.clone();
+                    move || watchdog(sender, account, stop)
+                })
+                .ok();
+            Self {
+                account,
+                watchdog,
+                stop,
+            }
+        }
+
+        // No threads on the web, so exceeding the budget is only noticed between runs
+        #[cfg(target_arch = "wasm32")]
+        {
+            drop(sender);
+            Self { account }
+        }
+    }
+
+    /// Count this thread's allocations against the budget while a script runs
+    pub fn enter(&self) {
+        self.account.exceeded.store(false, Ordering::Relaxed);
+        self.account.armed.store(true, Ordering::Relaxed);
+        CURRENT.with(|current| current.set(Arc::as_ptr(&self.account)));
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(watchdog) = &self.watchdog {
+            watchdog.thread().unpark();
+        }
+    }
+
+    /// Stop counting. Returns true if the budget was exceeded at any point during the run.
+    pub fn exit(&self) -> bool {
+        CURRENT.with(|current| current.set(std::ptr::null()));
+        self.account.armed.store(false, Ordering::Relaxed);
+        self.account.exceeded.swap(false, Ordering::Relaxed)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Drop for MemoryBudget {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(watchdog) = self.watchdog.take() {
+            watchdog.thread().unpark();
+            let _ = watchdog.join();
+        }
+    }
+}
+
+/// Polls the account while a script runs and interrupts the interpreter once the
+/// budget is blown. This can't be done from the allocator itself, because sending
+/// the signal allocates.
+#[cfg(not(target_arch = "wasm32"))]
+fn watchdog(
+    sender: rustpython_vm::signal::UserSignalSender,
+    account: Arc<Account>,
+    stop: Arc<AtomicBool>,
+) {
+    while !stop.load(Ordering::Relaxed) {
+        if !account.armed.load(Ordering::Relaxed) {
+            // Nothing is running
+            std::thread::park();
+            continue;
+        }
+
+        if account.exceeded.load(Ordering::Relaxed) {
+            let _ = sender.send(Box::new(|vm: &VirtualMachine| {
+                Err(vm.new_exception_msg(
+                    vm.ctx.exceptions.memory_error.to_owned(),
+                    "script exceeded its memory budget".to_owned(),
+                ))
+            }));
+            // Only interrupt once per run
+            account.armed.store(false, Ordering::Relaxed);
+        }
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+    }
+}
@@ -0,0 +1,272 @@
+//! Recording input from a real session and replaying it deterministically, one frame at a time
+
+use egui::{Event, Key, Modifiers, Pos2, RawInput, Vec2};
+
+use crate::headless::ScriptedEvent;
+
+/// A timeline of input events, saved as JSON. Pointer positions are relative to the top left
+/// corner of the script's area, so they line up wherever the script is drawn.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default, PartialEq)]
+pub struct InputRecording {
+    /// The size of the script's area when recorded, down to the bottom of the screen
+    pub script_size: Vec2,
+    /// How many frames the recording lasts
+    pub frames: usize,
+    pub events: Vec<ScriptedEvent>,
+    /// The rest of each frame's input, which recordings from before it was kept lack
+    #[serde(default)]
+    pub inputs: Vec<FrameInput>,
+}
+
+/// What a recorded frame's input was besides its events, so animations replay the same
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameInput {
+    /// Seconds since the recording started
+    pub time: f64,
+    pub predicted_dt: f32,
+    pub modifiers: Modifiers,
+}
+
+impl InputRecording {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Recordings are always serializable")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// The events, with pointer positions moved to a script area whose top left is `origin`
+    pub fn events_at(&self, origin: Pos2) -> Vec<ScriptedEvent> {
+        self.events
+            .iter()
+            .map(|scripted| ScriptedEvent {
+                frame: scripted.frame,
+                event: translate(&scripted.event, origin.to_vec2()),
+            })
+            .collect()
+    }
+}
+
+#[derive(Default)]
+pub enum Playback {
+    #[default]
+    Idle,
+    Recording(Recorder),
+    Replaying {
+        recording: InputRecording,
+        frame: usize,
+        /// `RawInput::time` when the replay started, which recorded times are relative to
+        start: Option<f64>,
+    },
+}
+
+/// A recording in progress, with what it needs to tell the script's input from the host's
+pub struct Recorder {
+    recording: InputRecording,
+    start: Option<f64>,
+    /// Whether the pointer was last seen over the script
+    over_script: bool,
+    /// Whether a button was pressed over the script and not released yet
+    dragging: bool,
+}
+
+impl Playback {
+    pub fn is_idle(&self) -> bool {
+        matches!(self, Self::Idle)
+    }
+
+    pub fn start_recording(&mut self, script_size: Vec2) {
+        *self = Self::Recording(Recorder {
+            recording: InputRecording {
+                script_size,
+                ..Default::default()
+            },
+            start: None,
+            over_script: false,
+            dragging: false,
+        });
+    }
+
+    pub fn start_replay(&mut self, recording: InputRecording) {
+        *self = Self::Replaying {
+            recording,
+            frame: 0,
+            start: None,
+        };
+    }
+
+    /// Returns the recording, if one was in progress
+    pub fn stop(&mut self) -> Option<InputRecording> {
+        match std::mem::take(self) {
+            Self::Recording(recorder) => Some(recorder.recording),
+            _ => None,
+        }
+    }
+
+    /// Call from `eframe::App::raw_input_hook`, before egui sees the input. `script_origin`
+    /// is the top left corner of the script's area, which pointer positions are saved
+    /// relative to and replayed from.
+    ///
+    /// While recording, only input meant for the script is kept: pointer input where
+    /// `over_script` is true, or that continues a drag that started there, and keyboard
+    /// input if `keyboard_to_script`. Clicks on the host's own UI, like the one that stops
+    /// the recording, would do something else entirely when replayed.
+    ///
+    /// While replaying, real input is swallowed, except for Escape which stops the replay.
+    /// The screen keeps its real size, so the host's layout stays put around the script.
+    pub fn process_input(
+        &mut self,
+        raw_input: &mut RawInput,
+        script_origin: Pos2,
+        over_script: impl Fn(Pos2) -> bool,
+        keyboard_to_script: bool,
+    ) {
+        match self {
+            Self::Idle => (),
+            Self::Recording(recorder) => {
+                recorder.record(raw_input, script_origin, over_script, keyboard_to_script);
+            }
+            Self::Replaying {
+                recording,
+                frame,
+                start,
+            } => {
+                let escape = raw_input.events.iter().any(|event| {
+                    matches!(
+                        event,
+                        Event::Key {
+                            key: Key::Escape,
+                            pressed: true,
+                            ..
+                        }
+                    )
+                });
+
+                let start = *start.get_or_insert(raw_input.time.unwrap_or(0.0));
+                match recording.inputs.get(*frame) {
+                    Some(input) => {
+                        raw_input.time = Some(start + input.time);
+                        raw_input.predicted_dt = input.predicted_dt;
+                        raw_input.modifiers = input.modifiers;
+                    }
+                    None => raw_input.modifiers = Modifiers::NONE,
+                }
+                raw_input.events = recording
+                    .events
+                    .iter()
+                    .filter(|scripted| scripted.frame == *frame)
+                    .map(|scripted| translate(&scripted.event, script_origin.to_vec2()))
+                    .collect();
+                *frame += 1;
+
+                if escape || *frame >= recording.frames {
+                    *self = Self::Idle;
+                }
+            }
+        }
+    }
+}
+
+impl Recorder {
+    fn record(
+        &mut self,
+        raw_input: &RawInput,
+        script_origin: Pos2,
+        over_script: impl Fn(Pos2) -> bool,
+        keyboard_to_script: bool,
+    ) {
+        let frame = self.recording.frames;
+        let time = match (raw_input.time, self.start) {
+            (Some(now), Some(start)) => now - start,
+            (Some(now), None) => {
+                self.start = Some(now);
+                0.0
+            }
+            // Backends without a clock advance by the frame time
+            (None, _) => self
+                .recording
+                .inputs
+                .last()
+                .map_or(0.0, |input| input.time + raw_input.predicted_dt as f64),
+        };
+        self.recording.inputs.push(FrameInput {
+            time,
+            predicted_dt: raw_input.predicted_dt,
+            modifiers: raw_input.modifiers,
+        });
+
+        for event in raw_input.events.iter().filter(|event| is_recordable(event)) {
+            if let Some(event) = self.for_script(event, &over_script, keyboard_to_script) {
+                let event = translate(&event, -script_origin.to_vec2());
+                self.recording.events.push(ScriptedEvent { frame, event });
+            }
+        }
+        self.recording.frames += 1;
+    }
+
+    /// What the script should see of `event` in the replay, if anything
+    fn for_script(
+        &mut self,
+        event: &Event,
+        over_script: &impl Fn(Pos2) -> bool,
+        keyboard_to_script: bool,
+    ) -> Option<Event> {
+        match event {
+            Event::PointerMoved(pos) => {
+                let was_over = std::mem::replace(&mut self.over_script, over_script(*pos));
+                match (self.dragging || self.over_script, was_over) {
+                    (true, _) => Some(event.clone()),
+                    // Leaving for the host's UI is leaving the script
+                    (false, true) => Some(Event::PointerGone),
+                    (false, false) => None,
+                }
+            }
+            Event::PointerButton { pos, pressed, .. } => {
+                self.over_script = over_script(*pos);
+                if !self.dragging && !self.over_script {
+                    return None;
+                }
+                self.dragging = *pressed;
+                Some(event.clone())
+            }
+            Event::Touch { pos, .. } => (self.dragging || over_script(*pos)).then(|| event.clone()),
+            Event::PointerGone => std::mem::take(&mut self.over_script).then(|| event.clone()),
+            Event::MouseMoved(_) | Event::MouseWheel { .. } | Event::Zoom(_) => {
+                (self.dragging || self.over_script).then(|| event.clone())
+            }
+            event if is_keyboard_input(event) => keyboard_to_script.then(|| event.clone()),
+            _ => Some(event.clone()),
+        }
+    }
+}
+
+/// Input that goes to whatever has keyboard focus, rather than to where the pointer is
+pub fn is_keyboard_input(event: &Event) -> bool {
+    matches!(
+        event,
+        Event::Copy
+            | Event::Cut
+            | Event::Paste(_)
+            | Event::Text(_)
+            | Event::Key { .. }
+            | Event::Ime(_)
+    )
+}
+
+/// `event` with its pointer position, if it has one, moved by `offset`
+fn translate(event: &Event, offset: Vec2) -> Event {
+    let mut event = event.clone();
+    match &mut event {
+        Event::PointerMoved(pos) | Event::PointerButton { pos, .. } | Event::Touch { pos, .. } => {
+            *pos += offset
+        }
+        _ => (),
+    }
+    event
+}
+
+/// Only input is worth replaying, not requests the app made to itself
+fn is_recordable(event: &Event) -> bool {
+    !matches!(event, Event::Screenshot { .. } | Event::WindowFocused(_))
+}
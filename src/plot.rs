@@ -0,0 +1,170 @@
+//! Turning raw script data into `egui_plot` items: binning, quartiles and color maps
+
+use egui::{Color32, ColorImage, Context, Id, Rgba, TextureHandle, TextureOptions};
+use egui_plot::{Bar, BarChart, BoxElem, BoxPlot, BoxSpread, Line, PlotImage, PlotPoint, Points};
+
+/// Everything a script can add to a plot
+pub enum PlotItem {
+    Line(Line),
+    Points(Points),
+    Bars(BarChart),
+    Boxes(BoxPlot),
+    Image(PlotImage),
+}
+
+impl PlotItem {
+    pub fn add_to(self, plot_ui: &mut egui_plot::PlotUi) {
+        match self {
+            Self::Line(line) => plot_ui.line(line),
+            Self::Points(points) => plot_ui.points(points),
+            Self::Bars(chart) => plot_ui.bar_chart(chart),
+            Self::Boxes(boxes) => plot_ui.box_plot(boxes),
+            Self::Image(image) => plot_ui.image(image),
+        }
+    }
+}
+
+/// Pair up `x` and `y`, or plot `x` against its indices if there is no `y`
+pub fn points(x: Vec<f64>, y: Option<Vec<f64>>) -> Result<Vec<[f64; 2]>, String> {
+    match y {
+        None => Ok(x
+            .into_iter()
+            .enumerate()
+            .map(|(i, y)| [i as f64, y])
+            .collect()),
+        Some(y) if y.len() == x.len() => Ok(x.into_iter().zip(y).map(|(x, y)| [x, y]).collect()),
+        Some(y) => Err(format!(
+            "x and y must be the same length, not {} and {}",
+            x.len(),
+            y.len()
+        )),
+    }
+}
+
+/// The most bars a histogram may have; more would only be wider than the screen
+pub const MAX_HISTOGRAM_BINS: usize = 10_000;
+
+/// Count `values` into `bins` equal-width bars spanning their range
+pub fn histogram(values: &[f64], bins: usize) -> Result<Vec<Bar>, String> {
+    if bins > MAX_HISTOGRAM_BINS {
+        return Err(format!(
+            "a histogram can have at most {MAX_HISTOGRAM_BINS} bins, not {bins}"
+        ));
+    }
+    let values: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    let (Some(min), Some(max)) = (
+        values.iter().copied().reduce(f64::min),
+        values.iter().copied().reduce(f64::max),
+    ) else {
+        return Ok(vec![]);
+    };
+    let bins = bins.max(1);
+    let width = if max > min {
+        (max - min) / bins as f64
+    } else {
+        1.0
+    };
+
+    let mut counts = vec![0usize; bins];
+    for v in values {
+        // The maximum belongs in the last bin, not one past it
+        let bin = (((v - min) / width) as usize).min(bins - 1);
+        counts[bin] += 1;
+    }
+
+    Ok(counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| Bar::new(min + (i as f64 + 0.5) * width, count as f64).width(width))
+        .collect())
+}
+
+/// A box with whiskers at the extremes, or `None` if there are no finite values
+pub fn box_elem(values: &[f64], x: f64) -> Option<BoxElem> {
+    let mut sorted: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    if sorted.is_empty() {
+        return None;
+    }
+    sorted.sort_by(f64::total_cmp);
+
+    let spread = BoxSpread::new(
+        sorted[0],
+        quantile(&sorted, 0.25),
+        quantile(&sorted, 0.5),
+        quantile(&sorted, 0.75),
+        sorted[sorted.len() - 1],
+    );
+    Some(BoxElem::new(x, spread))
+}
+
+/// Linearly interpolated quantile of sorted, non-empty `values`
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let pos = q * (sorted.len() - 1) as f64;
+    let (lo, hi) = (pos.floor() as usize, pos.ceil() as usize);
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (pos - lo as f64)
+}
+
+/// One unit square per value, with the first row at the top and the bottom left
+/// corner at `origin`, drawn as a single image. The texture is kept under `id`, and
+/// only uploaded again when the values change. `None` if there are no values.
+pub fn heatmap(ctx: &Context, id: Id, rows: &[Vec<f64>], origin: [f64; 2]) -> Option<PlotImage> {
+    let image = heatmap_image(rows)?;
+    let size = egui::vec2(image.width() as f32, image.height() as f32);
+
+    let hash = egui::util::hash((image.size, &image.pixels));
+    let cached = ctx.data(|data| data.get_temp::<(u64, TextureHandle)>(id));
+    let texture = match cached {
+        Some((cached_hash, texture)) if cached_hash == hash => texture,
+        Some((_, mut texture)) => {
+            texture.set(image, TextureOptions::NEAREST);
+            texture
+        }
+        None => ctx.load_texture("heatmap", image, TextureOptions::NEAREST),
+    };
+    ctx.data_mut(|data| data.insert_temp(id, (hash, texture.clone())));
+
+    let center = PlotPoint::new(
+        origin[0] + size.x as f64 / 2.0,
+        origin[1] + size.y as f64 / 2.0,
+    );
+    Some(PlotImage::new(&texture, center, size))
+}
+
+/// One pixel per value, first row on top. Non-finite values and short rows leave
+/// transparent pixels.
+pub fn heatmap_image(rows: &[Vec<f64>]) -> Option<ColorImage> {
+    let finite = rows.iter().flatten().copied().filter(|v| v.is_finite());
+    let (min, max) = finite.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+        (min.min(v), max.max(v))
+    });
+    let range = if max > min { max - min } else { 1.0 };
+
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if width == 0 {
+        return None;
+    }
+    let mut image = ColorImage::new([width, rows.len()], Color32::TRANSPARENT);
+    for (row, values) in rows.iter().enumerate() {
+        for (col, value) in values.iter().enumerate() {
+            if value.is_finite() {
+                image[(col, row)] = color_map(((value - min) / range) as f32);
+            }
+        }
+    }
+    Some(image)
+}
+
+/// Viridis-like map from 0..=1 to a color
+fn color_map(t: f32) -> Color32 {
+    const STOPS: [Color32; 5] = [
+        Color32::from_rgb(68, 1, 84),
+        Color32::from_rgb(59, 82, 139),
+        Color32::from_rgb(33, 145, 140),
+        Color32::from_rgb(94, 201, 98),
+        Color32::from_rgb(253, 231, 37),
+    ];
+    let pos = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f32;
+    let i = (pos.floor() as usize).min(STOPS.len() - 2);
+    let [a, b] = [STOPS[i], STOPS[i + 1]].map(Rgba::from);
+    egui::lerp(a..=b, pos - i as f32).into()
+}
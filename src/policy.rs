@@ -0,0 +1,488 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use egui::{DragValue, ScrollArea, Ui};
+use rustpython_vm::{
+    builtins::{PyBaseExceptionRef, PyStr},
+    common::rc::PyRc,
+    function::FuncArgs,
+    PyObjectRef, PyResult, VirtualMachine,
+};
+
+/// Native modules which only do computation, allowed unless the project says otherwise
+const DEFAULT_MODULES: &[&str] = &[
+    "array",
+    "binascii",
+    "_bisect",
+    "cmath",
+    "_contextvars",
+    "_csv",
+    "gc",
+    "_hashlib",
+    "_md5",
+    "_sha1",
+    "_sha256",
+    "_sha512",
+    "_sha3",
+    "_blake2",
+    "_json",
+    "math",
+    "_random",
+    "_statistics",
+    "unicodedata",
+    "zlib",
+];
+
+/// Modules built into the VM itself which can reach the filesystem or spawn processes.
+/// These are blocked unless they appear in the allowlist.
+const SYSTEM_MODULES: &[&str] = &[
+    "os",
+    "posix",
+    "nt",
+    "_io",
+    "_posixsubprocess",
+    "_multiprocessing",
+    "mmap",
+    "fcntl",
+];
+
+/// The import machinery, which keeps its own references to `posix` and `_io`.
+/// Always blocked, whatever the allowlist says.
+const LOADER_MODULES: &[&str] = &["_imp", "_frozen_importlib", "_frozen_importlib_external"];
+
+/// Modules the VM imports while it starts up, so they stay in the interpreter even when
+/// blocked. Scripts can still reach their objects without importing them, through the
+/// standard streams or the import machinery's globals, so [`RuntimePolicy::install`] also
+/// guards what those objects can do.
+const BOOT_MODULES: &[&str] = &["_imp", "_io", "posix", "nt"];
+
+/// The OS modules, whose functions are replaced with ones that raise when blocked
+const OS_MODULES: &[&str] = &["posix", "nt"];
+
+thread_local! {
+    /// The policy of each interpreter on this thread, by the address of its state. Native
+    /// types are shared by every interpreter on a thread, so their guards look it up here.
+    static POLICIES: RefCell<HashMap<usize, RuntimePolicy>> = RefCell::default();
+}
+
+fn policy_key(vm: &VirtualMachine) -> usize {
+    PyRc::as_ptr(&vm.state) as usize
+}
+
+/// Deeper Python recursion than this risks overflowing the host stack
+const MAX_SAFE_RECURSION: usize = 2_000;
+
+/// Shallower than this and scripts can hardly call anything
+const MIN_RECURSION: usize = 50;
+
+/// Modules which are governed by [`RuntimePolicy::network`] instead of the allowlist
+const NETWORK_MODULES: &[&str] = &["_socket", "socket", "_ssl", "ssl", "select"];
+
+/// What scripts are allowed to do, chosen per project and enforced by the [`crate::Runtime`]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct RuntimePolicy {
+    /// Native modules scripts may import
+    pub allowed_modules: Vec<String>,
+    /// Directories `open()` may access. Empty means no file access at all
+    pub file_roots: Vec<String>,
+    /// Allow sockets
+    pub network: bool,
+    /// Maximum Python recursion depth; `sys.setrecursionlimit` cannot exceed it
+    pub max_recursion: usize,
+    /// Approximate allocation budget of each run in MiB. Zero means unlimited
+    pub max_memory_mb: usize,
+}
+
+impl Default for RuntimePolicy {
+    fn default() -> Self {
+        Self {
+            allowed_modules: DEFAULT_MODULES.iter().map(|s| s.to_string()).collect(),
+            file_roots: vec![],
+            network: false,
+            max_recursion: 500,
+            max_memory_mb: 256,
+        }
+    }
+}
+
+fn permission_error(vm: &VirtualMachine, msg: String) -> PyBaseExceptionRef {
+    vm.new_exception_msg(vm.ctx.exceptions.permission_error.to_owned(), msg)
+}
+
+impl RuntimePolicy {
+    /// Whether the native module `name` may be installed and imported
+    pub fn allows_module(&self, name: &str) -> bool {
+        if LOADER_MODULES.contains(&name) {
+            return false;
+        }
+        if NETWORK_MODULES.contains(&name) {
+            return self.network;
+        }
+        self.allowed_modules.iter().any(|allowed| allowed == name)
+    }
+
+    /// Whether `open()` may touch `path`
+    pub fn allows_path(&self, path: &Path) -> bool {
+        let Some(path) = resolve(path) else {
+            return false;
+        };
+        self.file_roots
+            .iter()
+            .filter_map(|root| resolve(Path::new(root)))
+            .any(|root| path.starts_with(root))
+    }
+
+    /// Every module name the policy has an opinion about
+    fn known_modules() -> Vec<String> {
+        let mut names: Vec<String> = rustpython_stdlib::get_module_inits()
+            .map(|(name, _)| name.into_owned())
+            .chain(SYSTEM_MODULES.iter().map(|s| s.to_string()))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Every module scripts may not import
+    fn blocked_modules(&self) -> HashSet<String> {
+        Self::known_modules()
+            .into_iter()
+            .chain(NETWORK_MODULES.iter().map(|s| s.to_string()))
+            .chain(LOADER_MODULES.iter().map(|s| s.to_string()))
+            .filter(|name| !self.allows_module(name))
+            .collect()
+    }
+
+    /// Leave blocked native modules out of an interpreter that is being built, apart
+    /// from the ones it needs to start up. Call from `Interpreter::with_init`.
+    pub fn strip_modules(&self, vm: &mut VirtualMachine) {
+        let blocked = self.blocked_modules();
+        let state = PyRc::get_mut(&mut vm.state)
+            .expect("The interpreter is not shared while it is being built");
+        state.module_inits.retain(|name, _| {
+            !blocked.contains(name.as_ref()) || BOOT_MODULES.contains(&name.as_ref())
+        });
+    }
+
+    /// Install the import, `open()`, `FileIO`, OS module and recursion guards.
+    /// Must be called after the interpreter has finished its own imports. The policy stays
+    /// in force until the returned [`InstalledPolicy`] is dropped.
+    pub fn install(&self, vm: &VirtualMachine) -> PyResult<InstalledPolicy> {
+        if self.max_recursion < MIN_RECURSION {
+            return Err(vm.new_value_error(format!(
+                "max_recursion must be at least {MIN_RECURSION}, not {}",
+                self.max_recursion
+            )));
+        }
+        let blocked = self.blocked_modules();
+
+        let installed = InstalledPolicy {
+            key: policy_key(vm),
+        };
+        POLICIES.with(|policies| policies.borrow_mut().insert(installed.key, self.clone()));
+        #[cfg(not(target_arch = "wasm32"))]
+        file_io::guard(vm)?;
+
+        // Forget anything blocked that the interpreter imported while starting up, after
+        // disarming what scripts could still reach of it
+        let sys_modules = vm.sys_module.get_attr("modules", vm)?;
+        for name in OS_MODULES.iter().filter(|name| blocked.contains(**name)) {
+            if let Ok(module) = sys_modules.get_item(*name, vm) {
+                disarm_os_module(&module, name, vm)?;
+            }
+        }
+        guard_create_builtin(blocked.clone(), vm)?;
+        for name in &blocked {
+            let _ = sys_modules.del_item(name.as_str(), vm);
+        }
+
+        let original_import = vm.builtins.get_attr("__import__", vm)?;
+        let import = vm.new_function(
+            "__import__",
+            move |args: FuncArgs, vm: &VirtualMachine| -> PyResult {
+                if let Some(name) = args.args.first().and_then(|n| n.payload::<PyStr>()) {
+                    let top_level = name.as_str().split('.').next().unwrap_or_default();
+                    if blocked.contains(top_level) {
+                        return Err(permission_error(
+                            vm,
+                            format!("import of '{top_level}' is blocked by the runtime policy"),
+                        ));
+                    }
+                }
+                original_import.call(args, vm)
+            },
+        );
+        vm.builtins.set_attr("__import__", import, vm)?;
+
+        let policy = self.clone();
+        let original_open = vm.builtins.get_attr("open", vm)?;
+        let open = vm.new_function(
+            "open",
+            move |args: FuncArgs, vm: &VirtualMachine| -> PyResult {
+                let file = args
+                    .args
+                    .first()
+                    .or_else(|| args.kwargs.get("file"))
+                    .and_then(|f| f.payload::<PyStr>().map(|s| s.as_str().to_owned()));
+                match file {
+                    Some(path) if policy.allows_path(Path::new(&path)) => {
+                        original_open.call(args, vm)
+                    }
+                    Some(path) => Err(permission_error(
+                        vm,
+                        format!("access to '{path}' is blocked by the runtime policy"),
+                    )),
+                    None => Err(permission_error(
+                        vm,
+                        "open() only accepts str paths under the runtime policy".to_owned(),
+                    )),
+                }
+            },
+        );
+        vm.builtins.set_attr("open", open, vm)?;
+
+        let max_recursion = self.max_recursion.min(MAX_SAFE_RECURSION);
+        vm.recursion_limit.set(max_recursion);
+        let set_recursion_limit = vm.new_function(
+            "setrecursionlimit",
+            move |limit: usize, vm: &VirtualMachine| -> PyResult<()> {
+                if limit > max_recursion {
+                    return Err(permission_error(
+                        vm,
+                        format!("recursion limit cannot exceed {max_recursion} under the runtime policy"),
+                    ));
+                }
+                vm.recursion_limit.set(limit);
+                Ok(())
+            },
+        );
+        vm.sys_module
+            .set_attr("setrecursionlimit", set_recursion_limit, vm)?;
+
+        Ok(installed)
+    }
+
+    pub fn show(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.network, "Network access");
+        ui.horizontal(|ui| {
+            ui.label("Max recursion depth");
+            ui.add(
+                DragValue::new(&mut self.max_recursion).range(MIN_RECURSION..=MAX_SAFE_RECURSION),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Memory budget");
+            ui.add(
+                DragValue::new(&mut self.max_memory_mb)
+                    .range(0..=4096)
+                    .suffix(" MiB"),
+            );
+        })
+        .response
+        .on_hover_text("Zero means unlimited");
+
+        ui.separator();
+        ui.label("File access roots");
+        let mut remove = None;
+        for (idx, root) in self.file_roots.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(root);
+                if ui.small_button("Remove").clicked() {
+                    remove = Some(idx);
+                }
+            });
+        }
+        if let Some(idx) = remove {
+            self.file_roots.remove(idx);
+        }
+        if ui.button("Add root").clicked() {
+            self.file_roots.push(String::new());
+        }
+
+        ui.separator();
+        ui.label("Native modules");
+        ScrollArea::vertical()
+            .id_salt("policy_modules")
+            .max_height(200.0)
+            .show(ui, |ui| {
+                for name in Self::known_modules() {
+                    if NETWORK_MODULES.contains(&name.as_str()) {
+                        continue;
+                    }
+                    let mut allowed = self.allows_module(&name);
+                    if ui.checkbox(&mut allowed, &name).changed() {
+                        if allowed {
+                            self.allowed_modules.push(name);
+                        } else {
+                            self.allowed_modules.retain(|m| *m != name);
+                        }
+                    }
+                }
+            });
+    }
+}
+
+/// Keeps a policy in force for an interpreter, and forgets it once dropped
+pub struct InstalledPolicy {
+    key: usize,
+}
+
+impl Drop for InstalledPolicy {
+    fn drop(&mut self) {
+        POLICIES.with(|policies| policies.borrow_mut().remove(&self.key));
+    }
+}
+
+/// Replace every function of a blocked OS module with one that raises, and empty its
+/// environment, so references to it that outlive its import are harmless
+fn disarm_os_module(module: &PyObjectRef, name: &str, vm: &VirtualMachine) -> PyResult<()> {
+    let dict = module.dict().ok_or_else(|| {
+        vm.new_runtime_error(format!("the {name} module has no namespace to disarm"))
+    })?;
+    let items: Vec<_> = (&*dict).into_iter().collect();
+    for (attr, value) in items {
+        if attr.payload_is::<PyStr>() && value.is_callable() {
+            let message = format!("{name}.{} is blocked by the runtime policy", attr.str(vm)?);
+            let blocked = vm.new_function(
+                "blocked",
+                move |_args: FuncArgs, vm: &VirtualMachine| -> PyResult {
+                    Err(permission_error(vm, message.clone()))
+                },
+            );
+            dict.set_item(&*attr, blocked.into(), vm)?;
+        }
+    }
+    dict.set_item("environ", vm.ctx.new_dict().into(), vm)?;
+    Ok(())
+}
+
+/// Keep the import machinery from building fresh copies of blocked native modules, which
+/// it would otherwise do for any spec a script hands it
+fn guard_create_builtin(blocked: HashSet<String>, vm: &VirtualMachine) -> PyResult<()> {
+    let imp = vm.import("_imp", 0)?;
+    let original = imp.get_attr("create_builtin", vm)?;
+    let create_builtin = vm.new_function(
+        "create_builtin",
+        move |spec: PyObjectRef, vm: &VirtualMachine| -> PyResult {
+            let name = spec.get_attr("name", vm)?.str(vm)?;
+            if blocked.contains(name.as_str()) {
+                return Err(permission_error(
+                    vm,
+                    format!("import of '{name}' is blocked by the runtime policy"),
+                ));
+            }
+            original.call((spec,), vm)
+        },
+    );
+    imp.set_attr("create_builtin", create_builtin, vm)
+}
+
+/// There are no files on the web, and no `FileIO` to guard
+#[cfg(not(target_arch = "wasm32"))]
+mod file_io {
+    use std::cell::Cell;
+
+    use rustpython_vm::{
+        builtins::{PyInt, PyType},
+        function::{FuncArgs, PyMethodFlags},
+        ospath::OsPath,
+        Py, PyObjectRef, PyRef, PyResult, TryFromObject, VirtualMachine,
+    };
+
+    use super::{permission_error, policy_key, POLICIES};
+
+    /// `FileIO` may still wrap the standard streams' file descriptors
+    const STANDARD_FDS: std::ops::RangeInclusive<i32> = 0..=2;
+
+    type InitFn = fn(PyObjectRef, FuncArgs, &VirtualMachine) -> PyResult<()>;
+
+    thread_local! {
+        /// `FileIO`'s own initializer, which its guard calls once the file is allowed
+        static FILE_IO_INIT: Cell<Option<InitFn>> = const { Cell::new(None) };
+    }
+
+    /// Route `FileIO`, which `open()` and everything else that opens files goes through, past
+    /// the policy of whichever interpreter uses it. Both its slot and its `__init__` are
+    /// replaced, since scripts can call either on the standard streams' `FileIO`.
+    pub fn guard(vm: &VirtualMachine) -> PyResult<()> {
+        if FILE_IO_INIT.get().is_some() {
+            // Already guarded for every interpreter on this thread
+            return Ok(());
+        }
+        let file_io = vm.import("_io", 0)?.get_attr("FileIO", vm)?;
+        let file_io: &'static Py<PyType> =
+            PyRef::leak(PyRef::<PyType>::try_from_object(vm, file_io)?);
+        let Some(init) = file_io.slots.init.load() else {
+            return Err(vm.new_runtime_error("FileIO has no initializer to guard".to_owned()));
+        };
+        FILE_IO_INIT.set(Some(init));
+        file_io.slots.init.store(Some(guarded_init));
+
+        let method = vm
+            .ctx
+            .new_method_def("__init__", guarded_init, PyMethodFlags::METHOD, None)
+            .build_method(file_io, vm);
+        file_io.set_attr(vm.ctx.intern_str("__init__"), method.into());
+        Ok(())
+    }
+
+    /// `FileIO`'s initializer, refusing paths outside the file roots, file descriptors other
+    /// than the standard streams', and openers, which could hand it any file descriptor
+    fn guarded_init(zelf: PyObjectRef, args: FuncArgs, vm: &VirtualMachine) -> PyResult<()> {
+        let policy = POLICIES.with(|policies| policies.borrow().get(&policy_key(vm)).cloned());
+        if let Some(policy) = policy {
+            let arg = |i: usize, name: &str| args.args.get(i).or_else(|| args.kwargs.get(name));
+            if arg(3, "opener").is_some_and(|opener| !vm.is_none(opener)) {
+                return Err(permission_error(
+                    vm,
+                    "FileIO openers are blocked by the runtime policy".to_owned(),
+                ));
+            }
+            match arg(0, "name") {
+                Some(fd) if fd.payload_is::<PyInt>() => {
+                    let fd = i32::try_from_object(vm, fd.clone())?;
+                    if !STANDARD_FDS.contains(&fd) {
+                        return Err(permission_error(
+                            vm,
+                            format!("file descriptor {fd} is blocked by the runtime policy"),
+                        ));
+                    }
+                }
+                Some(path) => {
+                    let path = OsPath::try_from_object(vm, path.clone())?;
+                    if !policy.allows_path(path.as_path()) {
+                        return Err(permission_error(
+                            vm,
+                            format!(
+                                "access to '{}' is blocked by the runtime policy",
+                                path.as_path().display()
+                            ),
+                        ));
+                    }
+                }
+                None => (),
+            }
+        }
+
+        let init = FILE_IO_INIT
+            .get()
+            .expect("The guard is only installed with the initializer");
+        init(zelf, args, vm)
+    }
+}
+
+/// Make `path` absolute, following symlinks.
+fn resolve(path: &Path) -> Option<PathBuf> {
+    std::fs::canonicalize(path).ok().or_else(|| {
+        // The file might not exist yet, so resolve its directory instead
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        Some(std::fs::canonicalize(parent).ok()?.join(path.file_name()?))
+    })
+}
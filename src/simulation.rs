@@ -0,0 +1,123 @@
+//! Fixed timestep simulation: the script's `step(dt)` runs at a steady rate, however
+//! fast or slow frames are
+
+use egui::{DragValue, Slider, Ui};
+
+/// Most steps taken in one frame. After a long stall the simulation falls behind
+/// instead of freezing the app while it catches up, and queued steps beyond this are
+/// taken over the next frames.
+const MAX_STEPS_PER_FRAME: usize = 240;
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct Simulation {
+    /// Steps per simulated second
+    pub rate: f64,
+    /// Simulated seconds per real second
+    pub speed: f64,
+    pub paused: bool,
+    /// How many steps "Run N steps" takes
+    pub batch: usize,
+    /// Simulated time not yet stepped through
+    #[serde(skip)]
+    accumulator: f64,
+    /// Steps asked for explicitly, which run even while paused
+    #[serde(skip)]
+    queued: usize,
+    #[serde(skip)]
+    steps_taken: u64,
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self {
+            rate: 60.0,
+            speed: 1.0,
+            paused: false,
+            batch: 10,
+            accumulator: 0.0,
+            queued: 0,
+            steps_taken: 0,
+        }
+    }
+}
+
+impl Simulation {
+    /// Simulated seconds per step
+    pub fn dt(&self) -> f64 {
+        1.0 / self.rate
+    }
+
+    /// Take `steps` more steps, whether or not the simulation is paused
+    pub fn queue(&mut self, steps: usize) {
+        self.queued = self.queued.saturating_add(steps);
+    }
+
+    /// Whether steps will be due without any input
+    pub fn is_running(&self) -> bool {
+        !self.paused || self.queued > 0
+    }
+
+    pub fn steps_taken(&self) -> u64 {
+        self.steps_taken
+    }
+
+    /// Call once per frame with the real seconds since the last frame.
+    /// Returns how many steps to take this frame.
+    pub fn advance(&mut self, frame_dt: f64) -> usize {
+        let mut steps = 0;
+        if !self.paused {
+            self.accumulator += frame_dt * self.speed;
+            // Truncation is intended, the remainder carries over to the next frame
+            steps = (self.accumulator * self.rate) as usize;
+            self.accumulator -= steps as f64 * self.dt();
+        }
+        let queued = std::mem::take(&mut self.queued);
+        steps = steps.saturating_add(queued);
+
+        if steps > MAX_STEPS_PER_FRAME {
+            // Time the simulation fell behind is dropped, but steps asked for are not
+            self.queued = (steps - MAX_STEPS_PER_FRAME).min(queued);
+            steps = MAX_STEPS_PER_FRAME;
+            self.accumulator = 0.0;
+        }
+        self.steps_taken += steps as u64;
+        steps
+    }
+
+    /// Forget time and steps that haven't been taken yet
+    pub fn reset(&mut self) {
+        self.accumulator = 0.0;
+        self.queued = 0;
+        self.steps_taken = 0;
+    }
+
+    /// Controls for the Run menu
+    pub fn show_controls(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.paused, "Paused");
+        if ui.button("Single step").clicked() {
+            self.queue(1);
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Run").clicked() {
+                self.queue(self.batch);
+            }
+            ui.add(DragValue::new(&mut self.batch).range(1..=100_000));
+            ui.label("steps");
+        });
+        ui.add(
+            Slider::new(&mut self.speed, 0.1..=10.0)
+                .logarithmic(true)
+                .text("Speed"),
+        );
+        ui.horizontal(|ui| {
+            ui.label("Rate");
+            ui.add(
+                DragValue::new(&mut self.rate)
+                    .range(1.0..=1000.0)
+                    .suffix(" Hz"),
+            );
+        });
+        ui.label(format!("{} steps taken", self.steps_taken));
+    }
+}
@@ -0,0 +1,341 @@
+//! Software rendering of egui output to images, and golden-image comparison for tests.
+//!
+//! The rasterizer is deliberately simple: it fills the tessellated triangles with
+//! interpolated vertex colors and bilinearly sampled textures, blending premultiplied
+//! sRGBA the same way the GPU backends do. It is meant to catch changes in what the
+//! script draws, not to be pixel-identical with a real backend.
+
+use std::{collections::HashMap, path::Path};
+
+use egui::{
+    epaint::{ClippedPrimitive, ImageData, Primitive, Vertex},
+    Color32, ColorImage, Pos2, Rect, TextureId, TexturesDelta,
+};
+
+/// CPU copies of the textures egui asked for
+#[derive(Default)]
+pub struct SoftwareRenderer {
+    textures: HashMap<TextureId, ColorImage>,
+}
+
+impl SoftwareRenderer {
+    /// Must be called with every frame's `textures_delta`, or the font atlas goes missing
+    pub fn update_textures(&mut self, delta: &TexturesDelta) {
+        for (id, image_delta) in &delta.set {
+            let image = match &image_delta.image {
+                ImageData::Color(image) => image.as_ref().clone(),
+                ImageData::Font(font) => ColorImage {
+                    size: font.size,
+                    pixels: font.srgba_pixels(None).collect(),
+                },
+            };
+
+            match image_delta.pos {
+                None => {
+                    self.textures.insert(*id, image);
+                }
+                Some([x0, y0]) => {
+                    let Some(texture) = self.textures.get_mut(id) else {
+                        continue;
+                    };
+                    for y in 0..image.height() {
+                        for x in 0..image.width() {
+                            texture[(x0 + x, y0 + y)] = image[(x, y)];
+                        }
+                    }
+                }
+            }
+        }
+
+        for id in &delta.free {
+            self.textures.remove(id);
+        }
+    }
+
+    /// Rasterize to an image of `size` physical pixels
+    pub fn render(
+        &self,
+        primitives: &[ClippedPrimitive],
+        pixels_per_point: f32,
+        size: [usize; 2],
+    ) -> ColorImage {
+        let mut target = ColorImage::new(size, Color32::TRANSPARENT);
+
+        for ClippedPrimitive {
+            clip_rect,
+            primitive,
+        } in primitives
+        {
+            // Paint callbacks need a real GPU backend
+            let Primitive::Mesh(mesh) = primitive else {
+                continue;
+            };
+            let texture = self.textures.get(&mesh.texture_id);
+            let clip = Rect::from_min_max(
+                (clip_rect.min.to_vec2() * pixels_per_point).to_pos2(),
+                (clip_rect.max.to_vec2() * pixels_per_point).to_pos2(),
+            );
+
+            for tri in mesh.indices.as_chunks::<3>().0 {
+                let [a, b, c] = tri.map(|i| mesh.vertices[i as usize]);
+                fill_triangle(&mut target, clip, pixels_per_point, [a, b, c], texture);
+            }
+        }
+
+        target
+    }
+}
+
+fn fill_triangle(
+    target: &mut ColorImage,
+    clip: Rect,
+    pixels_per_point: f32,
+    verts: [Vertex; 3],
+    texture: Option<&ColorImage>,
+) {
+    let [a, b, c] = verts.map(|v| (v.pos.to_vec2() * pixels_per_point).to_pos2());
+
+    let area = edge(a, b, c);
+    if area.abs() < f32::EPSILON {
+        return;
+    }
+
+    let bounds = Rect::from_points(&[a, b, c]).intersect(clip);
+    let x_range =
+        bounds.min.x.floor().max(0.0) as usize..(bounds.max.x.ceil() as usize).min(target.width());
+    let y_range =
+        bounds.min.y.floor().max(0.0) as usize..(bounds.max.y.ceil() as usize).min(target.height());
+
+    for y in y_range {
+        for x in x_range.clone() {
+            // Sample at the pixel center
+            let p = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+            if !clip.contains(p) {
+                continue;
+            }
+
+            let w = [
+                edge(b, c, p) / area,
+                edge(c, a, p) / area,
+                edge(a, b, p) / area,
+            ];
+            if w.iter().any(|w| *w < 0.0) {
+                continue;
+            }
+
+            let mut color = [0.0; 4];
+            let mut uv = Pos2::ZERO;
+            for (vert, w) in verts.iter().zip(w) {
+                let rgba = vert.color.to_array();
+                for (channel, value) in color.iter_mut().zip(rgba) {
+                    *channel += w * value as f32 / 255.0;
+                }
+                uv += vert.uv.to_vec2() * w;
+            }
+
+            if let Some(texture) = texture {
+                let texel = sample(texture, uv);
+                for (channel, value) in color.iter_mut().zip(texel) {
+                    *channel *= value;
+                }
+            }
+
+            blend(&mut target[(x, y)], color);
+        }
+    }
+}
+
+/// Twice the signed area of the triangle `a`, `b`, `p`
+fn edge(a: Pos2, b: Pos2, p: Pos2) -> f32 {
+    (b - a).x * (p - a).y - (b - a).y * (p - a).x
+}
+
+/// Bilinear sample, as premultiplied RGBA in 0..=1
+fn sample(texture: &ColorImage, uv: Pos2) -> [f32; 4] {
+    let [w, h] = texture.size;
+    if w == 0 || h == 0 {
+        return [1.0; 4];
+    }
+
+    let x = (uv.x * w as f32 - 0.5).clamp(0.0, (w - 1) as f32);
+    let y = (uv.y * h as f32 - 0.5).clamp(0.0, (h - 1) as f32);
+    let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+    let (x1, y1) = ((x0 + 1).min(w - 1), (y0 + 1).min(h - 1));
+    let (fx, fy) = (x.fract(), y.fract());
+
+    let texel = |x: usize, y: usize| texture[(x, y)].to_array().map(|v| v as f32 / 255.0);
+    let lerp = |a: [f32; 4], b: [f32; 4], t: f32| -> [f32; 4] {
+        std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t)
+    };
+
+    lerp(
+        lerp(texel(x0, y0), texel(x1, y0), fx),
+        lerp(texel(x0, y1), texel(x1, y1), fx),
+        fy,
+    )
+}
+
+/// Premultiplied "over" blending
+fn blend(dst: &mut Color32, src: [f32; 4]) {
+    let inv_alpha = 1.0 - src[3];
+    let out: [u8; 4] = std::array::from_fn(|i| {
+        let d = dst.to_array()[i] as f32 / 255.0;
+        ((src[i] + d * inv_alpha).clamp(0.0, 1.0) * 255.0).round() as u8
+    });
+    *dst = Color32::from_rgba_premultiplied(out[0], out[1], out[2], out[3]);
+}
+
+pub fn encode_png(image: &ColorImage) -> Result<Vec<u8>, png::EncodingError> {
+    let mut bytes = vec![];
+    let mut encoder = png::Encoder::new(&mut bytes, image.width() as u32, image.height() as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let data: Vec<u8> = image
+        .pixels
+        .iter()
+        .flat_map(|pixel| pixel.to_srgba_unmultiplied())
+        .collect();
+    encoder.write_header()?.write_image_data(&data)?;
+    Ok(bytes)
+}
+
+pub fn decode_png(bytes: &[u8]) -> Result<ColorImage, png::DecodingError> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data)?;
+    data.truncate(info.buffer_size());
+
+    let size = [info.width as usize, info.height as usize];
+    let pixels = match info.color_type {
+        png::ColorType::Rgba => data
+            .as_chunks::<4>()
+            .0
+            .iter()
+            .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
+            .collect(),
+        png::ColorType::Rgb => data
+            .as_chunks::<3>()
+            .0
+            .iter()
+            .map(|p| Color32::from_rgb(p[0], p[1], p[2]))
+            .collect(),
+        png::ColorType::GrayscaleAlpha => data
+            .as_chunks::<2>()
+            .0
+            .iter()
+            .map(|p| Color32::from_rgba_unmultiplied(p[0], p[0], p[0], p[1]))
+            .collect(),
+        _ => data.iter().map(|v| Color32::from_gray(*v)).collect(),
+    };
+
+    Ok(ColorImage { size, pixels })
+}
+
+/// How far apart two images are
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageDiff {
+    /// Pixels where any channel differs by more than the tolerance
+    pub differing_pixels: usize,
+    /// Largest difference in any channel of any pixel
+    pub max_channel_diff: u8,
+}
+
+/// Compare two images of the same size. Returns `None` if the sizes differ.
+pub fn compare(a: &ColorImage, b: &ColorImage, tolerance: u8) -> Option<ImageDiff> {
+    if a.size != b.size {
+        return None;
+    }
+
+    let mut diff = ImageDiff {
+        differing_pixels: 0,
+        max_channel_diff: 0,
+    };
+    for (a, b) in a.pixels.iter().zip(&b.pixels) {
+        let pixel_diff = a
+            .to_array()
+            .iter()
+            .zip(b.to_array())
+            .map(|(a, b)| a.abs_diff(b))
+            .max()
+            .unwrap_or(0);
+        diff.max_channel_diff = diff.max_channel_diff.max(pixel_diff);
+        if pixel_diff > tolerance {
+            diff.differing_pixels += 1;
+        }
+    }
+    Some(diff)
+}
+
+/// Compare `image` against the golden image `<dir>/<name>.png`, panicking on mismatch.
+///
+/// Set `UPDATE_SNAPSHOTS=1` to write golden images that are missing or no longer match.
+/// Otherwise a missing golden is a failure too, and the new rendering is written next
+/// to the golden as `<name>.new.png` for review.
+#[track_caller]
+pub fn assert_snapshot(dir: &Path, name: &str, image: &ColorImage) {
+    /// Channel differences this small are antialiasing noise
+    const TOLERANCE: u8 = 2;
+
+    let golden_path = dir.join(format!("{name}.png"));
+    let new_path = dir.join(format!("{name}.new.png"));
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some_and(|v| v != "0");
+
+    let bytes = encode_png(image).expect("Failed to encode snapshot");
+    // Compare what would be written, since PNGs can't hold premultiplied alpha exactly
+    let image = &decode_png(&bytes).expect("Failed to decode snapshot");
+    let write = |path: &Path| {
+        std::fs::create_dir_all(dir).expect("Failed to create snapshot dir");
+        std::fs::write(path, &bytes).expect("Failed to write snapshot");
+    };
+
+    let golden = match std::fs::read(&golden_path) {
+        Ok(bytes) => decode_png(&bytes).expect("Failed to decode golden snapshot"),
+        Err(_) if update => {
+            eprintln!("Writing new snapshot {}", golden_path.display());
+            write(&golden_path);
+            let _ = std::fs::remove_file(&new_path);
+            return;
+        }
+        Err(err) => {
+            write(&new_path);
+            panic!(
+                "Golden snapshot {} is missing ({err}). See {}, or rerun with UPDATE_SNAPSHOTS=1 to accept it.",
+                golden_path.display(),
+                new_path.display()
+            );
+        }
+    };
+
+    let mismatch = match compare(&golden, image, TOLERANCE) {
+        None => Some(format!(
+            "size changed from {:?} to {:?}",
+            golden.size, image.size
+        )),
+        Some(diff) if diff.differing_pixels > 0 => Some(format!(
+            "{} pixels differ (max channel difference {})",
+            diff.differing_pixels, diff.max_channel_diff
+        )),
+        Some(_) => None,
+    };
+
+    match mismatch {
+        None => {
+            let _ = std::fs::remove_file(&new_path);
+        }
+        Some(_) if update => {
+            eprintln!("Updating snapshot {}", golden_path.display());
+            write(&golden_path);
+            let _ = std::fs::remove_file(&new_path);
+        }
+        Some(reason) => {
+            write(&new_path);
+            panic!(
+                "Snapshot {name} does not match: {reason}. See {}, or rerun with UPDATE_SNAPSHOTS=1 to accept it.",
+                new_path.display()
+            );
+        }
+    }
+}
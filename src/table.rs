@@ -0,0 +1,79 @@
+//! Sorting and selection state for script tables, kept in egui memory between frames
+
+use std::{cmp::Ordering, collections::BTreeSet};
+
+#[derive(Clone, Debug, Default)]
+pub struct TableState {
+    /// Column to sort by, and whether ascending
+    pub sort: Option<(usize, bool)>,
+    /// `repr` of the selected rows' keys, so the selection stays on the same rows when
+    /// they move
+    pub selected: BTreeSet<String>,
+}
+
+impl TableState {
+    pub fn load(ctx: &egui::Context, id: egui::Id) -> Self {
+        ctx.data(|data| data.get_temp(id)).unwrap_or_default()
+    }
+
+    pub fn store(self, ctx: &egui::Context, id: egui::Id) {
+        ctx.data_mut(|data| data.insert_temp(id, self));
+    }
+
+    /// Clicking the sorted column flips its direction, clicking another sorts by it
+    pub fn toggle_sort(&mut self, column: usize) {
+        self.sort = match self.sort {
+            Some((sorted, ascending)) if sorted == column => Some((column, !ascending)),
+            _ => Some((column, true)),
+        };
+    }
+
+    /// A plain click selects only the row with `key`, with `toggle` it is added or removed
+    pub fn click_row(&mut self, key: &str, toggle: bool) {
+        if !toggle {
+            self.selected.clear();
+            self.selected.insert(key.to_owned());
+        } else if !self.selected.remove(key) {
+            self.selected.insert(key.to_owned());
+        }
+    }
+}
+
+/// Cells that look like numbers sort numerically, before any text
+#[derive(Clone, Debug, PartialEq)]
+pub enum SortKey {
+    Number(f64),
+    Text(String),
+}
+
+impl SortKey {
+    pub fn from_text(text: String) -> Self {
+        match text.trim().parse() {
+            Ok(number) => Self::Number(number),
+            Err(_) => Self::Text(text),
+        }
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Number(a), Self::Number(b)) => a.total_cmp(b),
+            (Self::Number(_), Self::Text(_)) => Ordering::Less,
+            (Self::Text(_), Self::Number(_)) => Ordering::Greater,
+            (Self::Text(a), Self::Text(b)) => a.cmp(b),
+        }
+    }
+}
+
+/// Row indices in display order. The sort is stable, so equal keys keep their order.
+pub fn sorted_order(keys: &[SortKey], ascending: bool) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..keys.len()).collect();
+    order.sort_by(|&a, &b| {
+        let ordering = keys[a].cmp(&keys[b]);
+        if ascending {
+            ordering
+        } else {
+            ordering.reverse()
+        }
+    });
+    order
+}
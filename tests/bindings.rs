@@ -0,0 +1,1085 @@
+//! Exercises the `rust_py_module` bindings from Python, against an offscreen `egui::Context`
+
+use eframe_template::{
+    headless::{FrameReport, Headless},
+    ProjectFont, RuntimePolicy,
+};
+use egui::{
+    epaint::{ColorMode, Shape},
+    Color32, Event, Modifiers, PointerButton, Pos2,
+};
+
+fn headless(code: &str) -> Headless {
+    Headless::new(
+        code.to_owned(),
+        RuntimePolicy::default(),
+        egui::vec2(640.0, 480.0),
+    )
+}
+
+fn run_once(code: &str) -> FrameReport {
+    headless(code).step(vec![])
+}
+
+/// Runs `code` and returns its stdout, failing the test if the script raised
+fn run_ok(code: &str) -> String {
+    let report = run_once(code);
+    assert_eq!(report.error, None, "Script raised");
+    report.stdout
+}
+
+/// Runs `code` and returns the traceback, failing the test if the script succeeded
+fn run_err(code: &str) -> String {
+    run_once(code).error.expect("Script should have raised")
+}
+
+fn shapes(report: &FrameReport) -> impl Iterator<Item = &Shape> {
+    report.output.shapes.iter().map(|clipped| &clipped.shape)
+}
+
+fn parse_point(s: &str) -> Pos2 {
+    let (x, y) = s
+        .trim()
+        .trim_start_matches("Pos2(")
+        .trim_end_matches(')')
+        .split_once(',')
+        .expect("Not a point");
+    Pos2::new(x.trim().parse().unwrap(), y.trim().parse().unwrap())
+}
+
+fn click_events(pos: Pos2, pressed: bool) -> Vec<Event> {
+    vec![
+        Event::PointerMoved(pos),
+        Event::PointerButton {
+            pos,
+            button: PointerButton::Primary,
+            pressed,
+            modifiers: Modifiers::NONE,
+        },
+    ]
+}
+
+/// Click the center of the rect printed on the first line of the first frame
+fn click_printed_rect(headless: &mut Headless) -> FrameReport {
+    let first = headless.step(vec![]);
+    assert_eq!(first.error, None);
+    let mut lines = first.stdout.lines();
+    let min = parse_point(lines.next().unwrap());
+    let max = parse_point(lines.next().unwrap());
+    let center = min + (max - min) / 2.0;
+
+    headless.step(click_events(center, true));
+    headless.step(click_events(center, false))
+}
+
+#[test]
+fn button_not_clicked_without_input() {
+    assert_eq!(run_ok("print(egui.button('Hi').clicked())").trim(), "False");
+}
+
+#[test]
+fn button_clicked() {
+    let mut headless = headless(
+        r#"
+resp = egui.button("Click me")
+print(resp.rect().min())
+print(resp.rect().max())
+print(resp.clicked())
+"#,
+    );
+    let report = click_printed_rect(&mut headless);
+    assert_eq!(report.error, None);
+    assert_eq!(report.stdout.lines().nth(2), Some("True"));
+}
+
+#[test]
+fn rect_min_max() {
+    let out = run_ok(
+        r#"
+resp, paint = egui.allocate_painter([200.0, 100.0], "click")
+r = resp.rect()
+print(r.min())
+print(r.max())
+"#,
+    );
+    let mut lines = out.lines();
+    let min = parse_point(lines.next().unwrap());
+    let max = parse_point(lines.next().unwrap());
+    assert_eq!(max - min, egui::vec2(200.0, 100.0));
+}
+
+#[test]
+fn text_edit_singleline_returns_text() {
+    let out = run_ok(
+        r#"
+s, resp = egui.text_edit_singleline("hello")
+print(s)
+print(resp.clicked())
+"#,
+    );
+    assert_eq!(out, "hello\nFalse\n");
+}
+
+#[test]
+fn text_edit_singleline_typing() {
+    let mut headless = headless(
+        r#"
+try: s
+except: s = "ab"
+s, resp = egui.text_edit_singleline(s)
+print(resp.rect().min())
+print(resp.rect().max())
+print(s)
+"#,
+    );
+    click_printed_rect(&mut headless);
+    let report = headless.step(vec![Event::Text("c".to_owned())]);
+    assert_eq!(report.error, None);
+    assert_eq!(report.stdout.lines().nth(2), Some("abc"));
+}
+
+#[test]
+fn allocate_painter_senses() {
+    run_ok(
+        r#"
+egui.allocate_painter([10.0, 10.0], "click")
+egui.allocate_painter([10.0, 10.0], "drag")
+egui.allocate_painter([10.0, 10.0], "click|drag|focusable")
+egui.allocate_painter([10, 10], "hover")
+egui.allocate_painter((10, 10))
+egui.allocate_painter(desired_size=(10, 10), sense="drag")
+"#,
+    );
+}
+
+#[test]
+fn allocate_painter_bad_sense() {
+    let err = run_err("egui.allocate_painter([10.0, 10.0], 'poke')");
+    assert!(
+        err.contains("Must be hover, click, drag, or focusable"),
+        "{err}"
+    );
+}
+
+#[test]
+fn allocate_painter_bad_size() {
+    let err = run_err("egui.allocate_painter([10.0, 10.0, 10.0], 'click')");
+    assert!(err.contains("Points must be of dimension 2"), "{err}");
+}
+
+#[test]
+fn painter_line_emits_segments() {
+    let report = run_once(
+        r#"
+paint = egui.painter()
+paint.line([[0.0, 0.0], [10.0, 0.0], [10.0, 10.0]], 2.0, [255, 0, 0, 255])
+"#,
+    );
+    assert_eq!(report.error, None);
+
+    let segments: Vec<_> = shapes(&report)
+        .filter_map(|shape| match shape {
+            Shape::LineSegment { points, stroke } => Some((*points, stroke.clone())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(segments.len(), 2);
+    assert_eq!(segments[0].0, [Pos2::new(0.0, 0.0), Pos2::new(10.0, 0.0)]);
+    assert_eq!(segments[1].0, [Pos2::new(10.0, 0.0), Pos2::new(10.0, 10.0)]);
+    for (_, stroke) in segments {
+        assert_eq!(stroke.width, 2.0);
+        assert!(matches!(stroke.color, ColorMode::Solid(c) if c == Color32::RED));
+    }
+}
+
+#[test]
+fn painter_circle() {
+    let report = run_once(
+        r#"
+resp, paint = egui.allocate_painter([100.0, 100.0], "click")
+paint.circle([50.0, 60.0], 12.5, [0, 0, 255, 255], 1.5, [255, 255, 255, 255])
+"#,
+    );
+    assert_eq!(report.error, None);
+
+    let circle = shapes(&report)
+        .find_map(|shape| match shape {
+            Shape::Circle(circle) => Some(*circle),
+            _ => None,
+        })
+        .expect("No circle was painted");
+    assert_eq!(circle.center, Pos2::new(50.0, 60.0));
+    assert_eq!(circle.radius, 12.5);
+    assert_eq!(circle.fill, Color32::BLUE);
+    assert_eq!(circle.stroke.width, 1.5);
+    assert_eq!(circle.stroke.color, Color32::WHITE);
+}
+
+#[test]
+fn painter_keyword_arguments() {
+    let report = run_once(
+        r#"
+p = egui.painter()
+p.circle((10, 10), 5, fill="red")
+p.circle(center=(20, 20), radius=5, stroke_color="blue")
+p.line([(0, 0), (1, 1)], color="green")
+p.text((0, 0), "LEFT_TOP", text="hi")
+"#,
+    );
+    assert_eq!(report.error, None);
+
+    let circles: Vec<_> = shapes(&report)
+        .filter_map(|shape| match shape {
+            Shape::Circle(circle) => Some(*circle),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(circles.len(), 2);
+    assert_eq!(circles[0].fill, Color32::RED);
+    assert_eq!(circles[0].stroke.width, 0.0);
+    assert_eq!(circles[1].fill, Color32::TRANSPARENT);
+    assert_eq!(circles[1].stroke.width, 1.0);
+    assert_eq!(circles[1].stroke.color, Color32::BLUE);
+
+    let line = shapes(&report)
+        .find_map(|shape| match shape {
+            Shape::LineSegment { stroke, .. } => Some(stroke.clone()),
+            _ => None,
+        })
+        .expect("No line was painted");
+    assert_eq!(line.width, 1.0);
+    assert!(matches!(line.color, ColorMode::Solid(c) if c == Color32::GREEN));
+}
+
+#[test]
+fn painter_accepts_ints() {
+    let report = run_once(
+        r#"
+egui.button("ok", rounding=4)
+p = egui.painter()
+p.circle((10, 10), 5, fill="red", stroke_width=2, stroke_color="blue")
+p.line([(0, 0), (1, 1)], 3, "green")
+"#,
+    );
+    assert_eq!(report.error, None);
+    let circle = shapes(&report)
+        .find_map(|shape| match shape {
+            Shape::Circle(circle) => Some(*circle),
+            _ => None,
+        })
+        .expect("No circle was painted");
+    assert_eq!((circle.radius, circle.stroke.width), (5.0, 2.0));
+    let line = shapes(&report)
+        .find_map(|shape| match shape {
+            Shape::LineSegment { stroke, .. } => Some(stroke.width),
+            _ => None,
+        })
+        .expect("No line was painted");
+    assert_eq!(line, 3.0);
+}
+
+#[test]
+fn egui_keyword_arguments() {
+    let out = run_ok(
+        r#"
+egui.label(text="hi")
+egui.button(text="ok", fill="red")
+text, resp = egui.text_edit_singleline(text="edit")
+print(text)
+print(egui.animate_value(id="x", target=3.0, duration=0.0))
+print(egui.animate_bool(id="open", value=False))
+egui.request_repaint_after(seconds=1.0)
+egui.table(id="t", columns=["a"], rows=[[1]])
+"#,
+    );
+    assert_eq!(out, "edit\n3.0\n0.0\n");
+}
+
+#[test]
+fn missing_argument_is_a_type_error() {
+    let err = run_err("egui.painter().circle((0, 0))");
+    assert!(err.contains("TypeError"), "{err}");
+}
+
+#[test]
+fn painter_text_returns_rect() {
+    let report = run_once(
+        r#"
+r = egui.painter().text([100.0, 100.0], "LEFT_TOP", "hello", [255, 255, 255, 255])
+print(r.min())
+"#,
+    );
+    assert_eq!(report.error, None);
+    assert_eq!(parse_point(&report.stdout), Pos2::new(100.0, 100.0));
+    assert!(shapes(&report)
+        .any(|shape| matches!(shape, Shape::Text(text) if text.galley.text() == "hello")));
+}
+
+#[test]
+fn painter_text_anchors() {
+    run_ok(
+        r#"
+paint = egui.painter()
+for h in ["LEFT", "CENTER", "RIGHT"]:
+    for v in ["TOP", "CENTER", "BOTTOM"]:
+        paint.text([0.0, 0.0], f"{h}_{v}".lower(), "x", [0, 0, 0, 255])
+paint.text([0.0, 0.0], "", "x", [0, 0, 0, 255])
+"#,
+    );
+}
+
+#[test]
+fn painter_text_bad_anchor() {
+    let err = run_err("egui.painter().text([0.0, 0.0], 'MIDDLE', 'x', [0, 0, 0, 255])");
+    assert!(
+        err.contains("Must be {LEFT,CENTER,RIGHT}_{TOP,CENTER,BOTTOM}"),
+        "{err}"
+    );
+}
+
+#[test]
+fn wrong_length_color() {
+    let err = run_err("egui.painter().line([[0.0, 0.0], [1.0, 1.0]], 1.0, [255, 0])");
+    assert!(err.contains("ValueError"), "{err}");
+    assert!(
+        err.contains("Colors have 3 or 4 components, not 2"),
+        "{err}"
+    );
+}
+
+#[test]
+fn color_formats() {
+    let out = run_ok(
+        r##"
+C = egui.Color32
+for color in [
+    "#ff8800", "#ff000080", "#f80", "orange", "Light Blue",
+    (255, 136, 0), [255, 136, 0, 255], (1.0, 0.5, 0), (1.0, 0.0, 0.0, 0.5),
+    C.from_hsv(0.0, 1.0, 1.0), C.from_rgba_unmultiplied(255, 0, 0, 128),
+]:
+    print(C(color))
+print(C(255, 0, 0) == C("red"), C("red") == C("blue"))
+print(C(255, 0, 0, 128) == C(1.0, 0.0, 0.0, 0.5), C(128, 0, 0, 128) == C.from_rgba_premultiplied(128, 0, 0, 128))
+"##,
+    );
+    assert_eq!(
+        out,
+        r##"Color32("#ff8800")
+Color32("#ff000080")
+Color32("#ff8800")
+Color32("#ffa500")
+Color32("#add8e6")
+Color32("#ff8800")
+Color32("#ff8800")
+Color32("#ff8000")
+Color32("#ff000080")
+Color32("#ff0000")
+Color32("#ff000080")
+True False
+True False
+"##
+    );
+}
+
+#[test]
+fn color_arithmetic() {
+    let out = run_ok(
+        r##"
+black, white = egui.Color32("black"), egui.Color32("white")
+print(black.lerp(white, 0.5).to_tuple())
+print(white.gamma_multiply(0.5).a, white.linear_multiply(0.0).to_tuple())
+print(tuple(round(x, 3) for x in egui.Color32.from_hex("#00ff00").to_hsva()))
+"##,
+    );
+    assert_eq!(
+        out,
+        "(128, 128, 128, 255)\n128 (0, 0, 0, 0)\n(0.333, 1.0, 1.0, 1.0)\n"
+    );
+}
+
+#[test]
+fn colors_accept_ints_for_floats() {
+    let out = run_ok(
+        r#"
+C = egui.Color32
+print(C.from_hsv(0, 1, 1), C.from_hsva(0, 1, 1, 1), C((1.0, 0, 0)), C("black").lerp("white", 1))
+"#,
+    );
+    assert_eq!(
+        out,
+        "Color32(\"#ff0000\") Color32(\"#ff0000\") Color32(\"#ff0000\") Color32(\"#ffffff\")\n"
+    );
+}
+
+#[test]
+fn color_errors() {
+    for (code, kind, message) in [
+        ("egui.Color32('#12')", "ValueError", "not a hex color"),
+        ("egui.Color32('chartreuse')", "ValueError", "Unknown color"),
+        ("egui.Color32((256, 0, 0))", "ValueError", "0 to 255"),
+        ("egui.Color32((1.5, 0.0, 0.0))", "ValueError", "0 to 1"),
+        ("egui.Color32(42)", "TypeError", "Expected a color"),
+        ("egui.Color32(42)", "TypeError", "Color32.from_hsv"),
+        ("egui.Color32(('a', 'b', 'c'))", "TypeError", ""),
+    ] {
+        let err = run_err(code);
+        assert!(err.contains(kind) && err.contains(message), "{code}: {err}");
+    }
+}
+
+#[test]
+fn vec2_and_pos2_arithmetic() {
+    let out = run_ok(
+        r#"
+V, P = egui.Vec2, egui.Pos2
+v = V(3, 4)
+print(v + V(1, 1), v - V(1, 1), v * 2, 2 * v, v / 2, -v)
+print(v.length(), v.normalized(), v.rot90(), v.dot((1, 0)))
+print(P(1, 2) + v, P(5, 5) - P(1, 2), P(5, 5) - v)
+x, y = P(1, 2)
+print(x, y, P((1, 2)) == P(1.0, 2.0), V(1, 2) == P(1, 2))
+print(P(0, 0).lerp([10, 20], 0.5), V(0, 0).lerp(V(2, 4), 0.25), P(0, 0).distance(P(3, 4)))
+"#,
+    );
+    assert_eq!(
+        out,
+        "Vec2(4.0, 5.0) Vec2(2.0, 3.0) Vec2(6.0, 8.0) Vec2(6.0, 8.0) Vec2(1.5, 2.0) Vec2(-3.0, -4.0)
+5.0 Vec2(0.6, 0.8) Vec2(4.0, -3.0) 3.0
+Pos2(4.0, 6.0) Vec2(4.0, 3.0) Pos2(2.0, 1.0)
+1.0 2.0 True False
+Pos2(5.0, 10.0) Vec2(0.5, 1.0) 5.0
+"
+    );
+}
+
+#[test]
+fn geometry_accepts_ints() {
+    let out = run_ok(
+        r#"
+V, P = egui.Vec2, egui.Pos2
+print(V(1, 2).lerp((3, 4), 1), P(0, 0).lerp([2, 2], 1), V(1, 2) * 2)
+print(egui.Rect((0, 0), (2, 2)).expand(1))
+"#,
+    );
+    assert_eq!(
+        out,
+        "Vec2(3.0, 4.0) Pos2(2.0, 2.0) Vec2(2.0, 4.0)\nRect(Pos2(-1.0, -1.0), Pos2(3.0, 3.0))\n"
+    );
+}
+
+#[test]
+fn rect_methods() {
+    let out = run_ok(
+        r#"
+r = egui.Rect((0, 0), egui.Pos2(10, 20))
+print(r, r.center(), r.width(), r.height(), r.size())
+print(r.contains((5, 5)), r.contains(egui.Pos2(15, 5)))
+print(r.expand(1))
+other = egui.Rect.from_min_size((5, 10), (10, 10))
+print(r.intersects(other), r.intersect(other), r.union(other))
+print(r.lerp((0.5, 0.25)), r.translate(egui.Vec2(1, 1)).min())
+print(egui.Rect.from_center_size((5, 5), (2, 2)) == egui.Rect((4, 4), (6, 6)))
+"#,
+    );
+    assert_eq!(
+        out,
+        "Rect(Pos2(0.0, 0.0), Pos2(10.0, 20.0)) Pos2(5.0, 10.0) 10.0 20.0 Vec2(10.0, 20.0)
+True False
+Rect(Pos2(-1.0, -1.0), Pos2(11.0, 21.0))
+True Rect(Pos2(5.0, 10.0), Pos2(10.0, 20.0)) Rect(Pos2(0.0, 0.0), Pos2(15.0, 20.0))
+Pos2(5.0, 5.0) Pos2(1.0, 1.0)
+True
+"
+    );
+}
+
+#[test]
+fn points_accept_geometry_types() {
+    let report = run_once(
+        r#"
+p = egui.painter()
+p.line([egui.Pos2(0, 0), (10, 0), [10, 10]], 1.0, "red")
+p.circle(egui.Pos2(5, 5) + egui.Vec2(1, 1), 2.0, "red", 1.0, "blue")
+r = p.text(egui.Pos2(100, 100), "LEFT_TOP", "Hi", "white")
+print(r.min())
+"#,
+    );
+    assert_eq!(report.error, None);
+    assert_eq!(report.stdout, "Pos2(100.0, 100.0)\n");
+}
+
+#[test]
+fn points_reject_non_points() {
+    let err = run_err("egui.Vec2('ab')");
+    assert!(err.contains("TypeError"), "{err}");
+    let err = run_err("egui.Vec2(1, 2) + 'a'");
+    assert!(err.contains("TypeError"), "{err}");
+}
+
+#[test]
+fn wrong_dimension_point() {
+    let err =
+        run_err("egui.painter().circle([0.0, 0.0, 0.0], 1.0, [0, 0, 0, 0], 1.0, [0, 0, 0, 0])");
+    assert!(err.contains("Points must be of dimension 2"), "{err}");
+}
+
+#[test]
+fn exceptions_are_catchable() {
+    let out = run_ok(
+        r#"
+try:
+    egui.painter().text([0.0, 0.0], "nowhere", "x", [0, 0, 0, 255])
+except RuntimeError:
+    print("caught")
+"#,
+    );
+    assert_eq!(out, "caught\n");
+}
+
+fn key_event(key: egui::Key, pressed: bool) -> Event {
+    Event::Key {
+        key,
+        physical_key: None,
+        pressed,
+        repeat: false,
+        modifiers: Modifiers::NONE,
+    }
+}
+
+#[test]
+fn input_keys() {
+    let mut headless = headless(
+        r#"
+i = egui.input()
+print(i.key_pressed("space"), i.key_down("Space"), i.key_pressed("A"))
+"#,
+    );
+    let report = headless.step(vec![key_event(egui::Key::Space, true)]);
+    assert_eq!(report.error, None);
+    assert_eq!(report.stdout, "True True False\n");
+
+    // Held, but not newly pressed
+    let report = headless.step(vec![]);
+    assert_eq!(report.stdout, "False True False\n");
+}
+
+#[test]
+fn input_unknown_key() {
+    let err = run_err("egui.input().key_pressed('NotAKey')");
+    assert!(err.contains("ValueError"), "{err}");
+}
+
+#[test]
+fn input_pointer_and_text() {
+    let mut headless = headless(
+        r#"
+i = egui.input()
+print(i.pointer.pos)
+print(i.text_events)
+print(i.screen_rect.max())
+"#,
+    );
+    let report = headless.step(vec![
+        Event::PointerMoved(Pos2::new(12.0, 34.0)),
+        Event::Text("hi".to_owned()),
+    ]);
+    assert_eq!(report.error, None);
+    assert_eq!(
+        report.stdout,
+        "Pos2(12.0, 34.0)\n['hi']\nPos2(640.0, 480.0)\n"
+    );
+}
+
+#[test]
+fn input_modifiers_and_time() {
+    let out = run_ok(
+        r#"
+i = egui.input()
+m = i.modifiers
+print(m.ctrl, m.shift, m.alt, m.command)
+print(i.time >= 0, i.stable_dt > 0, i.scroll_delta, i.pointer.delta)
+"#,
+    );
+    assert_eq!(
+        out,
+        "False False False False\nTrue True Vec2(0.0, 0.0) Vec2(0.0, 0.0)\n"
+    );
+}
+
+#[test]
+fn time_and_frames() {
+    let mut headless = headless("print(egui.frame_number(), egui.time(), egui.dt() > 0)");
+    assert_eq!(headless.step(vec![]).stdout, "0 0.0 True\n");
+    let report = headless.step(vec![]);
+    assert_eq!(report.error, None);
+    assert!(report.stdout.starts_with("1 0.016"), "{}", report.stdout);
+}
+
+#[test]
+fn animations() {
+    let mut headless = headless(
+        r#"
+print(egui.animate_bool("open", egui.frame_number() > 0))
+print(egui.animate_value("x", 10.0 if egui.frame_number() > 0 else 0.0, 1.0))
+"#,
+    );
+    assert_eq!(headless.step(vec![]).stdout, "0.0\n0.0\n");
+
+    // Animations move part of the way towards their targets each frame. Values only
+    // start moving the frame after their target changes, like in egui.
+    let report = headless.step(vec![]);
+    let values: Vec<f32> = report.stdout.lines().map(|l| l.parse().unwrap()).collect();
+    assert!(values[0] > 0.0 && values[0] < 1.0, "{values:?}");
+    assert_eq!(values[1], 0.0);
+
+    let report = headless.step(vec![]);
+    let values: Vec<f32> = report.stdout.lines().map(|l| l.parse().unwrap()).collect();
+    assert!(values[1] > 0.0 && values[1] < 10.0, "{values:?}");
+}
+
+#[test]
+fn request_repaint_after() {
+    let report = run_once("egui.request_repaint_after(0.5)");
+    assert_eq!(report.error, None);
+    let repaint_delay = report.output.viewport_output[&egui::ViewportId::ROOT].repaint_delay;
+    assert!(repaint_delay <= std::time::Duration::from_secs_f32(0.5));
+
+    let err = run_err("egui.request_repaint_after(-1)");
+    assert!(err.contains("ValueError"), "{err}");
+}
+
+#[test]
+fn time_helpers_accept_ints() {
+    let out = run_ok("print(egui.animate_value('x', 3, 0))\negui.request_repaint_after(1)");
+    assert_eq!(out, "3.0\n");
+}
+
+#[test]
+fn request_repaint() {
+    let report = run_once("egui.request_repaint()");
+    assert_eq!(report.error, None);
+    let repaint_delay = report.output.viewport_output[&egui::ViewportId::ROOT].repaint_delay;
+    assert_eq!(repaint_delay, std::time::Duration::ZERO);
+}
+
+#[test]
+fn plot_series() {
+    let report = run_once(
+        r#"
+p = egui.plot("demo", height=200, x_label="t", y_label="v", link="group")
+p.line([0, 1, 4, 9], name="squares")
+p.line([0, 1, 2], [3, 2, 1], color=[255, 0, 0, 255], width=2)
+p.scatter([1, 2], [1, 2], name="points", width=3)
+p.bar([1, 2, 3], x=[10, 20, 30], width=5)
+p.histogram([1, 1, 2, 3, 5, 8], bins=4)
+p.box_plot([1, 2, 3, 4, 100], x=2)
+p.heatmap([[0, 1], [2, 3]], x=5, y=5)
+resp = p.show()
+print(resp.pointer)
+print(len(resp.bounds))
+"#,
+    );
+    assert_eq!(report.error, None);
+    assert_eq!(report.stdout, "None\n4\n");
+}
+
+#[test]
+fn histogram_bins_are_capped() {
+    let err = run_err("egui.plot('huge').histogram([1, 2, 3], bins=10**9)");
+    assert!(err.contains("ValueError"), "{err}");
+    assert!(err.contains("at most 10000 bins"), "{err}");
+}
+
+#[test]
+fn plot_set_bounds() {
+    let out = run_ok(
+        r#"
+p = egui.plot("bounded")
+p.line([0, 1])
+p.set_bounds(-1, 1, -2, 2)
+print(p.show().bounds)
+"#,
+    );
+    assert_eq!(out, "(-1.0, 1.0, -2.0, 2.0)\n");
+}
+
+#[test]
+fn plots_accept_ints() {
+    let out = run_ok(
+        r#"
+egui.plot("square", height=100, width=200, data_aspect=1).show()
+p = egui.plot("ints", height=100, width=200)
+p.line([0, 1], width=2)
+p.box_plot([1, 2, 3], x=2)
+p.heatmap([[0, 1]], x=5, y=5)
+p.set_bounds(0, 2, 0, 4)
+print(p.show().bounds)
+"#,
+    );
+    assert_eq!(out, "(0.0, 2.0, 0.0, 4.0)\n");
+}
+
+#[test]
+fn plot_mismatched_lengths() {
+    let err = run_err("p = egui.plot('bad'); p.line([0, 1, 2], [0, 1])");
+    assert!(err.contains("ValueError"), "{err}");
+}
+
+#[test]
+fn table_renders_cells() {
+    let report = run_once(
+        r#"
+rows = [["alice", 31], ["bob", 27.5], ["carol", None]]
+t = egui.table("people", ["Name", "Age"], rows, widths=[100, "remainder"], height=200)
+print(t.selected, t.clicked, t.sort)
+"#,
+    );
+    assert_eq!(report.error, None);
+    assert_eq!(report.stdout, "[] None None\n");
+    for cell in ["Name", "Age", "alice", "31", "27.5", "None"] {
+        assert!(
+            shapes(&report).any(
+                |shape| matches!(shape, Shape::Text(text) if text.galley.text().starts_with(cell))
+            ),
+            "{cell} was not drawn"
+        );
+    }
+}
+
+#[test]
+fn table_only_renders_visible_rows() {
+    let report = run_once(
+        r#"
+egui.table("long", ["i"], [[i] for i in range(100000)], height=100)
+"#,
+    );
+    assert_eq!(report.error, None);
+    let texts = shapes(&report)
+        .filter(|shape| matches!(shape, Shape::Text(_)))
+        .count();
+    assert!(texts < 100, "{texts} texts drawn");
+}
+
+#[test]
+fn table_selection_follows_the_key() {
+    let mut headless = headless(
+        r#"
+try: frame += 1
+except: frame = 0
+rows = [["alice", 31], ["bob", 27]]
+if frame >= 3:
+    rows.insert(0, ["carol", 40])
+t = egui.table("people", ["Name", "Age"], rows)
+print(t.selected, t.clicked)
+"#,
+    );
+    let first = headless.step(vec![]);
+    assert_eq!(first.error, None);
+    let bob = shapes(&first)
+        .find_map(|shape| match shape {
+            Shape::Text(text) if text.galley.text() == "bob" => Some(text.pos),
+            _ => None,
+        })
+        .expect("bob was not drawn");
+    let center = bob + egui::vec2(4.0, 4.0);
+
+    headless.step(click_events(center, true));
+    let clicked = headless.step(click_events(center, false));
+    assert_eq!(clicked.stdout, "[1] 1\n");
+
+    // Rows added in front move bob down, and his row stays selected
+    let moved = headless.step(vec![]);
+    assert_eq!(moved.error, None);
+    assert_eq!(moved.stdout, "[2] None\n");
+}
+
+#[test]
+fn table_keys_with_equal_hashes_are_told_apart() {
+    let mut headless = headless(
+        r#"
+assert hash(-1) == hash(-2)
+t = egui.table("numbers", ["n"], [[-1], [-2]])
+print(t.selected)
+"#,
+    );
+    let first = headless.step(vec![]);
+    assert_eq!(first.error, None);
+    let first_row = shapes(&first)
+        .find_map(|shape| match shape {
+            Shape::Text(text) if text.galley.text() == "-1" => Some(text.pos),
+            _ => None,
+        })
+        .expect("-1 was not drawn");
+    let center = first_row + egui::vec2(4.0, 4.0);
+
+    headless.step(click_events(center, true));
+    let clicked = headless.step(click_events(center, false));
+    assert_eq!(clicked.error, None);
+    assert_eq!(clicked.stdout, "[0]\n");
+}
+
+#[test]
+fn tables_accept_ints() {
+    let report = run_once(
+        r#"egui.table("ints", ["n"], [[2], [10]], widths=[50], height=100, row_height=20)"#,
+    );
+    assert_eq!(report.error, None);
+    for cell in ["2", "10"] {
+        assert!(
+            shapes(&report)
+                .any(|shape| matches!(shape, Shape::Text(text) if text.galley.text() == cell)),
+            "{cell} was not drawn"
+        );
+    }
+}
+
+#[test]
+fn table_bad_widths() {
+    let err = run_err("egui.table('t', ['a', 'b'], [], widths=[10])");
+    assert!(err.contains("ValueError"), "{err}");
+    let err = run_err("egui.table('t', ['a'], [], widths=['wide'])");
+    assert!(err.contains("ValueError"), "{err}");
+    let err = run_err("egui.table('t', ['a'], [], key=1)");
+    assert!(err.contains("ValueError"), "{err}");
+}
+
+#[test]
+fn style_round_trip() {
+    let out = run_ok(
+        r#"
+s = egui.style()
+s.dark_mode = False
+s.selection_color = [10, 20, 30, 255]
+s.item_spacing = [3, 4]
+s.indent = 7
+s.widget_rounding = 5
+s.text_style = "Monospace"
+s.set_text_size("Body", 20)
+print(s.dark_mode, s.selection_color, s.item_spacing, s.indent, s.widget_rounding)
+print(s.text_style, s.text_size("Body"), s.text_color)
+"#,
+    );
+    assert_eq!(
+        out,
+        "False Color32(\"#0a141e\") Vec2(3.0, 4.0) 7.0 5.0\nMonospace 20.0 None\n"
+    );
+}
+
+#[test]
+fn style_accepts_ints() {
+    let out = run_ok(
+        r#"
+s = egui.style()
+s.widget_rounding = 4
+s.indent = 10
+s.set_text_size("Body", 20)
+print(s.widget_rounding, s.indent, s.text_size("Body"))
+"#,
+    );
+    assert_eq!(out, "4.0 10.0 20.0\n");
+}
+
+#[test]
+fn style_is_scoped_to_the_script() {
+    let mut headless = headless(
+        r#"
+egui.style().dark_mode = False
+egui.style().item_spacing = [30, 40]
+"#,
+    );
+    let before = headless.ctx().style();
+    assert_eq!(headless.step(vec![]).error, None);
+    assert_eq!(*headless.ctx().style(), *before);
+}
+
+#[test]
+fn style_unknown_text_style() {
+    let err = run_err("egui.style().text_size('Huge')");
+    assert!(err.contains("ValueError"), "{err}");
+}
+
+#[test]
+fn button_overrides() {
+    let report = run_once(
+        r#"
+r = egui.button("Go", fill=[0, 255, 0, 255], min_size=[120, 40]).rect()
+print(r.min())
+print(r.max())
+"#,
+    );
+    assert_eq!(report.error, None);
+    let mut lines = report.stdout.lines();
+    let min = parse_point(lines.next().unwrap());
+    let max = parse_point(lines.next().unwrap());
+    assert!(max.x - min.x >= 120.0 && max.y - min.y >= 40.0);
+    assert!(shapes(&report)
+        .any(|shape| matches!(shape, Shape::Rect(rect) if rect.fill == Color32::GREEN)));
+}
+
+#[test]
+fn label_with_rich_text() {
+    let report = run_once(
+        r#"
+t = egui.rich_text("Big").size(32).strong().color([255, 0, 0, 255])
+egui.label(t)
+egui.label("plain")
+print(t.text)
+"#,
+    );
+    assert_eq!(report.error, None);
+    assert_eq!(report.stdout, "Big\n");
+    let big = shapes(&report)
+        .find_map(|shape| match shape {
+            Shape::Text(text) if text.galley.text() == "Big" => Some(text.clone()),
+            _ => None,
+        })
+        .expect("Rich text was not drawn");
+    let section = &big.galley.job.sections[0];
+    assert_eq!(section.format.font_id.size, 32.0);
+    assert_eq!(section.format.color, Color32::RED);
+}
+
+#[test]
+fn layout_job_in_painter() {
+    let report = run_once(
+        r#"
+job = egui.layout_job()
+job.append("plain ")
+job.append(egui.rich_text("mono").monospace().underline())
+job.append(egui.rich_text(" italic").italics())
+r = egui.painter().text([10.0, 10.0], "LEFT_TOP", job, [0, 255, 0, 255])
+print(r.min())
+"#,
+    );
+    assert_eq!(report.error, None);
+    assert_eq!(parse_point(&report.stdout), Pos2::new(10.0, 10.0));
+    let text = shapes(&report)
+        .find_map(|shape| match shape {
+            Shape::Text(text) if text.galley.text() == "plain mono italic" => Some(text.clone()),
+            _ => None,
+        })
+        .expect("Layout job was not painted");
+    assert_eq!(text.galley.job.sections.len(), 3);
+    assert!(text.galley.job.sections[2].format.italics);
+}
+
+#[test]
+fn rich_text_accepts_ints() {
+    let report = run_once(
+        r#"
+job = egui.layout_job()
+job.append(egui.rich_text("Big").size(20))
+job.wrap_width = 100
+print(job.wrap_width)
+egui.label(job)
+"#,
+    );
+    assert_eq!(report.error, None);
+    assert_eq!(report.stdout, "100.0\n");
+    let big = shapes(&report)
+        .find_map(|shape| match shape {
+            Shape::Text(text) if text.galley.text() == "Big" => Some(text.clone()),
+            _ => None,
+        })
+        .expect("Rich text was not drawn");
+    assert_eq!(big.galley.job.sections[0].format.font_id.size, 20.0);
+}
+
+#[test]
+fn label_rejects_other_types() {
+    let err = run_err("egui.label(42)");
+    assert!(err.contains("TypeError"), "{err}");
+}
+
+#[test]
+fn add_font_rejects_bad_data() {
+    let err = run_err("egui.add_font('junk', b'not a font')");
+    assert!(
+        err.contains("ValueError") && err.contains("not a TTF or OTF font"),
+        "{err}"
+    );
+    let err = run_err("egui.add_font('Monospace', b'')");
+    assert!(err.contains("ValueError"), "{err}");
+}
+
+#[test]
+fn project_fonts_are_loaded() {
+    // A real font, from egui's own
+    let definitions = egui::FontDefinitions::default();
+    let font = ProjectFont {
+        name: "Mine".to_owned(),
+        data: definitions.font_data["Hack"].font.to_vec(),
+    };
+
+    let mut headless = headless(
+        r#"
+egui.label(egui.rich_text("hello").family("Mine"))
+print(egui.fonts())
+"#,
+    );
+    assert_eq!(headless.load_fonts(&[font]), Ok(()));
+
+    // Fonts are usable from the next frame
+    headless.step(vec![]);
+    let report = headless.step(vec![]);
+    assert_eq!(report.error, None);
+    assert_eq!(report.stdout, "['Mine']\n");
+    let galley = shapes(&report)
+        .find_map(|shape| match shape {
+            Shape::Text(shape) if shape.galley.text() == "hello" => Some(shape.galley.clone()),
+            _ => None,
+        })
+        .expect("Text was not drawn");
+    assert_eq!(
+        galley.job.sections[0].format.font_id.family,
+        egui::FontFamily::Name("Mine".into())
+    );
+}
+
+#[test]
+fn project_fonts_are_stored_as_base64() {
+    let font = ProjectFont {
+        name: "Mine".to_owned(),
+        data: b"font".to_vec(),
+    };
+    let json = serde_json::to_string(&font).unwrap();
+    assert_eq!(json, r#"{"name":"Mine","data":"Zm9udA=="}"#);
+    assert_eq!(serde_json::from_str::<ProjectFont>(&json).unwrap(), font);
+}
+
+#[test]
+fn broken_project_fonts_are_reported() {
+    let broken = ProjectFont {
+        name: "Broken".to_owned(),
+        data: vec![0, 1],
+    };
+
+    let headless = headless("");
+    let err = headless.load_fonts(&[broken]).unwrap_err();
+    assert!(err.contains("Broken is not a TTF or OTF font"), "{err}");
+}
+
+#[test]
+fn unknown_font_family_falls_back() {
+    let report = run_once(
+        r#"
+egui.label(egui.rich_text("hello").family("NotLoaded"))
+egui.painter().text([0.0, 0.0], "LEFT_TOP", "there", [0, 0, 0, 255], "NotLoaded")
+print(egui.fonts())
+"#,
+    );
+    assert_eq!(report.error, None);
+    assert_eq!(report.stdout, "[]\n");
+    for text in ["hello", "there"] {
+        let galley = shapes(&report)
+            .find_map(|shape| match shape {
+                Shape::Text(shape) if shape.galley.text() == text => Some(shape.galley.clone()),
+                _ => None,
+            })
+            .expect("Text was not drawn");
+        assert_eq!(
+            galley.job.sections[0].format.font_id.family,
+            egui::FontFamily::Proportional
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use eframe_template::{
+    completion::{complete, signature_help, Global, Stubs},
+    headless::Headless,
+    RuntimePolicy,
+};
+
+fn names(code: &str, globals: &[Global]) -> Vec<String> {
+    complete(code, code.len(), globals)
+        .candidates
+        .into_iter()
+        .map(|candidate| candidate.name)
+        .collect()
+}
+
+#[test]
+fn stubs_match_bindings() {
+    let mut headless = Headless::new(
+        r#"
+classes = {
+    "PyEgui": type(egui),
+    "PyPainter": type(egui.painter()),
+    "PyResponse": type(egui.label("")),
+    "Rect": egui.Rect,
+    "Vec2": egui.Vec2,
+    "Pos2": egui.Pos2,
+    "Color32": egui.Color32,
+    "PyInput": type(egui.input()),
+    "PyPointer": type(egui.input().pointer),
+    "PyModifiers": type(egui.input().modifiers),
+    "RichText": type(egui.rich_text("")),
+    "LayoutJob": type(egui.layout_job()),
+    "PyStyle": type(egui.style()),
+    "PyTableResponse": type(egui.table("t", ["a"], [])),
+    "PyPlot": type(egui.plot("p")),
+    "PyPlotResponse": type(egui.plot("q").show()),
+}
+for name, cls in classes.items():
+    print(name, *sorted(n for n in dir(cls) if not n.startswith("_")))
+"#
+        .to_owned(),
+        RuntimePolicy::default(),
+        egui::vec2(640.0, 480.0),
+    );
+    let report = headless.step(vec![]);
+    assert_eq!(report.error, None);
+
+    let stubs = Stubs::get();
+    let mut bound = 0;
+    for line in report.stdout.lines() {
+        let mut words = line.split(' ');
+        let class = words.next().unwrap();
+        let actual: Vec<&str> = words.collect();
+        let members = stubs
+            .class(class)
+            .unwrap_or_else(|| panic!("{class} is missing from the stubs"));
+        let mut stubbed: Vec<&str> = members
+            .iter()
+            .map(|member| member.name.as_str())
+            .filter(|name| !name.starts_with('_'))
+            .collect();
+        stubbed.sort();
+        assert_eq!(stubbed, actual, "{class}");
+        bound += 1;
+    }
+    assert_eq!(bound, stubs.classes().count());
+}
+
+#[test]
+fn completes_members_of_the_egui_global() {
+    let found = names("egui.pa", &[]);
+    assert_eq!(found, ["painter"]);
+
+    let all = complete("egui.", 5, &[]);
+    assert!(all.candidates.len() > 20);
+    assert_eq!(all.replace, 5..5);
+}
+
+#[test]
+fn follows_calls_and_attributes() {
+    assert_eq!(names("egui.painter().ci", &[]), ["circle"]);
+    assert_eq!(
+        names("x = egui.input().pointer.pri", &[]),
+        ["primary_down", "primary_pressed", "primary_released"]
+    );
+    assert_eq!(names("egui.Vec2(1, 2).len", &[]), ["length"]);
+    assert_eq!(names("egui.style().item_spacing.rot", &[]), ["rot90"]);
+}
+
+#[test]
+fn follows_assignments() {
+    let code = "p = egui.painter()\nif True:\n    p.li";
+    assert_eq!(names(code, &[]), ["line"]);
+
+    let code = "resp, paint = egui.allocate_painter((10, 10))\nresp.cl";
+    assert_eq!(names(code, &[]), ["clicked"]);
+    let code = "resp, paint = egui.allocate_painter((10, 10))\npaint.te";
+    assert_eq!(names(code, &[]), ["text"]);
+}
+
+#[test]
+fn completes_names() {
+    let globals = [Global {
+        name: "counter".to_owned(),
+        class: "int".to_owned(),
+    }];
+    let code = "def count_up():\n    pass\ncou";
+    assert_eq!(names(code, &globals), ["count_up", "counter"]);
+    assert_eq!(names("eg", &[]), ["egui"]);
+    assert_eq!(names("whi", &[]), ["while"]);
+    assert!(names("1", &[]).is_empty());
+}
+
+#[test]
+fn uses_runtime_globals_for_types() {
+    let globals = [Global {
+        name: "canvas".to_owned(),
+        class: "PyPainter".to_owned(),
+    }];
+    assert_eq!(names("canvas.ci", &globals), ["circle"]);
+    assert!(names("unknown.ci", &globals).is_empty());
+}
+
+#[test]
+fn methods_insert_a_parenthesis() {
+    let completions = complete("egui.paint", 10, &[]);
+    assert_eq!(completions.replace, 5..10);
+    assert_eq!(completions.candidates[0].insert_text(), "painter(");
+
+    let completions = complete("egui.input().time", 17, &[]);
+    assert_eq!(completions.candidates.len(), 0);
+    let completions = complete("egui.input().ti", 15, &[]);
+    assert_eq!(completions.candidates[0].insert_text(), "time");
+}
+
+#[test]
+fn signature_help_for_calls() {
+    let code = "egui.painter().circle((0, 0), ";
+    let help = signature_help(code, code.len(), &[]).unwrap();
+    assert_eq!(help.name, "circle");
+    assert_eq!(help.active, 1);
+    assert_eq!(help.params[1], "radius: float");
+    assert_eq!(help.ty, "None");
+
+    let code = "v = egui.Vec2(";
+    let help = signature_help(code, code.len(), &[]).unwrap();
+    assert_eq!(help.name, "Vec2");
+    assert!(help.params[0].starts_with("x:"), "{:?}", help.params);
+
+    let code = "egui.label('hi')\nx = 1";
+    assert_eq!(signature_help(code, code.len(), &[]), None);
+    let code = "print(";
+    assert_eq!(signature_help(code, code.len(), &[]), None);
+}
+
+#[test]
+fn runtime_globals_include_the_script_and_builtins() {
+    let mut headless = Headless::new(
+        "counter = 3\ndef step(dt):\n    pass\n".to_owned(),
+        RuntimePolicy::default(),
+        egui::vec2(640.0, 480.0),
+    );
+    assert_eq!(headless.step(vec![]).error, None);
+
+    let globals = headless.globals();
+    let class_of = |name: &str| {
+        globals
+            .iter()
+            .find(|global| global.name == name)
+            .map(|global| global.class.as_str())
+    };
+    assert_eq!(class_of("counter"), Some("int"));
+    assert_eq!(class_of("step"), Some("function"));
+    assert_eq!(class_of("egui"), Some("PyEgui"));
+    assert!(class_of("print").is_some());
+}
@@ -0,0 +1,164 @@
+//! `help()` in scripts and the topics of the editor's docs panel
+
+use eframe_template::{
+    completion::{topic_at, Global, Topic},
+    headless::Headless,
+    RuntimePolicy,
+};
+
+fn headless(code: &str) -> Headless {
+    Headless::new(
+        code.to_owned(),
+        RuntimePolicy::default(),
+        egui::vec2(640.0, 480.0),
+    )
+}
+
+/// Runs `code` and returns its stdout, failing the test if the script raised
+fn run_ok(code: &str) -> String {
+    let report = headless(code).step(vec![]);
+    assert_eq!(report.error, None, "Script raised");
+    report.stdout
+}
+
+fn topic(code: &str, cursor: usize) -> Option<Topic> {
+    topic_at(code, cursor, &[])
+}
+
+#[test]
+fn every_method_has_a_docstring() {
+    let stdout = run_ok(
+        r#"
+classes = [
+    type(egui), type(egui.painter()), type(egui.label("")), egui.Rect, egui.Vec2,
+    egui.Pos2, egui.Color32, type(egui.input()), type(egui.rich_text("")),
+    type(egui.layout_job()), type(egui.style()), type(egui.plot("p")),
+]
+for cls in classes:
+    if not cls.__doc__:
+        print(cls.__name__)
+    for name in dir(cls):
+        member = getattr(cls, name)
+        if not name.startswith("_") and callable(member) and not member.__doc__:
+            print(cls.__name__, name)
+"#,
+    );
+    assert_eq!(stdout, "");
+}
+
+#[test]
+fn help_shows_the_signature_and_docstring() {
+    let stdout = run_ok("help(egui.button)");
+    let mut lines = stdout.lines();
+    assert_eq!(
+        lines.next(),
+        Some(
+            "PyEgui.button(text: Text, fill: Color | None = None, min_size: Point | None = None, \
+             rounding: float | None = None) -> PyResponse"
+        )
+    );
+    assert_eq!(lines.next(), Some(""));
+    assert!(lines.next().unwrap().starts_with("A clickable button"));
+
+    let stdout = run_ok("help(egui.Vec2.length)");
+    assert_eq!(stdout, "Vec2.length() -> float\n\nEuclidean length\n");
+}
+
+#[test]
+fn help_on_classes_lists_members() {
+    let stdout = run_ok("help(egui)");
+    assert!(
+        stdout.starts_with("class PyEgui\n\nThe `egui` global"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("\n\nlabel(text: Text) -> PyResponse\n    Text that isn't interactive"));
+
+    let stdout = run_ok("help(egui.Vec2)");
+    assert!(
+        stdout.starts_with("class Vec2(x: float | Point, y: float = ...)"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("\n\nx: float\n"), "{stdout}");
+}
+
+#[test]
+fn help_on_python_values() {
+    let stdout = run_ok("def step(dt):\n    '''Advance by dt'''\nhelp(step)");
+    assert_eq!(stdout, "step\n\nAdvance by dt\n");
+
+    let stdout = run_ok("help()");
+    assert!(stdout.starts_with("help(thing)"));
+}
+
+#[test]
+fn topics_under_the_cursor() {
+    let code = "p = egui.painter()\np.circle((0, 0), 5)";
+    let member = Some(Topic::Member {
+        class: "PyPainter".to_owned(),
+        name: "circle".to_owned(),
+    });
+    // Anywhere in the name, including just after it
+    assert_eq!(topic(code, 21), member);
+    assert_eq!(topic(code, 27), member);
+
+    assert_eq!(topic(code, 0), Some(Topic::Class("PyPainter".to_owned())));
+    assert_eq!(topic(code, 5), Some(Topic::Class("PyEgui".to_owned())));
+    assert_eq!(
+        topic("egui.Vec2(1, 2)", 6),
+        Some(Topic::Class("Vec2".to_owned()))
+    );
+    assert_eq!(
+        topic("print(1)", 2),
+        Some(Topic::Global("print".to_owned()))
+    );
+
+    assert_eq!(topic(code, 28), None);
+    assert_eq!(topic("if x:", 1), None);
+    assert_eq!(topic("egui.nothing", 7), None);
+}
+
+#[test]
+fn topics_use_runtime_globals() {
+    let globals = [Global {
+        name: "canvas".to_owned(),
+        class: "PyPainter".to_owned(),
+    }];
+    assert_eq!(
+        topic_at("canvas.line", 8, &globals),
+        Some(Topic::Member {
+            class: "PyPainter".to_owned(),
+            name: "line".to_owned(),
+        })
+    );
+}
+
+#[test]
+fn help_for_topics() {
+    let mut headless = headless("counter = 3\n");
+    assert_eq!(headless.step(vec![]).error, None);
+
+    let help = headless
+        .help(&Topic::Member {
+            class: "PyPainter".to_owned(),
+            name: "circle".to_owned(),
+        })
+        .unwrap();
+    assert!(
+        help.starts_with("PyPainter.circle(center: Point, radius: float"),
+        "{help}"
+    );
+    assert!(
+        help.ends_with("Without a `fill`, only the outline is drawn"),
+        "{help}"
+    );
+
+    let help = headless.help(&Topic::Class("Rect".to_owned())).unwrap();
+    assert!(
+        help.starts_with("class Rect(min: Point, max: Point)"),
+        "{help}"
+    );
+
+    let help = headless.help(&Topic::Global("counter".to_owned())).unwrap();
+    assert!(help.starts_with("int\n\n"), "{help}");
+    assert_eq!(headless.help(&Topic::Global("missing".to_owned())), None);
+}
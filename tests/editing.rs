@@ -0,0 +1,143 @@
+//! The code editor's own edits, with the selection marked as `«»` or the cursor as `|`
+
+use std::ops::Range;
+
+use eframe_template::editing::{
+    dedent, indent, matching_bracket, newline, toggle_comment, type_char, Edit,
+};
+
+fn unmark(marked: &str) -> (String, Range<usize>) {
+    if let Some(cursor) = marked.find('|') {
+        return (marked.replacen('|', "", 1), cursor..cursor);
+    }
+    let start = marked.find('«').expect("No selection marked");
+    let code = marked.replacen('«', "", 1);
+    let end = code.find('»').expect("Selection not closed");
+    (code.replacen('»', "", 1), start..end)
+}
+
+fn mark(code: &str, selection: Range<usize>) -> String {
+    if selection.is_empty() {
+        return format!("{}|{}", &code[..selection.start], &code[selection.start..]);
+    }
+    format!(
+        "{}«{}»{}",
+        &code[..selection.start],
+        &code[selection.clone()],
+        &code[selection.end..]
+    )
+}
+
+fn check(edit: impl Fn(&str, Range<usize>) -> Edit, before: &str, after: &str) {
+    let (mut code, selection) = unmark(before);
+    let edit = edit(&code, selection);
+    edit.apply(&mut code);
+    assert_eq!(mark(&code, edit.selection), after, "from {before:?}");
+}
+
+fn typed(c: char) -> impl Fn(&str, Range<usize>) -> Edit {
+    move |code, selection| type_char(code, selection, c).expect("Should have been handled")
+}
+
+#[test]
+fn newline_keeps_and_adjusts_the_indent() {
+    check(newline, "x = 1|", "x = 1\n|");
+    check(newline, "    x = 1|", "    x = 1\n    |");
+    check(newline, "if x:|", "if x:\n    |");
+    check(
+        newline,
+        "    def f():  # comment|",
+        "    def f():  # comment\n        |",
+    );
+    check(newline, "d = {'a': 1|", "d = {'a': 1\n|");
+    check(newline, "a«bc»d", "a\n|d");
+}
+
+#[test]
+fn newline_dedents_after_leaving_a_block() {
+    check(newline, "    return x|", "    return x\n|");
+    check(newline, "        pass|", "        pass\n    |");
+    check(newline, "    break|", "    break\n|");
+    check(newline, "    passed = 1|", "    passed = 1\n    |");
+    check(newline, "return|", "return\n|");
+}
+
+#[test]
+fn tab_inserts_spaces_to_the_next_level() {
+    check(indent, "|x", "    |x");
+    check(indent, "ab|", "ab  |");
+    check(indent, "a«b»", "a   |");
+}
+
+#[test]
+fn tab_indents_selected_lines() {
+    check(indent, "«a\nb»", "    «a\n    b»");
+    check(indent, "«a\n\nb»", "    «a\n\n    b»");
+    // A selection ending at the start of a line leaves that line alone
+    check(indent, "«a\n»b", "    «a\n»b");
+}
+
+#[test]
+fn shift_tab_dedents() {
+    check(dedent, "    |x", "|x");
+    check(dedent, "      x|", "    x|");
+    check(dedent, "  |  x", "|x");
+    check(dedent, "|x", "|x");
+    check(dedent, "«    a\n        b»", "«a\n    b»");
+}
+
+#[test]
+fn comments_toggle() {
+    check(toggle_comment, "|x = 1", "# |x = 1");
+    check(toggle_comment, "    |x", "    # |x");
+    check(toggle_comment, "«if x:\n    y»", "# «if x:\n#     y»");
+    check(toggle_comment, "«# if x:\n#     y»", "«if x:\n    y»");
+    check(toggle_comment, "#x|", "x|");
+    // Uncommented lines win, and blank lines stay blank
+    check(toggle_comment, "«# a\n\nb»", "# «# a\n\n# b»");
+}
+
+#[test]
+fn brackets_and_quotes_close() {
+    check(typed('('), "|", "(|)");
+    check(typed('('), "f|", "f(|)");
+    check(typed('['), "f(|)", "f([|])");
+    check(typed('"'), "x = |", "x = \"|\"");
+    check(typed('\''), "f(|)", "f('|')");
+    check(typed('['), "«ab»", "[«ab»]");
+    check(typed('"'), "x = «ab»", "x = \"«ab»\"");
+}
+
+#[test]
+fn closers_step_over() {
+    check(typed(')'), "(|)", "()|");
+    check(typed('"'), "\"|\"", "\"\"|");
+}
+
+#[test]
+fn some_characters_are_left_alone() {
+    let untouched = |marked: &str, c: char| {
+        let (code, selection) = unmark(marked);
+        assert_eq!(type_char(&code, selection, c), None, "{marked:?} {c:?}");
+    };
+    untouched("|x", '(');
+    untouched("it|", '\'');
+    untouched("\"\"|", '"');
+    untouched("|", ')');
+    untouched("|", 'a');
+}
+
+#[test]
+fn brackets_match() {
+    let code = "f(a[1], {2})";
+    assert_eq!(matching_bracket(code, 1), Some(11));
+    assert_eq!(matching_bracket(code, 11), Some(1));
+    assert_eq!(matching_bracket(code, 3), Some(5));
+    assert_eq!(matching_bracket(code, 10), Some(8));
+    assert_eq!(matching_bracket(code, 0), None);
+
+    assert_eq!(matching_bracket("print(')')", 5), Some(9));
+    assert_eq!(matching_bracket("f(  # (\n)", 1), Some(8));
+    assert_eq!(matching_bracket("s = \"\"\"(\n\"\"\" + (1)", 15), Some(17));
+    assert_eq!(matching_bracket("(]", 0), None);
+}
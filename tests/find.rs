@@ -0,0 +1,151 @@
+//! The editor's find and replace
+
+use std::ops::Range;
+
+use eframe_template::find::{mark_matches, next, previous, Query};
+use egui::{
+    text::{LayoutJob, TextFormat},
+    Color32,
+};
+
+fn query(text: &str) -> Query {
+    Query {
+        text: text.to_owned(),
+        ..Query::default()
+    }
+}
+
+#[test]
+fn plain_text_is_not_a_pattern() {
+    let code = "a.b = a*b\nA.B";
+    assert_eq!(query("a.b").find_all(code), Ok(vec![0..3, 10..13]));
+    assert_eq!(
+        query("*").find_all(code),
+        Ok(vec![Range { start: 7, end: 8 }])
+    );
+    assert_eq!(query("").find_all(code), Ok(vec![]));
+
+    let case_sensitive = Query {
+        case_sensitive: true,
+        ..query("a.b")
+    };
+    assert_eq!(
+        case_sensitive.find_all(code),
+        Ok(vec![Range { start: 0, end: 3 }])
+    );
+}
+
+#[test]
+fn whole_words() {
+    let whole_word = Query {
+        whole_word: true,
+        ..query("x")
+    };
+    assert_eq!(
+        whole_word.find_all("x = max(x, x_1) + x2\nx"),
+        Ok(vec![0..1, 8..9, 21..22])
+    );
+    // Words ending in punctuation too
+    let call = Query {
+        whole_word: true,
+        ..query("f(")
+    };
+    assert_eq!(
+        call.find_all("f(1) + gf(2)"),
+        Ok(vec![Range { start: 0, end: 2 }])
+    );
+}
+
+#[test]
+fn regexes() {
+    let regex = Query {
+        regex: true,
+        ..query(r"^def (\w+)")
+    };
+    let code = "def f():\n    pass\ndef g(): pass";
+    assert_eq!(regex.find_all(code), Ok(vec![0..5, 18..23]));
+
+    // Empty matches can't be found or replaced
+    let empty = Query {
+        regex: true,
+        ..query("x*")
+    };
+    assert_eq!(empty.find_all("axxb"), Ok(vec![Range { start: 1, end: 3 }]));
+
+    let invalid = Query {
+        regex: true,
+        ..query("(")
+    };
+    assert!(invalid.find_all(code).is_err());
+}
+
+#[test]
+fn replacing() {
+    let regex = Query {
+        regex: true,
+        ..query(r"(\w+)\.x")
+    };
+    let code = "a.x + b.x";
+    assert_eq!(
+        regex.replace_all(code, "$1.y"),
+        Ok(("a.y + b.y".to_owned(), 2))
+    );
+    assert_eq!(
+        regex.replacement(code, 6..9, "x($1)"),
+        Ok(Some("x(b)".to_owned()))
+    );
+    assert_eq!(regex.replacement(code, 7..9, "x($1)"), Ok(None));
+
+    // Only regexes have groups
+    let plain = query("a.x");
+    assert_eq!(
+        plain.replace_all("a.x + A.X", "$1"),
+        Ok(("$1 + $1".to_owned(), 2))
+    );
+    assert_eq!(query("").replace_all("abc", "x"), Ok(("abc".to_owned(), 0)));
+}
+
+#[test]
+fn navigation_wraps_around() {
+    let matches = [2..4, 10..12, 20..22];
+    assert_eq!(next(&matches, 0), Some(&(2..4)));
+    assert_eq!(next(&matches, 2), Some(&(2..4)));
+    assert_eq!(next(&matches, 4), Some(&(10..12)));
+    assert_eq!(next(&matches, 21), Some(&(2..4)));
+
+    assert_eq!(previous(&matches, 10), Some(&(2..4)));
+    assert_eq!(previous(&matches, 11), Some(&(10..12)));
+    assert_eq!(previous(&matches, 2), Some(&(20..22)));
+
+    assert_eq!(next(&[], 0), None);
+    assert_eq!(previous(&[], 0), None);
+}
+
+#[test]
+fn matches_are_marked_across_sections() {
+    let mut job = LayoutJob::default();
+    job.append("abc", 0.0, TextFormat::default());
+    job.append("def", 0.0, TextFormat::default());
+    job.append("ghi", 0.0, TextFormat::default());
+
+    let (mark, current) = (Color32::RED, Color32::GREEN);
+    mark_matches(&mut job, &[1..4, 7..8], Some(&(7..8)), mark, current);
+
+    let sections: Vec<_> = job
+        .sections
+        .iter()
+        .map(|section| (section.byte_range.clone(), section.format.background))
+        .collect();
+    assert_eq!(
+        sections,
+        [
+            (0..1, Color32::TRANSPARENT),
+            (1..3, mark),
+            (3..4, mark),
+            (4..6, Color32::TRANSPARENT),
+            (6..7, Color32::TRANSPARENT),
+            (7..8, current),
+            (8..9, Color32::TRANSPARENT),
+        ]
+    );
+}
@@ -0,0 +1,169 @@
+//! The project's edit history: undo, redo and checkpoints
+
+use eframe_template::history::{ago, edit_end, Entry, History};
+
+fn history(code: &str) -> History {
+    let mut history = History::default();
+    history.sync(code, 0.0);
+    history
+}
+
+fn codes(history: &History) -> Vec<&str> {
+    let (versions, _) = history.versions();
+    versions
+        .iter()
+        .map(|version| version.code.as_str())
+        .collect()
+}
+
+#[test]
+fn quick_edits_are_one_step() {
+    let mut history = history("");
+    history.edit("a", 10.0);
+    history.edit("ab", 10.5);
+    history.edit("abc", 11.0);
+    history.edit("abcd", 20.0);
+    assert_eq!(codes(&history), ["", "abc", "abcd"]);
+
+    assert_eq!(history.undo(), Some("abc"));
+    assert_eq!(history.undo(), Some(""));
+    assert_eq!(history.undo(), None);
+    assert_eq!(history.redo(), Some("abc"));
+    assert_eq!(history.redo(), Some("abcd"));
+    assert_eq!(history.redo(), None);
+}
+
+#[test]
+fn the_first_version_is_kept() {
+    // Editing right after loading doesn't merge into what was loaded
+    let mut history = history("x");
+    history.edit("xy", 0.1);
+    assert_eq!(codes(&history), ["x", "xy"]);
+}
+
+#[test]
+fn editing_after_undo_drops_redo() {
+    let mut history = history("a");
+    history.edit("ab", 10.0);
+    history.edit("abc", 20.0);
+    history.undo();
+    // Right after an undo still starts a new step
+    history.edit("abX", 20.1);
+    assert_eq!(codes(&history), ["a", "ab", "abX"]);
+    assert!(!history.can_redo());
+
+    // Unchanged code is no edit, and keeps what can be redone
+    history.undo();
+    history.edit("ab", 30.0);
+    assert!(history.can_redo());
+}
+
+#[test]
+fn restoring_can_be_undone() {
+    let mut history = history("a");
+    history.edit("b", 10.0);
+    history.checkpoint("first", "b", 10.0);
+    history.edit("c", 20.0);
+
+    let checkpoint = history.get(Entry::Checkpoint(0)).unwrap().code.clone();
+    history.restore(&checkpoint, 20.5);
+    assert_eq!(codes(&history), ["a", "b", "c", "b"]);
+    assert_eq!(history.undo(), Some("c"));
+}
+
+#[test]
+fn checkpoints_are_named() {
+    let mut history = history("");
+    history.checkpoint("one", "1", 0.0);
+    history.checkpoint("two", "2", 0.0);
+    history.checkpoint("one", "1 again", 5.0);
+    let names: Vec<_> = history
+        .checkpoints()
+        .iter()
+        .map(|(name, version)| (name.as_str(), version.code.as_str()))
+        .collect();
+    assert_eq!(names, [("one", "1 again"), ("two", "2")]);
+
+    history.remove_checkpoint(0);
+    history.remove_checkpoint(5);
+    assert_eq!(history.checkpoints().len(), 1);
+    assert_eq!(history.get(Entry::Checkpoint(1)), None);
+}
+
+#[test]
+fn old_versions_are_dropped() {
+    let mut history = history("");
+    for i in 0..500 {
+        history.edit(&i.to_string(), i as f64 * 10.0);
+    }
+    let (versions, current) = history.versions();
+    assert!(versions.len() < 500);
+    assert_eq!(versions[current].code, "499");
+}
+
+#[test]
+fn survives_saving() {
+    let mut history = history("a");
+    history.edit("ab", 10.0);
+    history.checkpoint("saved", "ab", 10.0);
+    history.undo();
+
+    let json = serde_json::to_string(&history).unwrap();
+    let mut loaded: History = serde_json::from_str(&json).unwrap();
+    loaded.sync("a", 100.0);
+    assert_eq!(codes(&loaded), ["a", "ab"]);
+    assert_eq!(loaded.redo(), Some("ab"));
+    assert_eq!(loaded.checkpoints().len(), 1);
+
+    // Projects saved before there was a history
+    let mut loaded: History = serde_json::from_str("{}").unwrap();
+    loaded.sync("old", 0.0);
+    assert_eq!(codes(&loaded), ["old"]);
+}
+
+#[test]
+fn corrupt_saves_load() {
+    let json = r#"{"versions":[{"code":"a","time":0.0}],"current":7,"checkpoints":[]}"#;
+    let mut loaded: History = serde_json::from_str(json).unwrap();
+    assert_eq!(loaded.versions().1, 0);
+    assert_eq!(loaded.undo(), None);
+    assert_eq!(loaded.redo(), None);
+}
+
+#[test]
+fn large_edits_are_bounded() {
+    let mut history = history("");
+    for i in 0..50 {
+        history.edit(&i.to_string().repeat(100_000), i as f64 * 10.0);
+    }
+    let (versions, current) = history.versions();
+    let bytes: usize = versions.iter().map(|version| version.code.len()).sum();
+    assert!(bytes <= 2 << 20, "{bytes} bytes kept");
+    assert_eq!(current, versions.len() - 1);
+    assert_eq!(history.undo().map(str::len), Some(100_000 * 2));
+
+    assert!(!history.checkpoint("huge", &"x".repeat(3 << 20), 1000.0));
+    assert!(history.checkpoint("small", "x", 1000.0));
+    assert_eq!(history.checkpoints().len(), 1);
+}
+
+#[test]
+fn cursor_goes_after_the_change() {
+    assert_eq!(edit_end("abc", "abXc"), 3);
+    assert_eq!(edit_end("abXc", "abc"), 2);
+    assert_eq!(edit_end("aa", "aaa"), 3);
+    assert_eq!(edit_end("x = 1\ny = 2", "x = 1\ny = 3"), 11);
+    assert_eq!(edit_end("é", "éé"), 4);
+    assert_eq!(edit_end("", ""), 0);
+}
+
+#[test]
+fn times_read_naturally() {
+    assert_eq!(ago(-3.0), "just now");
+    assert_eq!(ago(5.0), "just now");
+    assert_eq!(ago(42.9), "42 s ago");
+    assert_eq!(ago(150.0), "2 min ago");
+    assert_eq!(ago(7200.0), "2 h ago");
+    assert_eq!(ago(90000.0), "1 day ago");
+    assert_eq!(ago(3.0 * 86400.0), "3 days ago");
+}
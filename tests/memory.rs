@@ -0,0 +1,55 @@
+//! The memory budget, with the counting allocator installed as the binaries do
+
+use eframe_template::{headless::Headless, CountingAllocator, RuntimePolicy};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn headless(code: &str) -> Headless {
+    let policy = RuntimePolicy {
+        max_memory_mb: 16,
+        ..RuntimePolicy::default()
+    };
+    Headless::new(code.to_owned(), policy, egui::vec2(640.0, 480.0))
+}
+
+#[test]
+fn runaway_scripts_are_stopped() {
+    let report = headless("x = [bytearray(1024 * 1024) for _ in range(1000)]").step(vec![]);
+    let notice = report.notice.expect("The runtime should have been reset");
+    assert!(notice.contains("memory budget"), "{notice}");
+}
+
+#[test]
+fn host_allocations_between_runs_dont_count() {
+    let mut headless = headless("print('ok')");
+    let mut host = vec![];
+    for _ in 0..4 {
+        let report = headless.step(vec![]);
+        assert_eq!(report.notice, None);
+        assert_eq!(report.error, None);
+        // More than the whole budget, every frame
+        host.push(vec![1u8; 32 * 1024 * 1024]);
+    }
+}
+
+#[test]
+fn memory_freed_during_a_run_is_available_again() {
+    let report = headless(
+        "for _ in range(20):\n    x = bytearray(8 * 1024 * 1024)\n    del x\nprint('done')",
+    )
+    .step(vec![]);
+    assert_eq!(report.notice, None);
+    assert_eq!(report.stdout, "done\n");
+}
+
+#[test]
+fn memory_kept_between_runs_counts() {
+    let mut headless = headless(
+        "try:\n    kept\nexcept NameError:\n    kept = []\nkept.append(bytearray(4 * 1024 * 1024))",
+    );
+    let notice = (0..10)
+        .find_map(|_| headless.step(vec![]).notice)
+        .expect("The runtime should have been reset");
+    assert!(notice.contains("memory budget"), "{notice}");
+}
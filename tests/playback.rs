@@ -0,0 +1,215 @@
+use eframe_template::{
+    headless::Headless,
+    playback::{InputRecording, Playback},
+    RuntimePolicy,
+};
+use egui::{Event, Key, Modifiers, PointerButton, Pos2, RawInput, Rect};
+
+fn input(events: Vec<Event>) -> RawInput {
+    RawInput {
+        events,
+        ..Default::default()
+    }
+}
+
+fn everywhere(_: Pos2) -> bool {
+    true
+}
+
+fn click(pos: Pos2, pressed: bool) -> Event {
+    Event::PointerButton {
+        pos,
+        button: PointerButton::Primary,
+        pressed,
+        modifiers: Modifiers::NONE,
+    }
+}
+
+fn escape() -> Event {
+    Event::Key {
+        key: Key::Escape,
+        physical_key: None,
+        pressed: true,
+        repeat: false,
+        modifiers: Modifiers::NONE,
+    }
+}
+
+fn record(frames: Vec<Vec<Event>>) -> InputRecording {
+    let mut playback = Playback::default();
+    playback.start_recording(egui::vec2(100.0, 100.0));
+    for events in frames {
+        playback.process_input(&mut input(events), Pos2::ZERO, everywhere, true);
+    }
+    playback.stop().expect("Was recording")
+}
+
+#[test]
+fn replay_matches_recording() {
+    let frames = vec![
+        vec![Event::PointerMoved(Pos2::new(1.0, 2.0))],
+        vec![],
+        vec![Event::Text("a".to_owned()), Event::Text("b".to_owned())],
+    ];
+    let recording = record(frames.clone());
+    assert_eq!(recording.frames, 3);
+
+    let mut playback = Playback::default();
+    playback.start_replay(recording);
+    for expected in frames {
+        // Real input is ignored while replaying
+        let mut raw_input = input(vec![Event::Text("real".to_owned())]);
+        playback.process_input(&mut raw_input, Pos2::ZERO, everywhere, true);
+        assert_eq!(raw_input.events, expected);
+    }
+    assert!(playback.is_idle());
+}
+
+#[test]
+fn escape_stops_replay() {
+    let recording = record(vec![vec![], vec![], vec![]]);
+    let mut playback = Playback::default();
+    playback.start_replay(recording);
+    playback.process_input(&mut input(vec![escape()]), Pos2::ZERO, everywhere, true);
+    assert!(playback.is_idle());
+}
+
+#[test]
+fn json_roundtrip() {
+    let recording = record(vec![
+        vec![Event::PointerMoved(Pos2::new(3.0, 4.0))],
+        vec![escape()],
+    ]);
+    let json = recording.to_json();
+    assert_eq!(InputRecording::from_json(&json).unwrap(), recording);
+}
+
+#[test]
+fn host_input_is_not_recorded() {
+    let over_script = |pos: Pos2| pos.y > 50.0;
+    let (inside, outside) = (Pos2::new(10.0, 60.0), Pos2::new(10.0, 10.0));
+    let frames = [
+        vec![Event::PointerMoved(inside), click(inside, true)],
+        // Dragging out of the script still belongs to it
+        vec![Event::PointerMoved(outside), click(outside, false)],
+        // Like clicking "Stop recording" in the menu
+        vec![click(outside, true), click(outside, false)],
+        vec![Event::Text("typed into the editor".to_owned())],
+    ];
+
+    let mut playback = Playback::default();
+    playback.start_recording(egui::vec2(100.0, 100.0));
+    for (i, events) in frames.into_iter().enumerate() {
+        let keyboard_to_script = i < 3;
+        playback.process_input(
+            &mut input(events),
+            Pos2::ZERO,
+            over_script,
+            keyboard_to_script,
+        );
+    }
+    let recording = playback.stop().expect("Was recording");
+
+    let events: Vec<_> = recording
+        .events
+        .iter()
+        .map(|scripted| (scripted.frame, scripted.event.clone()))
+        .collect();
+    assert_eq!(
+        events,
+        [
+            (0, Event::PointerMoved(inside)),
+            (0, click(inside, true)),
+            (1, Event::PointerMoved(outside)),
+            (1, click(outside, false)),
+        ]
+    );
+    assert_eq!(recording.frames, 4);
+}
+
+#[test]
+fn frame_timing_is_replayed() {
+    let mut playback = Playback::default();
+    playback.start_recording(egui::vec2(100.0, 100.0));
+    for (time, predicted_dt) in [(10.0, 0.1), (10.25, 0.25)] {
+        let mut raw_input = RawInput {
+            time: Some(time),
+            predicted_dt,
+            screen_rect: Some(Rect::from_min_size(Pos2::ZERO, egui::vec2(100.0, 100.0))),
+            ..Default::default()
+        };
+        playback.process_input(&mut raw_input, Pos2::ZERO, everywhere, true);
+    }
+    let recording = playback.stop().expect("Was recording");
+
+    // The window keeps its size
+    let screen_rect = Rect::from_min_size(Pos2::ZERO, egui::vec2(900.0, 700.0));
+    playback.start_replay(recording);
+    let mut replayed = vec![];
+    for time in [50.0, 50.01] {
+        let mut raw_input = RawInput {
+            time: Some(time),
+            screen_rect: Some(screen_rect),
+            ..Default::default()
+        };
+        playback.process_input(&mut raw_input, Pos2::ZERO, everywhere, true);
+        replayed.push((
+            raw_input.time,
+            raw_input.predicted_dt,
+            raw_input.screen_rect,
+        ));
+    }
+    assert_eq!(
+        replayed,
+        [
+            (Some(50.0), 0.1, Some(screen_rect)),
+            (Some(50.25), 0.25, Some(screen_rect)),
+        ]
+    );
+}
+
+#[test]
+fn pointer_positions_follow_the_script_area() {
+    let mut playback = Playback::default();
+    playback.start_recording(egui::vec2(100.0, 100.0));
+    let events = vec![click(Pos2::new(25.0, 35.0), true)];
+    playback.process_input(&mut input(events), Pos2::new(20.0, 30.0), everywhere, true);
+    let recording = playback.stop().expect("Was recording");
+    assert_eq!(recording.events[0].event, click(Pos2::new(5.0, 5.0), true));
+
+    // Replayed where the script is now
+    playback.start_replay(recording.clone());
+    let mut raw_input = input(vec![]);
+    playback.process_input(&mut raw_input, Pos2::new(200.0, 100.0), everywhere, true);
+    assert_eq!(raw_input.events, [click(Pos2::new(205.0, 105.0), true)]);
+
+    let events = recording.events_at(Pos2::new(8.0, 8.0));
+    assert_eq!(events[0].event, click(Pos2::new(13.0, 13.0), true));
+}
+
+#[test]
+fn recordings_replay_headless() {
+    // Clicking the script's first button in the app, where the script starts at (300, 40)
+    let origin = Pos2::new(300.0, 40.0);
+    let button = origin + egui::vec2(10.0, 8.0);
+    let mut playback = Playback::default();
+    playback.start_recording(egui::vec2(400.0, 300.0));
+    for events in [
+        vec![Event::PointerMoved(button)],
+        vec![click(button, true)],
+        vec![click(button, false)],
+        vec![],
+    ] {
+        playback.process_input(&mut input(events), origin, everywhere, true);
+    }
+    let recording = playback.stop().expect("Was recording");
+
+    let code = "if egui.button('Go').clicked():\n    print('clicked')".to_owned();
+    let mut headless = Headless::new(code, RuntimePolicy::default(), egui::vec2(800.0, 600.0));
+    headless.fit_script_area(recording.script_size);
+    assert_eq!(headless.script_area().size(), recording.script_size);
+    let events = recording.events_at(headless.script_area().min);
+    let reports = headless.run(recording.frames, &events);
+    assert!(reports.iter().all(|report| report.error.is_none()));
+    assert!(reports.iter().any(|report| report.stdout == "clicked\n"));
+}
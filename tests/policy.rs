@@ -0,0 +1,138 @@
+//! The sandbox policy, as scripts run into it
+
+use eframe_template::{
+    headless::{FrameReport, Headless},
+    RuntimePolicy,
+};
+
+fn run(code: &str, policy: RuntimePolicy) -> FrameReport {
+    Headless::new(code.to_owned(), policy, egui::vec2(640.0, 480.0)).step(vec![])
+}
+
+fn assert_blocked(code: &str, policy: RuntimePolicy) {
+    let error = run(code, policy)
+        .error
+        .unwrap_or_else(|| panic!("{code:?} should have been blocked"));
+    assert!(error.contains("PermissionError"), "{code:?}: {error}");
+}
+
+#[test]
+fn system_modules_are_blocked() {
+    for code in [
+        "import posix",
+        "import _io",
+        "import os",
+        "__import__('os.path')",
+    ] {
+        assert_blocked(code, RuntimePolicy::default());
+    }
+}
+
+#[test]
+fn the_import_machinery_is_blocked() {
+    for code in [
+        "__import__('_frozen_importlib_external')._os",
+        "import _frozen_importlib",
+        "import _imp",
+    ] {
+        assert_blocked(code, RuntimePolicy::default());
+    }
+
+    // Even when a project allows them
+    let policy = RuntimePolicy {
+        allowed_modules: vec!["_frozen_importlib_external".to_owned(), "_imp".to_owned()],
+        ..RuntimePolicy::default()
+    };
+    assert_blocked("import _frozen_importlib_external", policy);
+}
+
+#[test]
+fn allowed_modules_import() {
+    let report = run(
+        "import math\nprint(math.floor(2.5))",
+        RuntimePolicy::default(),
+    );
+    assert_eq!(report.error, None);
+    assert_eq!(report.stdout, "2\n");
+
+    let policy = RuntimePolicy {
+        allowed_modules: vec!["posix".to_owned()],
+        ..RuntimePolicy::default()
+    };
+    assert_eq!(run("import posix", policy).error, None);
+}
+
+#[test]
+fn invalid_policies_fall_back_to_the_default() {
+    let policy = RuntimePolicy {
+        max_recursion: 1,
+        ..RuntimePolicy::default()
+    };
+    let mut headless = Headless::new("print('ran')".to_owned(), policy, egui::vec2(640.0, 480.0));
+    for _ in 0..2 {
+        let report = headless.step(vec![]);
+        assert_eq!(report.error, None);
+        assert_eq!(report.stdout, "ran\n");
+        let notice = report.notice.expect("The fallback should be explained");
+        assert!(
+            notice.starts_with("Could not apply the project's sandbox policy"),
+            "{notice}"
+        );
+    }
+}
+
+#[test]
+fn files_cannot_be_opened_around_open() {
+    for code in [
+        "import sys\ntype(sys.stderr.buffer.raw)('/etc/passwd')",
+        "import sys\nraw = sys.stderr.buffer.raw\ntype(raw).__init__(raw, '/etc/passwd')",
+        "import sys\ntype(sys.stderr.buffer.raw)(7)",
+        "import sys\ntype(sys.stderr.buffer.raw)('/etc/passwd', opener=lambda p, f: 2)",
+        r#"
+def walk(cls):
+    for sub in type.__subclasses__(cls):
+        yield sub
+        yield from walk(sub)
+
+file_io = next(cls for cls in walk(object) if repr(cls).endswith(".FileIO'>"))
+file_io('/etc/passwd')
+"#,
+    ] {
+        assert_blocked(code, RuntimePolicy::default());
+    }
+}
+
+#[test]
+fn os_modules_cannot_be_reached_through_the_import_machinery() {
+    let code = r#"
+import sys
+importer = sys.meta_path[0]
+external = importer.find_spec.__globals__['_bootstrap_external']
+if external is not None:
+    external._os.listdir('/')
+importer.find_spec.__globals__['_imp'].create_builtin(importer.find_spec('posix'))
+"#;
+    assert_blocked(code, RuntimePolicy::default());
+}
+
+#[test]
+fn file_roots_allow_files() {
+    let root = std::env::temp_dir().join(format!("policy-root-{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    let path = root.join("data.txt");
+    std::fs::write(&path, "hello").unwrap();
+
+    let policy = RuntimePolicy {
+        file_roots: vec![root.display().to_string()],
+        ..RuntimePolicy::default()
+    };
+    let path = path.display().to_string();
+    let code = format!(
+        "import sys\nprint(open({path:?}).read())\n\
+         print(type(sys.stderr.buffer.raw)({path:?}).read())"
+    );
+    let report = run(&code, policy);
+    std::fs::remove_dir_all(&root).unwrap();
+    assert_eq!(report.error, None);
+    assert_eq!(report.stdout, "hello\nb'hello'\n");
+}
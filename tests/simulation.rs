@@ -0,0 +1,63 @@
+use eframe_template::simulation::Simulation;
+
+#[test]
+fn steps_at_fixed_rate() {
+    // Powers of two, so no step is lost to rounding
+    let mut sim = Simulation::default();
+    sim.rate = 64.0;
+    // Three frames at 32 Hz are six steps at 64 Hz
+    let steps: usize = (0..3).map(|_| sim.advance(1.0 / 32.0)).sum();
+    assert_eq!(steps, 6);
+    assert_eq!(sim.steps_taken(), 6);
+
+    // Three frames at 40 Hz are four and a half steps at 60 Hz, and the half carries over
+    let mut sim = Simulation::default();
+    let steps: usize = (0..3).map(|_| sim.advance(1.0 / 40.0)).sum();
+    assert_eq!(steps, 4);
+}
+
+#[test]
+fn remainder_carries_over() {
+    let mut sim = Simulation::default();
+    assert_eq!(sim.advance(0.75 / 60.0), 0);
+    assert_eq!(sim.advance(0.75 / 60.0), 1);
+}
+
+#[test]
+fn speed_multiplier() {
+    let mut sim = Simulation::default();
+    sim.speed = 2.0;
+    assert_eq!(sim.advance(0.5), 60);
+}
+
+#[test]
+fn paused_only_takes_queued_steps() {
+    let mut sim = Simulation::default();
+    sim.paused = true;
+    assert!(!sim.is_running());
+    assert_eq!(sim.advance(1.0), 0);
+
+    sim.queue(5);
+    assert!(sim.is_running());
+    assert_eq!(sim.advance(1.0), 5);
+    assert_eq!(sim.advance(1.0), 0);
+}
+
+#[test]
+fn long_frames_are_capped() {
+    let mut sim = Simulation::default();
+    let steps = sim.advance(3600.0);
+    assert!(steps < 1000, "{steps}");
+    // The backlog is dropped rather than caught up on later
+    assert_eq!(sim.advance(0.0), 0);
+}
+
+#[test]
+fn large_batches_run_over_several_frames() {
+    let mut sim = Simulation::default();
+    sim.paused = true;
+    sim.queue(1000);
+    let steps: Vec<usize> = (0..6).map(|_| sim.advance(1.0)).collect();
+    assert_eq!(steps, [240, 240, 240, 240, 40, 0]);
+    assert_eq!(sim.steps_taken(), 1000);
+}
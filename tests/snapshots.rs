@@ -0,0 +1,51 @@
+//! Golden-image tests for what scripts draw.
+//!
+//! Run with `UPDATE_SNAPSHOTS=1` to accept intentional changes.
+
+use std::path::PathBuf;
+
+use eframe_template::{headless::Headless, snapshot::assert_snapshot, RuntimePolicy};
+
+fn snapshot_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots")
+}
+
+/// Run `code` for a couple of frames, so layout has settled, and snapshot the last one
+fn check_snapshot(name: &str, code: &str) {
+    let mut headless = Headless::new(
+        code.to_owned(),
+        RuntimePolicy::default(),
+        egui::vec2(320.0, 240.0),
+    );
+    let reports = headless.run(2, &[]);
+    let last = reports.last().expect("No frames were run");
+    assert_eq!(last.error, None, "Script failed");
+
+    let image = headless.render(&last.output);
+    assert_snapshot(&snapshot_dir(), name, &image);
+}
+
+#[test]
+fn painter_shapes() {
+    check_snapshot(
+        "painter_shapes",
+        r#"
+resp, paint = egui.allocate_painter([200.0, 150.0], "click")
+(x, y) = resp.rect().min()
+paint.line([[x, y], [x + 200, y + 150]], 2.0, [255, 0, 0, 255])
+paint.circle([x + 100, y + 75], 30.0, [0, 0, 255, 255], 1.0, [255, 255, 255, 255])
+paint.text([x + 100, y + 140], "CENTER_BOTTOM", "painter", [255, 255, 255, 255])
+"#,
+    );
+}
+
+#[test]
+fn widgets() {
+    check_snapshot(
+        "widgets",
+        r#"
+egui.button("Click me")
+egui.text_edit_singleline("some text")
+"#,
+    );
+}